@@ -0,0 +1,46 @@
+//! Times rendering the `index` and `lists` pages against a database on disk,
+//! with the in-memory repository as a floor for the rendering itself.
+//!
+//! Run with `cargo bench --bench render`. The queries behind these pages are
+//! timed on their own in `task-stack-core/benches/queries.rs`.
+
+#[path = "../../task-stack-core/benches/common/mod.rs"]
+mod common;
+
+use common::{seed, time, BenchDir};
+use std::sync::Arc;
+use task_stack_core::repository::{InMemoryRepository, LibsqlRepository, TaskRepository};
+use task_stack_core::TaskStack;
+use task_stack_lib::ui::pages;
+
+async fn bench(label: &str, stack: &TaskStack) {
+    time(&format!("{label} index"), "render", || {
+        pages::index::render(stack)
+    })
+    .await;
+    time(&format!("{label} lists"), "render", || {
+        pages::lists::render(stack)
+    })
+    .await;
+}
+
+async fn seeded(repo: Arc<dyn TaskRepository>) -> TaskStack {
+    let stack = TaskStack::load(repo).await.expect("Stack to load");
+    seed(&stack).await;
+    stack
+}
+
+fn main() {
+    let dir = BenchDir::new();
+
+    tauri::async_runtime::block_on(async {
+        let repo = LibsqlRepository::open(dir.join("tasks.db"), None)
+            .await
+            .expect("Database to open");
+        let stack = seeded(Arc::new(repo)).await;
+        bench("libsql", &stack).await;
+
+        let stack = seeded(Arc::new(InMemoryRepository::new())).await;
+        bench("in-memory", &stack).await;
+    });
+}
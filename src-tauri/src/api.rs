@@ -0,0 +1,361 @@
+//! The local HTTP API, served on localhost for scripts and integrations when
+//! it's turned on. Every request needs the bearer token kept in `api.json`.
+
+use crate::rpc;
+use crate::tasks::TaskStack;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tauri::{AppHandle, Manager, Runtime};
+use tiny_http::{Header, Method, Request, Response, Server};
+use ulid::Ulid;
+
+/// Where the API settings are kept, relative to the app data directory. They
+/// apply to every profile, so they live outside the database.
+pub const API_FILE: &str = "api.json";
+pub const DEFAULT_PORT: u16 = 7411;
+/// Bodies are a task or an order of ids, so anything larger is refused.
+pub const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            token: new_token(),
+        }
+    }
+}
+
+impl ApiConfig {
+    /// Reads the API settings, starting switched off with a fresh token if
+    /// there are none or they can't be read.
+    pub fn load(app_data_dir: &Path) -> Self {
+        match fs::read_to_string(app_data_dir.join(API_FILE)) {
+            Ok(json) => serde_json::from_str(&json)
+                .inspect_err(|e| eprintln!("Failed to read API settings: {}", e))
+                .unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Writes the settings readable by the user alone, as the token opens every task.
+    pub fn save(&self, app_data_dir: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options
+            .open(app_data_dir.join(API_FILE))
+            .map_err(|e| e.to_string())?;
+        // The mode only applies to new files, so ones saved before are narrowed first
+        #[cfg(unix)]
+        file.set_permissions(fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+        file.write_all(json.as_bytes()).map_err(|e| e.to_string())
+    }
+}
+
+/// 32 random bytes as hex.
+pub fn new_token() -> String {
+    rand::random::<[u8; 32]>()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+struct Running {
+    server: Arc<Server>,
+    thread: JoinHandle<()>,
+    port: u16,
+}
+
+/// The server, managed so it can be restarted when its settings change.
+#[derive(Default)]
+pub struct ApiServer {
+    running: Mutex<Option<Running>>,
+    error: Mutex<Option<String>>,
+}
+
+impl ApiServer {
+    /// Stops the server, then starts it again on `config` if it's enabled.
+    pub fn apply(&self, app: &AppHandle, config: &ApiConfig) {
+        self.stop();
+        *self.error.lock().unwrap() = None;
+        if !config.enabled {
+            return;
+        }
+        let server = match Server::http(("127.0.0.1", config.port)) {
+            Ok(server) => Arc::new(server),
+            Err(e) => {
+                eprintln!("Failed to start the API on port {}: {}", config.port, e);
+                *self.error.lock().unwrap() = Some(e.to_string());
+                return;
+            }
+        };
+        let thread = {
+            let server = server.clone();
+            let app = app.clone();
+            let token = config.token.clone();
+            std::thread::spawn(move || {
+                for mut request in server.incoming_requests() {
+                    let response = respond(&app, &token, &mut request);
+                    let _ = request
+                        .respond(response)
+                        .inspect_err(|e| eprintln!("Failed to answer an API request: {}", e));
+                }
+            })
+        };
+        *self.running.lock().unwrap() = Some(Running {
+            server,
+            thread,
+            port: config.port,
+        });
+    }
+
+    pub fn stop(&self) {
+        if let Some(running) = self.running.lock().unwrap().take() {
+            running.server.unblock();
+            let _ = running.thread.join();
+        }
+    }
+
+    /// The port being served, nothing when switched off, or why it couldn't start.
+    pub fn status(&self) -> Result<Option<u16>, String> {
+        if let Some(error) = self.error.lock().unwrap().clone() {
+            return Err(error);
+        }
+        Ok(self.running.lock().unwrap().as_ref().map(|r| r.port))
+    }
+}
+
+/// A response other than 200, with the message sent as `{ "error": ... }`.
+/// Errors from the stack are 400; what wasn't found is looked up first to be a 404.
+struct Failure(u16, String);
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure(400, message)
+    }
+}
+
+fn json_response(status: u16, body: &Value) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(
+            Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                .expect("Header to be valid"),
+        )
+}
+
+/// Compares every byte so the time taken doesn't hint at how much matched.
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Answers one request, as the server does for each it's sent.
+pub fn respond<R: Runtime>(
+    app: &AppHandle<R>,
+    token: &str,
+    request: &mut Request,
+) -> Response<Cursor<Vec<u8>>> {
+    let authorized = request.headers().iter().any(|header| {
+        header.field.equiv("Authorization")
+            && header
+                .value
+                .as_str()
+                .strip_prefix("Bearer ")
+                .is_some_and(|given| same_token(given.trim(), token))
+    });
+    if !authorized {
+        return json_response(401, &json!({ "error": "Missing or wrong token" }));
+    }
+    // Nothing is managed until a database opens; a closed stack refuses requests itself
+    let Some(stack) = app.try_state::<TaskStack>() else {
+        return json_response(503, &json!({ "error": "The database isn't open" }));
+    };
+
+    let mut body = Vec::new();
+    if let Err(e) = request
+        .as_reader()
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_end(&mut body)
+    {
+        return json_response(400, &json!({ "error": e.to_string() }));
+    }
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return json_response(413, &json!({ "error": "The body is too large" }));
+    }
+    let Ok(body) = String::from_utf8(body) else {
+        return json_response(400, &json!({ "error": "The body isn't UTF-8" }));
+    };
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+    match tauri::async_runtime::block_on(route(&stack, &method, path, query, &body)) {
+        Ok(value) => json_response(200, &value),
+        Err(Failure(status, message)) => json_response(status, &json!({ "error": message })),
+    }
+}
+
+/// Decodes `%XX` escapes and `+` in a query value.
+fn decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.bytes();
+    while let Some(byte) = rest.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = rest.by_ref().take(2).collect();
+                match std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(decoded) => bytes.push(decoded),
+                    None => {
+                        bytes.push(b'%');
+                        bytes.extend(hex);
+                    }
+                }
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn query_value(query: &str, key: &str) -> Option<String> {
+    query
+        .split('&')
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| decode(value))
+}
+
+/// The stack for the `list` query parameter, by id or name, or the current list.
+async fn stack_for(stack: &TaskStack, list: Option<String>) -> Result<TaskStack, Failure> {
+    match list {
+        Some(list) => match stack.lookup_list(&list).await? {
+            Some(list_id) => Ok(stack.for_list(list_id)),
+            None => Err(Failure(404, format!("No list named {list}"))),
+        },
+        None => Ok(stack.for_list(stack.get_list_id())),
+    }
+}
+
+/// The id of a task in `stack`'s list, so nothing outside the `list` scope is touched.
+async fn task_id(stack: &TaskStack, id: &str) -> Result<Ulid, Failure> {
+    let id = Ulid::from_string(id).map_err(|e| Failure(400, format!("{id}: {e}")))?;
+    match stack.lookup_task(&id).await? {
+        Some(_) => Ok(id),
+        None => Err(Failure(404, format!("No task {id} in this list"))),
+    }
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &str) -> Result<T, Failure> {
+    serde_json::from_str(body).map_err(|e| Failure(400, format!("Invalid body: {e}")))
+}
+
+#[derive(Deserialize)]
+struct NewTask {
+    title: String,
+    description: Option<String>,
+    list: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Move {
+    position: usize,
+}
+
+#[derive(Deserialize)]
+struct Order {
+    ids: Vec<Ulid>,
+}
+
+async fn route(
+    stack: &TaskStack,
+    method: &Method,
+    path: &str,
+    query: &str,
+    body: &str,
+) -> Result<Value, Failure> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let list = query_value(query, "list");
+
+    match (method, segments.as_slice()) {
+        (Method::Get, ["lists"]) => Ok(rpc::list_summaries(stack).await?),
+        (Method::Get, ["tasks"]) => {
+            let stack = stack_for(stack, list).await?;
+            let tasks = if query_value(query, "all").is_some_and(|all| all != "false") {
+                stack.get_tasks().await?
+            } else {
+                stack.get_current_tasks().await?
+            };
+            Ok(json!(tasks))
+        }
+        (Method::Get, ["tasks", "current"]) => {
+            let stack = stack_for(stack, list).await?;
+            match stack.first_active().await? {
+                Some(task) => Ok(json!(task)),
+                None => Err(Failure(404, "The stack is empty".to_string())),
+            }
+        }
+        (Method::Post, ["tasks"]) => {
+            let new: NewTask = parse_body(body)?;
+            if new.title.trim().is_empty() {
+                return Err(Failure(400, "A task needs a title".to_string()));
+            }
+            let stack = stack_for(stack, new.list.or(list)).await?;
+            let task = stack
+                .push(new.title.trim().to_string(), new.description)
+                .await?;
+            Ok(json!(task))
+        }
+        (Method::Put, ["tasks", "order"]) => {
+            let order: Order = parse_body(body)?;
+            let stack = stack_for(stack, list).await?;
+            stack.reorder(&order.ids).await?;
+            Ok(json!(stack.get_current_tasks().await?))
+        }
+        (Method::Post, ["tasks", id, "complete"]) => {
+            let stack = stack_for(stack, list).await?;
+            let task = stack.complete_task(task_id(&stack, id).await?).await?;
+            Ok(json!(task))
+        }
+        (Method::Post, ["tasks", id, "defer"]) => {
+            let stack = stack_for(stack, list).await?;
+            let id = task_id(&stack, id).await?;
+            stack.move_to_end(id).await?;
+            Ok(json!(stack.find_task(&id).await?))
+        }
+        (Method::Post, ["tasks", id, "move"]) => {
+            let to: Move = parse_body(body)?;
+            let stack = stack_for(stack, list).await?;
+            let id = task_id(&stack, id).await?;
+            stack.move_task(id, to.position).await?;
+            Ok(json!(stack.find_task(&id).await?))
+        }
+        _ => Err(Failure(404, format!("No endpoint {method} {path}"))),
+    }
+}
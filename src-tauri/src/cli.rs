@@ -0,0 +1,435 @@
+//! The `task-stack` binary, working on the same database as the app. The app
+//! itself is built as `task-stack-app`.
+
+use crate::database;
+use crate::profiles::Profiles;
+use crate::repository::LibsqlRepository;
+use crate::rpc::{self, Client, RpcError};
+use crate::status::{self, Status};
+use crate::tasks::{Task, TaskStack, TaskState};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use ulid::Ulid;
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_ERROR: i32 = 1;
+/// There was no active task to show, complete or defer.
+pub const EXIT_EMPTY: i32 = 2;
+pub const EXIT_USAGE: i32 = 64;
+
+/// Where the app keeps its data, under the platform's data directory.
+const APP_IDENTIFIER: &str = "net.thekwoka.taskstack";
+/// Read for the passphrase of an encrypted database.
+const PASSPHRASE_VAR: &str = "TASK_STACK_PASSPHRASE";
+/// Titles longer than this are cut short by `status`.
+const STATUS_WIDTH: usize = 40;
+/// Shorter still in a prompt or i3blocks' short text.
+const STATUS_SHORT_WIDTH: usize = 24;
+
+const USAGE: &str = "Usage: task-stack [options] <command> [args]
+
+Commands:
+  push <title>        Add a task to the end of the stack
+  next                Show the task at the top of the stack
+  done [id]           Complete the top task, or the task with this id
+  defer [id]          Move the top task, or the task with this id, to the end
+  ls                  Show the active and recently completed tasks
+  lists               Show the lists
+  switch <list>       Make a list, by name or id, the current one
+  export              Write tasks out, to stdout unless --output is given
+  status              One line about the top task for status bars and prompts,
+                      read without writing so it's safe to poll
+
+Options:
+  --json              Print JSON instead of text
+  --list <list>       Work on this list instead of the current one
+  --profile <name>    Use this profile's database
+  --db <path>         Use the database at this path
+  -d, --description <text>
+                      Description for push
+  --all               With ls, show every task; with export as json or csv,
+                      every list
+  --format <format>   With export: json (default), markdown, todotxt, ics or csv
+                      With status: plain (default), waybar, polybar, i3blocks
+                      or prompt
+  -o, --output <path> With export, write to this file
+
+Commands go through Task Stack when it's running, so its window updates right
+away; otherwise, or with --db or --profile, the database is opened directly.
+
+Exit codes: 0 success, 1 error, 2 the stack is empty, 64 bad usage.
+Set TASK_STACK_PASSPHRASE to open an encrypted database.";
+
+enum Error {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for Error {
+    fn from(error: String) -> Self {
+        Error::Failed(error)
+    }
+}
+
+impl From<RpcError> for Error {
+    fn from(error: RpcError) -> Self {
+        match error.code {
+            rpc::INVALID_PARAMS | rpc::METHOD_NOT_FOUND => Error::Usage(error.message),
+            _ => Error::Failed(error.message),
+        }
+    }
+}
+
+/// How a command finished when it didn't fail.
+enum Outcome {
+    Done,
+    Empty,
+}
+
+#[derive(Default)]
+struct Options {
+    json: bool,
+    all: bool,
+    db: Option<PathBuf>,
+    profile: Option<String>,
+    list: Option<String>,
+    description: Option<String>,
+    format: Option<String>,
+    output: Option<PathBuf>,
+    command: Option<String>,
+    args: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self, Error> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| Error::Usage(format!("{name} needs a value")))
+            };
+            match arg.as_str() {
+                "--json" => options.json = true,
+                "--all" => options.all = true,
+                "--db" => options.db = Some(PathBuf::from(value(&arg)?)),
+                "--profile" => options.profile = Some(value(&arg)?),
+                "--list" => options.list = Some(value(&arg)?),
+                "-d" | "--description" => options.description = Some(value(&arg)?),
+                "--format" => options.format = Some(value(&arg)?),
+                "-o" | "--output" => options.output = Some(PathBuf::from(value(&arg)?)),
+                "-h" | "--help" => options.command = Some("help".to_string()),
+                flag if flag.starts_with('-') && flag.len() > 1 => {
+                    return Err(Error::Usage(format!("Unknown option {flag}")));
+                }
+                _ if options.command.is_none() => options.command = Some(arg),
+                _ => options.args.push(arg),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Prints `value` as JSON, or `text` otherwise.
+    fn print(&self, text: impl FnOnce() -> String, value: impl FnOnce() -> Value) {
+        if self.json {
+            println!("{}", value());
+        } else {
+            println!("{}", text());
+        }
+    }
+}
+
+/// Runs the command line `args`, without the program name, and returns the exit code.
+pub fn run(args: Vec<String>) -> i32 {
+    let result =
+        Options::parse(args).and_then(|options| tauri::async_runtime::block_on(execute(&options)));
+    match result {
+        Ok(Outcome::Done) => EXIT_OK,
+        Ok(Outcome::Empty) => EXIT_EMPTY,
+        Err(Error::Usage(message)) => {
+            eprintln!("{message}\n\n{USAGE}");
+            EXIT_USAGE
+        }
+        Err(Error::Failed(message)) => {
+            eprintln!("{message}");
+            EXIT_ERROR
+        }
+    }
+}
+
+fn app_data_dir() -> Result<PathBuf, Error> {
+    Ok(dirs::data_dir()
+        .ok_or_else(|| "No data directory on this system; pass --db".to_string())?
+        .join(APP_IDENTIFIER))
+}
+
+/// The running app, unless the options point at a database of their own.
+fn connect(options: &Options) -> Option<Client> {
+    if options.db.is_some() || options.profile.is_some() {
+        return None;
+    }
+    Client::connect(&app_data_dir().ok()?)
+}
+
+/// The database the options point at: `--db`, `--profile`, or the current profile.
+fn database_path(options: &Options) -> Result<PathBuf, Error> {
+    if let Some(path) = &options.db {
+        return Ok(path.clone());
+    }
+    let app_data_dir = app_data_dir()?;
+    let profiles = Profiles::load(&app_data_dir);
+    match &options.profile {
+        Some(name) => profiles
+            .get(name)
+            .map(|profile| profile.path.clone())
+            .ok_or_else(|| Error::Failed(format!("No profile named {name}"))),
+        None => Ok(profiles.current().path.clone()),
+    }
+}
+
+fn existing_database(options: &Options) -> Result<PathBuf, Error> {
+    let path = database_path(options)?;
+    if !path.exists() {
+        return Err(Error::Failed(format!(
+            "No database at {}; open Task Stack once to create it",
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+/// The passphrase from the environment, when the database needs one.
+fn passphrase(path: &Path) -> Result<Option<String>, Error> {
+    if !database::is_encrypted(path) {
+        return Ok(None);
+    }
+    std::env::var(PASSPHRASE_VAR)
+        .map(Some)
+        .map_err(|_| Error::Failed(format!("The database is encrypted; set {PASSPHRASE_VAR}")))
+}
+
+/// Opens the database without checking or repairing it, which is left to the app.
+async fn open(options: &Options) -> Result<TaskStack, Error> {
+    let path = existing_database(options)?;
+    let passphrase = passphrase(&path)?;
+    let repo = LibsqlRepository::open(path, passphrase)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(TaskStack::load(Arc::new(repo)).await?)
+}
+
+fn task_line(task: &Task) -> String {
+    let mark = if task.state == TaskState::Completed {
+        'x'
+    } else {
+        ' '
+    };
+    format!("{}  [{mark}] {}", task.id, task.title)
+}
+
+fn shorten(title: &str, width: usize) -> String {
+    if title.chars().count() <= width {
+        return title.to_string();
+    }
+    let mut short: String = title.chars().take(width - 1).collect();
+    short.push('…');
+    short
+}
+
+/// `status` in the shape `format` asks for. Nothing is shown when the stack
+/// is empty, apart from the plain and waybar formats.
+fn status_output(status: &Status, format: &str) -> Result<String, Error> {
+    let title = |width| status.task.as_ref().map(|task| shorten(&task.title, width));
+    Ok(match format {
+        "plain" => match title(STATUS_WIDTH) {
+            Some(title) => format!("{title} ({} left in {})", status.active, status.list),
+            None => format!("Nothing left in {}", status.list),
+        },
+        "waybar" => {
+            let mut tooltip = format!(
+                "{}: {} of {} left",
+                status.list, status.active, status.total
+            );
+            if let Some(task) = &status.task {
+                tooltip.push_str(&format!("\n\n{}", task.title));
+                if let Some(description) = &task.description {
+                    tooltip.push_str(&format!("\n{description}"));
+                }
+            }
+            let done = status.total - status.active;
+            json!({
+                "text": title(STATUS_WIDTH).unwrap_or_default(),
+                "alt": status.list,
+                "tooltip": tooltip,
+                "class": if status.task.is_some() { "active" } else { "empty" },
+                "percentage": (done * 100).checked_div(status.total).unwrap_or(100),
+            })
+            .to_string()
+        }
+        "polybar" => title(STATUS_WIDTH)
+            .map(|title| format!("{title} ({})", status.active))
+            .unwrap_or_default(),
+        // Full text then short text, one per line
+        "i3blocks" => match (title(STATUS_WIDTH), title(STATUS_SHORT_WIDTH)) {
+            (Some(title), Some(short)) => format!("{title} ({})\n{short}", status.active),
+            _ => String::new(),
+        },
+        "prompt" => title(STATUS_SHORT_WIDTH)
+            .map(|title| format!("[{}] {title}", status.active))
+            .unwrap_or_default(),
+        format => return Err(Error::Usage(format!("Unknown status format {format}"))),
+    })
+}
+
+/// Reads straight from the file, never through the app, so status bars
+/// polling it don't wake the app up.
+async fn show_status(options: &Options) -> Result<Outcome, Error> {
+    let path = existing_database(options)?;
+    let status = status::read(
+        &path,
+        passphrase(&path)?.as_deref(),
+        options.list.as_deref(),
+    )
+    .await?;
+    let output = status_output(&status, options.format.as_deref().unwrap_or("plain"))?;
+    options.print(|| output, || json!(status));
+    Ok(Outcome::Done)
+}
+
+/// The RPC parameters for `command`, checking its arguments.
+fn params(command: &str, options: &Options) -> Result<Value, Error> {
+    let mut params = json!({ "list": options.list });
+    match command {
+        "push" => {
+            let title = options.args.join(" ");
+            if title.trim().is_empty() {
+                return Err(Error::Usage("push needs a title".to_string()));
+            }
+            params["title"] = json!(title.trim());
+            params["description"] = json!(options.description);
+        }
+        "done" | "defer" => {
+            if let Some(id) = options.args.first() {
+                let id = Ulid::from_string(id).map_err(|e| Error::Usage(format!("{id}: {e}")))?;
+                params["id"] = json!(id);
+            }
+        }
+        "ls" => params["all"] = json!(options.all),
+        "switch" => {
+            let list = options.args.join(" ");
+            if list.is_empty() {
+                return Err(Error::Usage("switch needs a list name or id".to_string()));
+            }
+            params["list"] = json!(list);
+        }
+        "export" => {
+            params["format"] = json!(options.format);
+            params["all"] = json!(options.all);
+        }
+        "next" | "lists" => {}
+        command => return Err(Error::Usage(format!("Unknown command {command}"))),
+    }
+    Ok(params)
+}
+
+/// Runs `command` through the running app, or on the database when the app
+/// isn't running or hasn't opened it.
+async fn call(command: &str, params: Value, options: &Options) -> Result<Value, Error> {
+    if let Some(mut client) = connect(options) {
+        match client.call(command, params.clone()) {
+            Err(error) if error.code == rpc::NOT_OPEN => {}
+            result => return Ok(result?),
+        }
+    }
+    let stack = open(options).await?;
+    Ok(rpc::call(&stack, command, params).await?)
+}
+
+fn task(result: Value) -> Result<Task, Error> {
+    serde_json::from_value(result).map_err(|e| Error::Failed(e.to_string()))
+}
+
+async fn execute(options: &Options) -> Result<Outcome, Error> {
+    let command = options.command.as_deref().unwrap_or("help");
+    if command == "help" {
+        println!("{USAGE}");
+        return Ok(Outcome::Done);
+    }
+    if command == "status" {
+        return show_status(options).await;
+    }
+    let result = call(command, params(command, options)?, options).await?;
+
+    if result.is_null() && matches!(command, "next" | "done" | "defer") {
+        options.print(|| "The stack is empty".to_string(), || Value::Null);
+        return Ok(Outcome::Empty);
+    }
+    match command {
+        "push" => {
+            let task = task(result)?;
+            options.print(|| format!("Pushed {}", task_line(&task)), || json!(task));
+        }
+        "next" => {
+            let task = task(result)?;
+            options.print(
+                || match &task.description {
+                    Some(description) => format!("{}\n\n{description}", task_line(&task)),
+                    None => task_line(&task),
+                },
+                || json!(task),
+            );
+        }
+        "done" => {
+            let task = task(result)?;
+            options.print(|| format!("Completed {}", task_line(&task)), || json!(task));
+        }
+        "defer" => {
+            let task = task(result)?;
+            options.print(|| format!("Deferred {}", task_line(&task)), || json!(task));
+        }
+        "ls" => {
+            let tasks: Vec<Task> =
+                serde_json::from_value(result).map_err(|e| Error::Failed(e.to_string()))?;
+            options.print(
+                || tasks.iter().map(task_line).collect::<Vec<_>>().join("\n"),
+                || json!(tasks),
+            );
+        }
+        "lists" => {
+            let lists = result.as_array().cloned().unwrap_or_default();
+            options.print(
+                || {
+                    lists
+                        .iter()
+                        .map(|list| {
+                            let mark = if list["current"] == true { '*' } else { ' ' };
+                            format!(
+                                "{mark} {}  {} ({} active, {} total)",
+                                list["id"].as_str().unwrap_or_default(),
+                                list["name"].as_str().unwrap_or_default(),
+                                list["active"],
+                                list["total"]
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                },
+                || json!(lists),
+            );
+        }
+        "switch" => {
+            let list = options.args.join(" ");
+            options.print(|| format!("Switched to {list}"), || result);
+        }
+        "export" => {
+            let contents = result.as_str().unwrap_or_default();
+            match &options.output {
+                Some(path) => std::fs::write(path, contents).map_err(|e| e.to_string())?,
+                None => print!("{contents}"),
+            }
+        }
+        _ => unreachable!("commands are checked before they're called"),
+    }
+    Ok(Outcome::Done)
+}
@@ -1,12 +1,164 @@
+use crate::api::{self, ApiConfig, ApiServer};
+use crate::backup;
+use crate::database::{self, Unopened};
+use crate::deep_link;
+use crate::formats::{self, ImportMode};
+use crate::importers;
+use crate::integrity::Problem;
+use crate::profiles::Profiles;
+use crate::repository::LibsqlRepository;
+use crate::settings::{Settings, View};
 use crate::tasks::TaskStack;
 use crate::types::{DomUpdate, PageResponse};
 use crate::ui::pages;
+use chrono::NaiveDate;
 use html_node::{html, text};
-use tauri::State;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{path::BaseDirectory, AppHandle, Manager, State};
 use ulid::Ulid;
 
+fn backup_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(backup::BACKUP_DIR, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+fn unopened(app: &AppHandle) -> Option<Unopened> {
+    app.try_state::<Mutex<Option<Unopened>>>()
+        .and_then(|state| state.lock().unwrap().clone())
+}
+
+fn profile_list(app: &AppHandle) -> Profiles {
+    app.state::<Mutex<Profiles>>().lock().unwrap().clone()
+}
+
+/// Applies `change` to the profile list and saves it, returning the database
+/// file of the profile to open.
+fn update_profiles(
+    app: &AppHandle,
+    change: impl FnOnce(&mut Profiles, &Path) -> Result<PathBuf, String>,
+) -> Result<PathBuf, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let state = app.state::<Mutex<Profiles>>();
+    let mut profiles = state.lock().unwrap();
+    let path = change(&mut profiles, &app_data_dir)?;
+    profiles.save(&app_data_dir)?;
+    Ok(path)
+}
+
+fn broken_path(app: &AppHandle) -> Result<PathBuf, String> {
+    match unopened(app) {
+        Some(Unopened::Broken { path, .. }) => Ok(path),
+        _ => Err("There is no broken database to recover".to_string()),
+    }
+}
+
+/// Entry point for the frontend, which may find the database locked or broken.
+#[tauri::command]
+pub async fn start(app: AppHandle) -> Result<PageResponse, String> {
+    let pending = deep_link::take_pending(&app);
+    let response = start_page(&app).await?;
+    // Links opened before the window started show in place of the last view
+    Ok(deep_link::show_pending(pending, response))
+}
+
+/// The page to start on: the last view, or why the database isn't open.
+async fn start_page(app: &AppHandle) -> Result<PageResponse, String> {
+    // A profile that was just switched to may be locked, leaving the stack closed until it's unlocked
+    let page = match unopened(app) {
+        Some(Unopened::Locked(_)) => pages::unlock::render(None, &profile_list(app)),
+        Some(Unopened::Broken { path, error }) => {
+            pages::recovery::render(&path, &error, &backup_dir(app)?).await
+        }
+        None => {
+            let Some(stack) = app.try_state::<TaskStack>() else {
+                return Err("No database is open".to_string());
+            };
+            if crate::take_integrity_pending(app) {
+                let problems = stack.check_integrity().await?;
+                for problem in &problems {
+                    eprintln!("Integrity problem: {}", problem);
+                }
+                // Repairs rewrite rows, so they wait for the user to ask
+                if problems.iter().any(Problem::is_repairable) {
+                    return Ok(PageResponse::notify(
+                        &stack,
+                        DomUpdate::from(pages::integrity::render(&stack).await, "#app", "replace"),
+                        format!("Found {} problems in your tasks", problems.len()),
+                        "error",
+                    ));
+                }
+            }
+            return match stack.get_last_view().await {
+                View::Index => index(stack).await,
+                View::List => list(stack).await,
+                View::Lists => lists(stack).await,
+            };
+        }
+    };
+    Ok(PageResponse::new(DomUpdate::from(page, "#app", "replace")))
+}
+
+#[tauri::command]
+pub async fn unlock_database(app: AppHandle, passphrase: String) -> Result<PageResponse, String> {
+    let Some(Unopened::Locked(path)) = unopened(&app) else {
+        return start(app).await;
+    };
+    let opened = LibsqlRepository::open(path.clone(), Some(passphrase)).await;
+    let loaded = match opened {
+        Ok(repo) => TaskStack::load(Arc::new(repo)).await,
+        Err(e) if database::is_wrong_passphrase(&e) => {
+            return Ok(PageResponse::new(DomUpdate::from(
+                pages::unlock::render(
+                    Some("That passphrase didn't unlock your tasks. Try again."),
+                    &profile_list(&app),
+                ),
+                "#app",
+                "replace",
+            )))
+        }
+        Err(e) => Err(e.to_string()),
+    };
+    match loaded {
+        Ok(stack) => {
+            if crate::take_backup_pending(&app, &path) {
+                let _ = backup::snapshot(&stack, &backup_dir(&app)?)
+                    .await
+                    .inspect_err(|e| eprintln!("Startup backup failed: {}", e));
+            }
+            crate::install_stack(&app, stack);
+        }
+        Err(error) => crate::set_unopened(&app, Some(Unopened::Broken { path, error })),
+    }
+    start(app).await
+}
+
+#[tauri::command]
+pub async fn recover_from_backup(app: AppHandle, name: String) -> Result<PageResponse, String> {
+    let path = broken_path(&app)?;
+    backup::recover(&path, &backup_dir(&app)?, &name).await?;
+    crate::open_database(&app, path).await;
+    start(app).await
+}
+
+#[tauri::command]
+pub async fn recover_open_file(app: AppHandle, path: String) -> Result<PageResponse, String> {
+    broken_path(&app)?;
+    open_profile_file(app, path).await
+}
+
+#[tauri::command]
+pub async fn recover_start_fresh(app: AppHandle) -> Result<PageResponse, String> {
+    let path = broken_path(&app)?;
+    backup::set_aside(&path)?;
+    crate::open_database(&app, path).await;
+    start(app).await
+}
+
 #[tauri::command]
 pub async fn index(stack: State<'_, TaskStack>) -> Result<PageResponse, String> {
+    stack.set_last_view(View::Index).await?;
     Ok(PageResponse::new(DomUpdate::from(
         pages::index::render(&stack).await,
         "#app",
@@ -16,7 +168,7 @@ pub async fn index(stack: State<'_, TaskStack>) -> Result<PageResponse, String>
 #[tauri::command]
 pub async fn set_list_id(state: State<'_, TaskStack>, list_id: String) -> Result<(), String> {
     let list_id = Ulid::from_string(&list_id).map_err(|e| e.to_string())?;
-    state.set_list_id(list_id);
+    state.set_list_id(list_id).await?;
     Ok(())
 }
 
@@ -30,8 +182,21 @@ pub async fn add_task(
     stack: State<'_, TaskStack>,
     title: String,
     description: Option<String>,
+    list_id: Option<Ulid>,
 ) -> Result<PageResponse, String> {
-    stack.push(title, description).await?;
+    match list_id {
+        // A draft from a link for another list switches to it once the task is in
+        Some(list_id) => {
+            if stack.lookup_list(&list_id.to_string()).await? != Some(list_id) {
+                return Err("The list this task was for no longer exists".to_string());
+            }
+            stack.for_list(list_id).push(title, description).await?;
+            stack.set_list_id(list_id).await?;
+        }
+        None => {
+            stack.push(title, description).await?;
+        }
+    }
     Ok(PageResponse::new(DomUpdate::from(
         pages::index::render(&stack).await,
         "#app",
@@ -44,16 +209,8 @@ pub async fn complete_task(
     stack: State<'_, TaskStack>,
     id: String,
 ) -> Result<PageResponse, String> {
-    println!("Completing task with ID: {}", id);
-    let id = Ulid::from_string(&id).map_err(|e| {
-        println!("Failed to parse ID: {}", e);
-        e.to_string()
-    })?;
-    stack.complete_task(id).await.map_err(|e| {
-        println!("Failed to complete task: {}", e);
-        e.to_string()
-    })?;
-    println!("Task completed successfully");
+    let id = Ulid::from_string(&id).map_err(|e| e.to_string())?;
+    stack.complete_task(id).await?;
     Ok(PageResponse::new(DomUpdate::from(
         pages::index::render(&stack).await,
         "#app",
@@ -76,6 +233,7 @@ pub async fn move_task_to_end(
 
 #[tauri::command]
 pub async fn list(stack: State<'_, TaskStack>) -> Result<PageResponse, String> {
+    stack.set_last_view(View::List).await?;
     Ok(PageResponse::new(DomUpdate::from(
         pages::list::render(&stack).await,
         "#app",
@@ -121,7 +279,7 @@ pub async fn switch_list(
         )))
     } else {
         let id = list_id.parse::<Ulid>().map_err(|e| e.to_string())?;
-        state.set_list_id(id);
+        state.set_list_id(id).await?;
         Ok(PageResponse::new(DomUpdate::from(
             pages::index::render(&state).await,
             "#app",
@@ -136,7 +294,7 @@ pub async fn create_list(
     name: String,
 ) -> Result<PageResponse, String> {
     let id = state.create_new_list(&name).await?;
-    state.set_list_id(id);
+    state.set_list_id(id).await?;
     Ok(PageResponse::new(DomUpdate::from(
         pages::index::render(&state).await,
         "#app",
@@ -146,9 +304,562 @@ pub async fn create_list(
 
 #[tauri::command]
 pub async fn lists(stack: State<'_, TaskStack>) -> Result<PageResponse, String> {
+    stack.set_last_view(View::Lists).await?;
     Ok(PageResponse::new(DomUpdate::from(
         pages::lists::render(&stack).await,
         "#app",
         "replace",
     )))
 }
+
+#[tauri::command]
+pub async fn delete_list(
+    state: State<'_, TaskStack>,
+    list_id: String,
+) -> Result<PageResponse, String> {
+    let id = list_id.parse::<Ulid>().map_err(|e| e.to_string())?;
+    state.delete_list(id).await?;
+    Ok(PageResponse::notify(
+        &state,
+        DomUpdate::from(pages::lists::render(&state).await, "#app", "replace"),
+        "List deleted".to_string(),
+        "success",
+    ))
+}
+
+#[tauri::command]
+pub async fn settings(stack: State<'_, TaskStack>) -> Result<PageResponse, String> {
+    Ok(PageResponse::new(DomUpdate::from(
+        pages::settings::render(&stack).await,
+        "#app",
+        "replace",
+    )))
+}
+
+#[tauri::command]
+pub async fn set_passphrase(
+    stack: State<'_, TaskStack>,
+    passphrase: String,
+    confirm: String,
+) -> Result<PageResponse, String> {
+    if passphrase != confirm {
+        return Err("Passphrases don't match".to_string());
+    }
+    let message = if passphrase.is_empty() {
+        stack.set_passphrase(None).await?;
+        "Encryption removed"
+    } else {
+        stack.set_passphrase(Some(passphrase.as_str())).await?;
+        "Passphrase updated"
+    };
+    Ok(PageResponse::notify(
+        &stack,
+        DomUpdate::from(pages::settings::render(&stack).await, "#app", "replace"),
+        message.to_string(),
+        "success",
+    ))
+}
+
+#[tauri::command]
+pub async fn backups(app: AppHandle, stack: State<'_, TaskStack>) -> Result<PageResponse, String> {
+    Ok(PageResponse::new(DomUpdate::from(
+        pages::backups::render(&stack, &backup_dir(&app)?).await,
+        "#app",
+        "replace",
+    )))
+}
+
+#[tauri::command]
+pub async fn create_backup(
+    app: AppHandle,
+    stack: State<'_, TaskStack>,
+) -> Result<PageResponse, String> {
+    let backup_dir = backup_dir(&app)?;
+    backup::snapshot(&stack, &backup_dir).await?;
+    Ok(PageResponse::notify(
+        &stack,
+        DomUpdate::from(
+            pages::backups::render(&stack, &backup_dir).await,
+            "#app",
+            "replace",
+        ),
+        "Backup created".to_string(),
+        "success",
+    ))
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    stack: State<'_, TaskStack>,
+    name: String,
+) -> Result<PageResponse, String> {
+    let backup_dir = backup_dir(&app)?;
+    let summary = backup::restore(&stack, &backup_dir, &name).await?;
+    Ok(PageResponse::notify(
+        &stack,
+        DomUpdate::from(
+            pages::backups::render(&stack, &backup_dir).await,
+            "#app",
+            "replace",
+        ),
+        format!(
+            "Restored {} lists and {} tasks",
+            summary.list_count, summary.task_count
+        ),
+        "success",
+    ))
+}
+
+#[tauri::command]
+pub async fn preferences(stack: State<'_, TaskStack>) -> Result<PageResponse, String> {
+    Ok(PageResponse::new(DomUpdate::from(
+        pages::preferences::render(&stack).await,
+        "#app",
+        "replace",
+    )))
+}
+
+#[tauri::command]
+pub async fn get_settings(stack: State<'_, TaskStack>) -> Result<Settings, String> {
+    Ok(stack.get_settings())
+}
+
+#[tauri::command]
+pub async fn update_settings(
+    stack: State<'_, TaskStack>,
+    settings: Settings,
+) -> Result<PageResponse, String> {
+    stack.update_settings(settings).await?;
+    Ok(PageResponse::notify(
+        &stack,
+        DomUpdate::from(pages::preferences::render(&stack).await, "#app", "replace"),
+        "Preferences saved".to_string(),
+        "success",
+    ))
+}
+
+#[tauri::command]
+pub async fn integrity(stack: State<'_, TaskStack>) -> Result<PageResponse, String> {
+    Ok(PageResponse::new(DomUpdate::from(
+        pages::integrity::render(&stack).await,
+        "#app",
+        "replace",
+    )))
+}
+
+#[tauri::command]
+pub async fn repair_database(stack: State<'_, TaskStack>) -> Result<PageResponse, String> {
+    let repairs = stack.repair().await?;
+    Ok(PageResponse::notify(
+        &stack,
+        DomUpdate::from(pages::integrity::render(&stack).await, "#app", "replace"),
+        repairs.to_string(),
+        "success",
+    ))
+}
+
+#[tauri::command]
+pub async fn profiles(app: AppHandle) -> Result<PageResponse, String> {
+    Ok(PageResponse::new(DomUpdate::from(
+        pages::profiles::render(&profile_list(&app)),
+        "#app",
+        "replace",
+    )))
+}
+
+#[tauri::command]
+pub async fn switch_profile(app: AppHandle, name: String) -> Result<PageResponse, String> {
+    let path = update_profiles(&app, |profiles, _| {
+        profiles.switch(&name).map(|profile| profile.path.clone())
+    })?;
+    crate::open_database(&app, path).await;
+    start(app).await
+}
+
+#[tauri::command]
+pub async fn create_profile(app: AppHandle, name: String) -> Result<PageResponse, String> {
+    let path = update_profiles(&app, |profiles, app_data_dir| {
+        let name = profiles.create(&name, app_data_dir)?.name.clone();
+        profiles.switch(&name).map(|profile| profile.path.clone())
+    })?;
+    crate::open_database(&app, path).await;
+    start(app).await
+}
+
+/// Adds an existing database file as a profile and switches to it.
+#[tauri::command]
+pub async fn open_profile_file(app: AppHandle, path: String) -> Result<PageResponse, String> {
+    let path = update_profiles(&app, |profiles, _| {
+        let name = profiles.add_file(Path::new(path.trim()))?.name.clone();
+        profiles.switch(&name).map(|profile| profile.path.clone())
+    })?;
+    crate::open_database(&app, path).await;
+    start(app).await
+}
+
+#[tauri::command]
+pub async fn transfer(stack: State<'_, TaskStack>) -> Result<PageResponse, String> {
+    Ok(PageResponse::new(DomUpdate::from(
+        pages::transfer::render(&stack).await,
+        "#app",
+        "replace",
+    )))
+}
+
+async fn api_page(app: &AppHandle, stack: &TaskStack) -> DomUpdate {
+    let config = app.state::<Mutex<ApiConfig>>().lock().unwrap().clone();
+    let status = app.state::<ApiServer>().status();
+    DomUpdate::from(
+        pages::api::render(stack, &config, status).await,
+        "#app",
+        "replace",
+    )
+}
+
+/// Applies `change` to the API settings, saves them and restarts the server on them.
+fn update_api_config(app: &AppHandle, change: impl FnOnce(&mut ApiConfig)) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let config = {
+        let state = app.state::<Mutex<ApiConfig>>();
+        let mut config = state.lock().unwrap();
+        change(&mut config);
+        config.save(&app_data_dir)?;
+        config.clone()
+    };
+    app.state::<ApiServer>().apply(app, &config);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn api(app: AppHandle, stack: State<'_, TaskStack>) -> Result<PageResponse, String> {
+    Ok(PageResponse::new(api_page(&app, &stack).await))
+}
+
+#[tauri::command]
+pub async fn update_api(
+    app: AppHandle,
+    stack: State<'_, TaskStack>,
+    enabled: bool,
+    port: u16,
+) -> Result<PageResponse, String> {
+    if port < 1024 {
+        return Err("Pick a port from 1024 up".to_string());
+    }
+    update_api_config(&app, |config| {
+        config.enabled = enabled;
+        config.port = port;
+    })?;
+    let message = match app.state::<ApiServer>().status() {
+        Ok(Some(port)) => format!("API listening on port {port}"),
+        Ok(None) => "API turned off".to_string(),
+        Err(e) => format!("The API couldn't start: {e}"),
+    };
+    Ok(PageResponse::notify(
+        &stack,
+        api_page(&app, &stack).await,
+        message,
+        "success",
+    ))
+}
+
+/// Replaces the token, so anything using the old one is locked out.
+#[tauri::command]
+pub async fn regenerate_api_token(
+    app: AppHandle,
+    stack: State<'_, TaskStack>,
+) -> Result<PageResponse, String> {
+    update_api_config(&app, |config| config.token = api::new_token())?;
+    Ok(PageResponse::notify(
+        &stack,
+        api_page(&app, &stack).await,
+        "New API token created".to_string(),
+        "success",
+    ))
+}
+
+#[tauri::command]
+pub async fn export_json(
+    stack: State<'_, TaskStack>,
+    path: String,
+) -> Result<PageResponse, String> {
+    let document = stack.export().await?;
+    let path = PathBuf::from(path.trim());
+    std::fs::write(&path, formats::json::to_string(&document)?).map_err(|e| e.to_string())?;
+    Ok(PageResponse::notify(
+        &stack,
+        DomUpdate::from(pages::transfer::render(&stack).await, "#app", "replace"),
+        format!(
+            "Exported {} lists and {} tasks to {}",
+            document.lists.len(),
+            document.task_count(),
+            path.display()
+        ),
+        "success",
+    ))
+}
+
+#[tauri::command]
+pub async fn import_json(
+    stack: State<'_, TaskStack>,
+    path: String,
+    mode: ImportMode,
+) -> Result<PageResponse, String> {
+    let json = std::fs::read_to_string(path.trim()).map_err(|e| e.to_string())?;
+    let report = stack.import(formats::json::from_str(&json)?, mode).await?;
+    Ok(PageResponse::notify(
+        &stack,
+        DomUpdate::from(pages::transfer::render(&stack).await, "#app", "replace"),
+        report.to_string(),
+        "success",
+    ))
+}
+
+/// Writes the current list to `path` with `write` and reports how many tasks went out.
+async fn export_list(
+    stack: &TaskStack,
+    path: &str,
+    write: fn(&formats::ListRecord) -> String,
+) -> Result<PageResponse, String> {
+    let list = stack.export_list(stack.get_list_id()).await?;
+    let path = PathBuf::from(path.trim());
+    std::fs::write(&path, write(&list)).map_err(|e| e.to_string())?;
+    Ok(PageResponse::notify(
+        stack,
+        DomUpdate::from(pages::transfer::render(stack).await, "#app", "replace"),
+        format!(
+            "Exported {} tasks from {} to {}",
+            list.tasks.len(),
+            list.name,
+            path.display()
+        ),
+        "success",
+    ))
+}
+
+/// The file at `path`, or the pasted `text` when no path was given.
+fn import_text(path: &str, text: String) -> Result<String, String> {
+    if path.is_empty() {
+        Ok(text)
+    } else {
+        std::fs::read_to_string(path).map_err(|e| e.to_string())
+    }
+}
+
+/// A name for a list created by importing `path`.
+fn imported_list_name(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "Imported".to_string())
+}
+
+/// `list` from an import form: a list id, or "new" for a list named after the file.
+fn import_destination(list: &str, path: &str) -> Result<formats::Destination, String> {
+    if list == "new" {
+        Ok(formats::Destination::New(imported_list_name(path)))
+    } else {
+        Ulid::from_string(list)
+            .map(formats::Destination::List)
+            .map_err(|e| e.to_string())
+    }
+}
+
+async fn imported(stack: &TaskStack, added: usize) -> Result<PageResponse, String> {
+    Ok(PageResponse::notify(
+        stack,
+        DomUpdate::from(pages::transfer::render(stack).await, "#app", "replace"),
+        format!("Imported {added} tasks"),
+        "success",
+    ))
+}
+
+/// Adds a checklist's tasks to `list`, either a list id or "new" for a list
+/// named after the checklist or the file, created only if a task goes into it.
+async fn import_checklist(
+    stack: &TaskStack,
+    checklist: formats::Checklist,
+    path: &str,
+    list: &str,
+) -> Result<PageResponse, String> {
+    if checklist.tasks.is_empty() {
+        return Err("No tasks found".to_string());
+    }
+    let destination = match checklist.name.as_deref() {
+        Some(name) if list == "new" && !name.is_empty() => {
+            formats::Destination::New(name.to_string())
+        }
+        _ => import_destination(list, path)?,
+    };
+    let added = stack
+        .import_entries(checklist.into_entries(), destination)
+        .await?;
+    imported(stack, added).await
+}
+
+/// Writes the current list as a Markdown checklist.
+#[tauri::command]
+pub async fn export_markdown(
+    stack: State<'_, TaskStack>,
+    path: String,
+) -> Result<PageResponse, String> {
+    export_list(&stack, &path, formats::markdown::export).await
+}
+
+/// Reads a checklist from a file or pasted text into `list`.
+#[tauri::command]
+pub async fn import_markdown(
+    stack: State<'_, TaskStack>,
+    path: String,
+    text: String,
+    list: String,
+) -> Result<PageResponse, String> {
+    let path = path.trim();
+    let checklist = formats::markdown::parse(&import_text(path, text)?);
+    import_checklist(&stack, checklist, path, &list).await
+}
+
+/// Writes the current list in todo.txt format.
+#[tauri::command]
+pub async fn export_todotxt(
+    stack: State<'_, TaskStack>,
+    path: String,
+) -> Result<PageResponse, String> {
+    export_list(&stack, &path, formats::todotxt::export).await
+}
+
+/// Reads todo.txt from a file or pasted text. Tasks go to the list their
+/// `+project` names; the rest into `list`, a list id or "new".
+#[tauri::command]
+pub async fn import_todotxt(
+    stack: State<'_, TaskStack>,
+    path: String,
+    text: String,
+    list: String,
+) -> Result<PageResponse, String> {
+    let path = path.trim();
+    let entries = formats::todotxt::parse(&import_text(path, text)?);
+    if entries.is_empty() {
+        return Err("No tasks found".to_string());
+    }
+    let destination = import_destination(&list, path)?;
+    let added = stack.import_todotxt(entries, destination).await?;
+    imported(&stack, added).await
+}
+
+/// Writes the current list as an iCalendar file of to-dos.
+#[tauri::command]
+pub async fn export_ics(stack: State<'_, TaskStack>, path: String) -> Result<PageResponse, String> {
+    export_list(&stack, &path, formats::ical::export).await
+}
+
+/// Reads the to-dos in an iCalendar file or pasted text into `list`.
+#[tauri::command]
+pub async fn import_ics(
+    stack: State<'_, TaskStack>,
+    path: String,
+    text: String,
+    list: String,
+) -> Result<PageResponse, String> {
+    let path = path.trim();
+    let checklist = formats::ical::parse(&import_text(path, text)?)?;
+    import_checklist(&stack, checklist, path, &list).await
+}
+
+/// Reads a date from a date input, which sends an empty string when unset.
+fn optional_date(value: &str) -> Result<Option<NaiveDate>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|e| format!("{value}: {e}"))
+}
+
+/// Writes tasks as CSV: the current list or, with `scope` "all", every list.
+/// `columns` is a comma-separated list of column names and `from`/`to` limit
+/// the export to tasks completed in that range.
+#[tauri::command]
+pub async fn export_csv(
+    stack: State<'_, TaskStack>,
+    path: String,
+    scope: String,
+    columns: String,
+    from: String,
+    to: String,
+) -> Result<PageResponse, String> {
+    let options = formats::csv::ExportOptions {
+        columns: formats::csv::Column::parse_list(&columns)?,
+        completed_from: optional_date(&from)?,
+        completed_to: optional_date(&to)?,
+    };
+    let lists = if scope == "all" {
+        stack.export().await?.lists
+    } else {
+        vec![stack.export_list(stack.get_list_id()).await?]
+    };
+    let (csv, count) = formats::csv::export(&lists, &options)?;
+    let path = PathBuf::from(path.trim());
+    std::fs::write(&path, csv).map_err(|e| e.to_string())?;
+    Ok(PageResponse::notify(
+        &stack,
+        DomUpdate::from(pages::transfer::render(&stack).await, "#app", "replace"),
+        format!("Exported {count} tasks to {}", path.display()),
+        "success",
+    ))
+}
+
+/// Reads CSV rows as tasks, with `mapping` naming the header each column is
+/// read from. Rows go to the list their list column names; the rest into
+/// `list`, a list id or "new".
+#[tauri::command]
+pub async fn import_csv(
+    stack: State<'_, TaskStack>,
+    path: String,
+    text: String,
+    mapping: String,
+    list: String,
+) -> Result<PageResponse, String> {
+    let path = path.trim();
+    let mapping = formats::csv::parse_mapping(&mapping)?;
+    let entries = formats::csv::parse(&import_text(path, text)?, &mapping)?;
+    if entries.is_empty() {
+        return Err("No tasks found".to_string());
+    }
+    let destination = import_destination(&list, path)?;
+    let added = stack.import_entries(entries, destination).await?;
+    imported(&stack, added).await
+}
+
+/// Imports another app's export with the importer named by `source`. With
+/// `dry_run` checked, only shows what the import would do.
+#[tauri::command]
+pub async fn import_from(
+    stack: State<'_, TaskStack>,
+    source: String,
+    path: String,
+    list: String,
+    dry_run: Option<String>,
+) -> Result<PageResponse, String> {
+    let importer = importers::find(&source)?;
+    let path = path.trim();
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let entries = importer.parse(&contents)?;
+    if entries.is_empty() {
+        return Err("No tasks found".to_string());
+    }
+    let destination = import_destination(&list, path)?;
+
+    if dry_run.is_some() {
+        let preview = stack.preview_entries(&entries, &destination).await?;
+        return Ok(PageResponse::new(DomUpdate::from(
+            pages::transfer::preview(importer.name(), &preview),
+            "#import-preview",
+            "replace",
+        )));
+    }
+    let added = stack.import_entries(entries, destination).await?;
+    imported(&stack, added).await
+}
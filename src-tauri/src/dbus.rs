@@ -0,0 +1,147 @@
+//! A session bus service for desktop extensions and scripts, e.g.
+//! `busctl --user get-property net.thekwoka.TaskStack /net/thekwoka/TaskStack
+//! net.thekwoka.TaskStack1 CurrentTask`. It connects to whichever bus
+//! `DBUS_SESSION_BUS_ADDRESS` names, so a private `dbus-daemon` works too.
+
+use crate::rpc;
+use crate::tasks::{Change, TaskStack};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, Runtime};
+use zbus::{connection, fdo, interface, object_server::SignalEmitter, Connection};
+
+pub const BUS_NAME: &str = "net.thekwoka.TaskStack";
+pub const OBJECT_PATH: &str = "/net/thekwoka/TaskStack";
+
+/// The connection, managed so changes can be announced on it.
+pub struct Bus(Connection);
+
+/// Generic over the runtime so tests can serve it from a mock app.
+struct Service<R: Runtime> {
+    app: AppHandle<R>,
+}
+
+impl<R: Runtime> Service<R> {
+    fn stack(&self) -> fdo::Result<tauri::State<'_, TaskStack>> {
+        self.app
+            .try_state::<TaskStack>()
+            .ok_or_else(|| fdo::Error::Failed("The database isn't open".to_string()))
+    }
+
+    /// Runs a CLI command through the same path as the socket.
+    async fn call(&self, method: &str, params: Value) -> fdo::Result<Value> {
+        let stack = self.stack()?;
+        rpc::call(&stack, method, params)
+            .await
+            .map_err(|e| fdo::Error::Failed(e.message))
+    }
+}
+
+/// Empty strings stand in for nothing, as D-Bus has no null.
+#[interface(name = "net.thekwoka.TaskStack1")]
+impl<R: Runtime> Service<R> {
+    /// The title of the task at the top of the stack.
+    #[zbus(property)]
+    async fn current_task(&self) -> fdo::Result<String> {
+        let task = self
+            .stack()?
+            .first_active()
+            .await
+            .map_err(fdo::Error::Failed)?;
+        Ok(task.map(|task| task.title).unwrap_or_default())
+    }
+
+    #[zbus(property)]
+    async fn current_task_id(&self) -> fdo::Result<String> {
+        let task = self
+            .stack()?
+            .first_active()
+            .await
+            .map_err(fdo::Error::Failed)?;
+        Ok(task.map(|task| task.id.to_string()).unwrap_or_default())
+    }
+
+    /// The name of the current list.
+    #[zbus(property)]
+    async fn current_list(&self) -> fdo::Result<String> {
+        let stack = self.stack()?;
+        let list_id = stack.get_list_id();
+        let lists = stack.get_lists().await.map_err(fdo::Error::Failed)?;
+        Ok(lists
+            .into_iter()
+            .find(|list| list.id == list_id)
+            .map(|list| list.name)
+            .unwrap_or_default())
+    }
+
+    #[zbus(property)]
+    async fn active_count(&self) -> fdo::Result<u32> {
+        let stack = self.stack()?;
+        let counts = stack.count_tasks().await.map_err(fdo::Error::Failed)?;
+        Ok(counts
+            .get(&stack.get_list_id())
+            .map_or(0, |count| count.active as u32))
+    }
+
+    /// Adds a task to the end of the current list and returns its id.
+    async fn push(&self, title: String, description: String) -> fdo::Result<String> {
+        let description = Some(description).filter(|d| !d.trim().is_empty());
+        let task = self
+            .call(
+                "push",
+                json!({ "title": title, "description": description }),
+            )
+            .await?;
+        Ok(task["id"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Whether there was a task to complete.
+    async fn complete_current(&self) -> fdo::Result<bool> {
+        Ok(!self.call("done", Value::Null).await?.is_null())
+    }
+
+    /// Whether there was a task to move.
+    async fn move_current_to_end(&self) -> fdo::Result<bool> {
+        Ok(!self.call("defer", Value::Null).await?.is_null())
+    }
+
+    /// Makes a list, by name or id, the current one.
+    async fn switch_list(&self, list: String) -> fdo::Result<()> {
+        self.call("switch", json!({ "list": list })).await?;
+        Ok(())
+    }
+}
+
+/// Claims the bus name and serves the service until the app exits.
+pub async fn serve<R: Runtime>(app: &AppHandle<R>) -> zbus::Result<()> {
+    let connection = connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, Service { app: app.clone() })?
+        .build()
+        .await?;
+    app.manage(Bus(connection));
+    Ok(())
+}
+
+/// Emits PropertiesChanged for what `change` may have touched.
+pub async fn announce<R: Runtime>(app: &AppHandle<R>, change: Change) -> zbus::Result<()> {
+    if change == Change::Settings {
+        return Ok(());
+    }
+    let Some(bus) = app.try_state::<Bus>() else {
+        return Ok(());
+    };
+    let service = bus
+        .0
+        .object_server()
+        .interface::<_, Service<R>>(OBJECT_PATH)
+        .await?;
+    let emitter: &SignalEmitter<'_> = service.signal_emitter();
+    let service = service.get().await;
+    service.current_task_changed(emitter).await?;
+    service.current_task_id_changed(emitter).await?;
+    service.active_count_changed(emitter).await?;
+    if !matches!(change, Change::Tasks { .. }) {
+        service.current_list_changed(emitter).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,301 @@
+//! `taskstack://` links from browsers, mail clients and other apps:
+//!
+//! - `taskstack://add?title=…&description=…&list=…` fills in the form for a
+//!   task to be checked and added from the window, onto `list` by name or id
+//!   if given, which becomes the current list once the task is added
+//! - `taskstack://task/<id>` opens the list holding a task
+//! - `taskstack://list/<name or id>` switches to a list
+
+use crate::settings::View;
+use crate::tasks::TaskStack;
+use crate::types::{DomUpdate, Notification, PageResponse};
+use crate::ui::pages::{self, index::Draft};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, Runtime, Url};
+use ulid::Ulid;
+
+pub const SCHEME: &str = "taskstack";
+/// Emitted with an `Opened` for the window to show.
+pub const OPEN_LINK: &str = "open-link";
+/// Longest title an `add` link may carry, in characters.
+pub const MAX_TITLE_LEN: usize = 200;
+/// Longest description an `add` link may carry, in characters.
+pub const MAX_DESCRIPTION_LEN: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Link {
+    Add {
+        title: String,
+        description: Option<String>,
+        list: Option<String>,
+    },
+    Task(Ulid),
+    List(String),
+}
+
+impl Link {
+    pub fn parse(url: &Url) -> Result<Self, String> {
+        if url.scheme() != SCHEME {
+            return Err(format!("Not a {SCHEME}:// link"));
+        }
+        // `taskstack://add` puts the route in the host, `taskstack:add` in the path
+        let route = format!("{}{}", url.host_str().unwrap_or_default(), url.path());
+        let segments: Vec<&str> = route.split('/').filter(|s| !s.is_empty()).collect();
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        match segments.as_slice() {
+            ["add"] => {
+                let title = param("title").ok_or_else(|| "The link has no title".to_string())?;
+                let description = param("description");
+                checked("title", &title, MAX_TITLE_LEN, |_| false)?;
+                if let Some(description) = &description {
+                    // Descriptions may run over several lines
+                    checked("description", description, MAX_DESCRIPTION_LEN, |c| {
+                        matches!(c, '\n' | '\r' | '\t')
+                    })?;
+                }
+                Ok(Link::Add {
+                    title,
+                    description,
+                    list: param("list"),
+                })
+            }
+            ["task", id] => Ulid::from_string(id)
+                .map(Link::Task)
+                .map_err(|e| format!("{id} isn't a task id: {e}")),
+            ["list", list] => {
+                let list = percent_decode(list);
+                if list.trim().is_empty() {
+                    return Err("The link has no list".to_string());
+                }
+                Ok(Link::List(list))
+            }
+            _ => Err(format!("Task Stack can't open {url}")),
+        }
+    }
+}
+
+/// Rejects a `field` longer than `max` characters or holding control
+/// characters other than those `allowed`, as links can come from anywhere.
+fn checked(field: &str, value: &str, max: usize, allowed: fn(char) -> bool) -> Result<(), String> {
+    if value.chars().count() > max {
+        return Err(format!(
+            "The link's {field} is longer than {max} characters"
+        ));
+    }
+    if value.chars().any(|c| c.is_control() && !allowed(c)) {
+        return Err(format!("The link's {field} has control characters"));
+    }
+    Ok(())
+}
+
+/// Paths stay percent-encoded when parsed, unlike query values.
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// What a link leads to: the page to show, and the element on it to scroll to.
+#[derive(Clone, Serialize)]
+pub struct Opened {
+    pub response: PageResponse,
+    pub focus: Option<String>,
+}
+
+/// What links opened before the window was listening, kept in order for
+/// `commands::start` to show instead of emitting them to no one.
+#[derive(Default)]
+pub struct PendingLink {
+    opened: Mutex<Vec<Opened>>,
+    ready: AtomicBool,
+}
+
+/// What links opened before the window started, oldest first. From now on
+/// links are emitted to the window as they're opened.
+pub fn take_pending<R: Runtime>(app: &AppHandle<R>) -> Vec<Opened> {
+    let Some(pending) = app.try_state::<PendingLink>() else {
+        return vec![];
+    };
+    pending.ready.store(true, Ordering::SeqCst);
+    std::mem::take(&mut *pending.opened.lock().unwrap())
+}
+
+/// Shows the links opened before the window started over `start`. Only one
+/// page fits, so the last link to open one wins; links that failed can't
+/// replace it and are reported alongside, as are pages left unshown.
+pub fn show_pending(opened: Vec<Opened>, start: PageResponse) -> PageResponse {
+    let (pages, failed): (Vec<PageResponse>, Vec<PageResponse>) = opened
+        .into_iter()
+        .map(|opened| opened.response)
+        .partition(|response| !response.updates.is_empty());
+    let skipped = pages.len().saturating_sub(1);
+    let mut response = pages.into_iter().last().unwrap_or(start);
+
+    let errors: Vec<Notification> = failed
+        .into_iter()
+        .filter_map(|response| response.notification)
+        .collect();
+    let mut messages: Vec<String> = errors.iter().map(|error| error.message.clone()).collect();
+    if skipped > 0 {
+        messages.push(format!(
+            "Showing the last of {} links opened at startup",
+            skipped + 1
+        ));
+    }
+    if messages.is_empty() {
+        return response;
+    }
+    let duration = errors
+        .first()
+        .map(|error| error.duration)
+        .or_else(|| response.notification.as_ref().map(|shown| shown.duration))
+        .flatten();
+    response.notification = Some(Notification {
+        message: messages.join(". "),
+        notification_type: if errors.is_empty() { "info" } else { "error" }.to_string(),
+        duration,
+    });
+    response
+}
+
+/// Queues `opened` for `take_pending` until the window has started, or emits it.
+fn show<R: Runtime>(app: &AppHandle<R>, opened: Opened) {
+    if let Some(pending) = app.try_state::<PendingLink>() {
+        let mut kept = pending.opened.lock().unwrap();
+        if !pending.ready.load(Ordering::SeqCst) {
+            kept.push(opened);
+            return;
+        }
+    }
+    let _ = app
+        .emit(OPEN_LINK, opened)
+        .inspect_err(|e| eprintln!("Failed to show a link: {}", e));
+}
+
+/// Follows `link` through the same commands as the CLI and the window. Nothing
+/// is added until the user confirms it in the window.
+pub async fn open(stack: &TaskStack, link: Link) -> Result<Opened, String> {
+    match link {
+        Link::Add {
+            title,
+            description,
+            list,
+        } => {
+            // The current list only changes once the task is added
+            let target = match &list {
+                Some(list) => {
+                    let list_id = stack.find_list(list).await?;
+                    stack
+                        .get_lists()
+                        .await?
+                        .into_iter()
+                        .find(|list| list.id == list_id && list_id != stack.get_list_id())
+                }
+                None => None,
+            };
+            stack.set_last_view(View::Index).await?;
+            let draft = Draft {
+                title: &title,
+                description: description.as_deref().unwrap_or_default(),
+                list: target.as_ref(),
+            };
+            let page = pages::index::render_draft(stack, &draft).await;
+            Ok(Opened {
+                response: PageResponse::notify(
+                    stack,
+                    DomUpdate::from(page, "#app", "replace"),
+                    "Check the task, then add it".to_string(),
+                    "info",
+                ),
+                focus: Some("task-form".to_string()),
+            })
+        }
+        Link::Task(id) => {
+            let task = stack.locate_task(&id).await?;
+            stack.set_list_id(task.list_id).await?;
+            stack.set_last_view(View::List).await?;
+            Ok(Opened {
+                response: PageResponse::new(DomUpdate::from(
+                    pages::list::render(stack).await,
+                    "#app",
+                    "replace",
+                )),
+                focus: Some(format!("task-{id}")),
+            })
+        }
+        Link::List(list) => {
+            let list_id = stack.find_list(&list).await?;
+            stack.set_list_id(list_id).await?;
+            stack.set_last_view(View::List).await?;
+            Ok(Opened {
+                response: PageResponse::new(DomUpdate::from(
+                    pages::list::render(stack).await,
+                    "#app",
+                    "replace",
+                )),
+                focus: None,
+            })
+        }
+    }
+}
+
+/// Opens each link in turn and brings the window forward to show the last.
+/// Problems are shown as notifications, since there's no caller to return them to.
+pub fn handle<R: Runtime>(app: &AppHandle<R>, urls: Vec<Url>) -> JoinHandle<()> {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        for url in urls {
+            let stack = app.try_state::<TaskStack>();
+            let settings = stack
+                .as_ref()
+                .map(|stack| stack.get_settings())
+                .unwrap_or_default();
+            let opened = match (Link::parse(&url), stack) {
+                (Err(error), _) => Err(error),
+                (Ok(_), None) => Err("Unlock Task Stack before opening links".to_string()),
+                (Ok(link), Some(stack)) => open(&stack, link).await,
+            };
+            let opened = opened.unwrap_or_else(|message| {
+                eprintln!("Failed to open {}: {}", url, message);
+                Opened {
+                    response: PageResponse::notification(Notification::new(
+                        &settings, message, "error",
+                    )),
+                    focus: None,
+                }
+            });
+            show(&app, opened);
+        }
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    })
+}
@@ -1,20 +1,188 @@
+pub mod api;
+pub mod cli;
 pub mod commands;
-pub mod database;
-pub mod tasks;
+#[cfg(target_os = "linux")]
+pub mod dbus;
+pub mod deep_link;
+pub mod rpc;
 pub mod types;
 pub mod ui;
 
+// The stack and its storage live in the core crate; the app's modules reach them by the same paths
+pub use task_stack_core::{
+    backup, database, formats, importers, integrity, profiles, repository, settings, status, tasks,
+};
+
 // Re-export the task stack for use in main.rs
 pub use tasks::{Task, TaskStack};
 
-use tauri::{path::BaseDirectory, Manager};
+use api::{ApiConfig, ApiServer};
+use database::Unopened;
+use profiles::Profiles;
+use repository::LibsqlRepository;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tasks::Change;
+use tauri::{path::BaseDirectory, AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tokio::sync::broadcast::{self, error::RecvError};
 
 #[cfg(desktop)]
 use tauri_plugin_window_state::StateFlags;
 
+/// Emitted to every window with a `Change` whenever the stack's data changes,
+/// so views opened elsewhere can refresh.
+pub const DATA_CHANGED: &str = "data-changed";
+/// How often to look for writes from outside the app, like the CLI.
+const EXTERNAL_CHANGE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Opens the database at `path` and makes it the current stack, or records why
+/// it couldn't be opened so `commands::start` can show the unlock or recovery page.
+pub async fn open_database<R: Runtime>(app: &AppHandle<R>, path: PathBuf) {
+    if database::is_encrypted(&path) {
+        set_unopened(app, Some(Unopened::Locked(path)));
+        return;
+    }
+    let loaded = match LibsqlRepository::open(path.clone(), None).await {
+        Ok(repo) => TaskStack::load(Arc::new(repo)).await,
+        Err(e) => Err(e.to_string()),
+    };
+    match loaded {
+        Ok(stack) => install_stack(app, stack),
+        Err(error) => {
+            eprintln!("Failed to open {:?}: {}", path, error);
+            set_unopened(app, Some(Unopened::Broken { path, error }));
+        }
+    }
+}
+
+/// Set when a database is opened, so `commands::start` checks its integrity
+/// once and offers repairs before anything else is shown.
+struct IntegrityPending(AtomicBool);
+
+/// Whether the open database hasn't been checked yet, clearing it.
+pub(crate) fn take_integrity_pending(app: &AppHandle) -> bool {
+    app.try_state::<IntegrityPending>()
+        .is_some_and(|pending| pending.0.swap(false, Ordering::SeqCst))
+}
+
+/// The encrypted database whose startup backup waits for it to be unlocked,
+/// as its unsaved writes can't be checkpointed without the passphrase.
+struct BackupPending(Mutex<Option<PathBuf>>);
+
+/// Whether the database at `path` still needs its startup backup, clearing it.
+pub(crate) fn take_backup_pending(app: &AppHandle, path: &Path) -> bool {
+    app.try_state::<BackupPending>().is_some_and(|pending| {
+        let mut pending = pending.0.lock().unwrap();
+        pending.take_if(|pending| *pending == path).is_some()
+    })
+}
+
+/// Manages `stack`, or swaps it into the managed stack when switching profiles.
+pub(crate) fn install_stack<R: Runtime>(app: &AppHandle<R>, stack: TaskStack) {
+    match app.try_state::<TaskStack>() {
+        Some(current) => current.replace(stack),
+        None => {
+            // Later stacks are swapped into this one, so its channel lasts the whole run
+            forward_changes(app, stack.subscribe());
+            app.manage(stack);
+        }
+    }
+    set_unopened(app, None);
+    match app.try_state::<IntegrityPending>() {
+        Some(pending) => pending.0.store(true, Ordering::SeqCst),
+        None => {
+            app.manage(IntegrityPending(AtomicBool::new(true)));
+        }
+    }
+}
+
+fn forward_changes<R: Runtime>(app: &AppHandle<R>, mut changes: broadcast::Receiver<Change>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let change = match changes.recv().await {
+                Ok(change) => change,
+                // Some were dropped, so have everything refreshed
+                Err(RecvError::Lagged(_)) => Change::Database,
+                Err(RecvError::Closed) => break,
+            };
+            let _ = app
+                .emit(DATA_CHANGED, change)
+                .inspect_err(|e| eprintln!("Failed to announce a change: {}", e));
+            #[cfg(target_os = "linux")]
+            let _ = dbus::announce(&app, change)
+                .await
+                .inspect_err(|e| eprintln!("Failed to announce a change on D-Bus: {}", e));
+        }
+    });
+}
+
+/// Checks every `interval` for writes to the open database from outside the
+/// app, announcing them as `Change::Database` so every view refreshes.
+pub fn watch_outside_changes<R: Runtime>(app: &AppHandle<R>, interval: Duration) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut seen = None;
+        let mut failing = false;
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Some(stack) = app.try_state::<TaskStack>() {
+                match stack.changed_elsewhere(&mut seen).await {
+                    Ok(changed) => {
+                        failing = false;
+                        if changed {
+                            stack.notify(Change::Database);
+                        }
+                    }
+                    // E.g. while a locked profile is closed; the next database starts afresh
+                    Err(e) => {
+                        seen = None;
+                        if !std::mem::replace(&mut failing, true) {
+                            eprintln!("Failed to check for outside changes: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Records why no database is open, closing the managed stack so the API, RPC,
+/// D-Bus and links don't carry on with the last profile's database.
+pub(crate) fn set_unopened<R: Runtime>(app: &AppHandle<R>, unopened: Option<Unopened>) {
+    if unopened.is_some() {
+        if let Some(stack) = app.try_state::<TaskStack>() {
+            stack.close();
+        }
+    }
+    match app.try_state::<Mutex<Option<Unopened>>>() {
+        Some(state) => *state.lock().unwrap() = unopened,
+        None => {
+            app.manage(Mutex::new(unopened));
+        }
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let builder = tauri::Builder::default();
+
+    // Registered first so a second launch exits before it touches tasks.db, handing
+    // any taskstack:// link it was opened with to this one
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }));
+
+    builder
+        .plugin(tauri_plugin_deep_link::init())
         .setup(|app| {
             let app_data_dir = app.path().app_data_dir().expect("App Data Dir to be Found");
             std::fs::create_dir_all(&app_data_dir).expect("App Data Dir to be Created");
@@ -28,31 +196,74 @@ pub fn run() {
                     .build(),
             );
 
-            let db_path = app
+            let _ = rpc::serve(handle, &app_data_dir)
+                .inspect_err(|e| eprintln!("Failed to listen for the CLI: {}", e));
+            #[cfg(target_os = "linux")]
+            {
+                let bus = handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let _ = dbus::serve(&bus)
+                        .await
+                        .inspect_err(|e| eprintln!("Failed to start the D-Bus service: {}", e));
+                });
+            }
+
+            let profiles = Profiles::load(&app_data_dir);
+            let db_path = profiles.current().path.clone();
+            app.manage(Mutex::new(profiles));
+            let backup_dir = app
                 .path()
-                .resolve("tasks.db", BaseDirectory::AppData)
+                .resolve(backup::BACKUP_DIR, BaseDirectory::AppData)
                 .expect("Path to be resolvable");
-            tauri::async_runtime::block_on(async move {
-                let db = database::init_database(&db_path)
-                    .await
-                    .expect("DB to be initialized");
-                let binding = database::get_lists(&db)
-                    .await
-                    .expect("DB Should be queryable");
-                let list_id = binding.first();
-                if let Some(id) = list_id {
-                    let task_stack = TaskStack::new(db, *id);
-                    handle.manage(task_stack);
-                    Ok::<(), Box<dyn std::error::Error>>(())
-                } else {
-                    let id = database::create_list(&db, "Initial List")
-                        .await
-                        .expect("List to be created");
-                    let task_stack = TaskStack::new(db, id);
-                    handle.manage(task_stack);
-                    Ok::<(), Box<dyn std::error::Error>>(())
+            // Nothing has opened the file yet, so a plain copy is consistent
+            if database::is_encrypted(&db_path) && backup::has_unsaved_writes(&db_path) {
+                app.manage(BackupPending(Mutex::new(Some(db_path.clone()))));
+            } else {
+                let _ = tauri::async_runtime::block_on(backup::snapshot_file(
+                    &db_path,
+                    None,
+                    &backup_dir,
+                ))
+                .inspect_err(|e| eprintln!("Startup backup failed: {}", e));
+            }
+
+            let scheduled = handle.clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    tokio::time::sleep(backup::BACKUP_INTERVAL).await;
+                    if let Some(stack) = scheduled.try_state::<TaskStack>() {
+                        let _ = backup::snapshot(&stack, &backup_dir)
+                            .await
+                            .inspect_err(|e| eprintln!("Scheduled backup failed: {}", e));
+                    }
                 }
-            })?;
+            });
+
+            watch_outside_changes(handle, EXTERNAL_CHANGE_INTERVAL);
+
+            tauri::async_runtime::block_on(open_database(handle, db_path));
+
+            let api = ApiConfig::load(&app_data_dir);
+            let server = ApiServer::default();
+            server.apply(handle, &api);
+            app.manage(Mutex::new(api));
+            app.manage(server);
+
+            // Installed builds register the scheme on install; these need it done at runtime
+            #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+            let _ = app
+                .deep_link()
+                .register_all()
+                .inspect_err(|e| eprintln!("Failed to register taskstack:// links: {}", e));
+            let links = handle.clone();
+            app.deep_link().on_open_url(move |event| {
+                deep_link::handle(&links, event.urls());
+            });
+            // The window isn't listening yet, so `commands::start` shows what the link opened
+            app.manage(deep_link::PendingLink::default());
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                deep_link::handle(handle, urls);
+            }
             #[cfg(debug_assertions)]
             {
                 let window = app.get_webview_window("main").unwrap();
@@ -62,6 +273,8 @@ pub fn run() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
+            commands::start,
+            commands::unlock_database,
             commands::index,
             commands::list,
             commands::lists,
@@ -72,6 +285,39 @@ pub fn run() {
             commands::get_list_id,
             commands::create_list,
             commands::switch_list,
+            commands::delete_list,
+            commands::settings,
+            commands::set_passphrase,
+            commands::backups,
+            commands::create_backup,
+            commands::restore_backup,
+            commands::preferences,
+            commands::get_settings,
+            commands::update_settings,
+            commands::integrity,
+            commands::repair_database,
+            commands::profiles,
+            commands::switch_profile,
+            commands::create_profile,
+            commands::open_profile_file,
+            commands::transfer,
+            commands::api,
+            commands::update_api,
+            commands::regenerate_api_token,
+            commands::export_json,
+            commands::import_json,
+            commands::export_markdown,
+            commands::import_markdown,
+            commands::export_todotxt,
+            commands::import_todotxt,
+            commands::export_ics,
+            commands::import_ics,
+            commands::export_csv,
+            commands::import_csv,
+            commands::import_from,
+            commands::recover_from_backup,
+            commands::recover_open_file,
+            commands::recover_start_fresh,
         ])
         .run(tauri::generate_context!())
         .expect("Task Stack to start correctly");
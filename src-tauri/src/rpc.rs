@@ -0,0 +1,383 @@
+//! Line-delimited JSON-RPC 2.0 over a Unix socket under the app data directory,
+//! so the CLI can work through the running app instead of opening the
+//! database alongside it. The methods are the CLI's commands.
+
+use crate::formats::{self, csv::ExportOptions};
+use crate::tasks::{Task, TaskStack};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use ulid::Ulid;
+
+/// The directory in the app data directory holding the socket, which only
+/// the user may enter.
+pub const SOCKET_DIR: &str = "rpc";
+/// The socket's file name in `SOCKET_DIR`.
+pub const SOCKET_FILE: &str = "taskstack.sock";
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// The method ran and failed.
+pub const FAILED: i64 = -32000;
+/// The app is running but its database is locked or broken.
+pub const NOT_OPEN: i64 = -32001;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        RpcError::new(FAILED, message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    /// Missing for notifications, which get no response; `null` is still an id.
+    #[serde(default, deserialize_with = "present")]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+fn present<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+    jsonrpc: String,
+    id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+/// Parameters shared by the methods; each reads the ones it needs.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Params {
+    /// The list to work on, by id or name; for `switch`, the one to switch to.
+    list: Option<String>,
+    /// The task for `done` and `defer`, instead of the top of the stack.
+    id: Option<Ulid>,
+    title: Option<String>,
+    description: Option<String>,
+    /// Every task for `ls`, every list for a JSON or CSV `export`.
+    all: bool,
+    format: Option<String>,
+}
+
+/// Each list with its task counts and whether it's the current one.
+pub async fn list_summaries(stack: &TaskStack) -> Result<Value, String> {
+    let lists = stack.get_lists().await?;
+    let counts = stack.count_tasks().await?;
+    let current = stack.get_list_id();
+    Ok(json!(lists
+        .iter()
+        .map(|list| {
+            let count = counts.get(&list.id).copied().unwrap_or_default();
+            json!({
+                "id": list.id,
+                "name": list.name,
+                "created_at": list.created_at,
+                "active": count.active,
+                "total": count.total,
+                "current": list.id == current,
+            })
+        })
+        .collect::<Vec<_>>()))
+}
+
+/// The task named by `id`, or the top of the stack.
+async fn target(stack: &TaskStack, id: Option<Ulid>) -> Result<Option<Task>, String> {
+    match id {
+        Some(id) => stack.find_task(&id).await.map(Some),
+        None => stack.first_active().await,
+    }
+}
+
+/// Runs `method` on `stack`. Methods working on a task give `null` when the
+/// stack is empty.
+pub async fn call(stack: &TaskStack, method: &str, params: Value) -> Result<Value, RpcError> {
+    let params: Params = match params {
+        Value::Null => Params::default(),
+        params => serde_json::from_value(params)
+            .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?,
+    };
+    let scoped = match &params.list {
+        Some(list) if method != "switch" => stack.for_list(stack.find_list(list).await?),
+        _ => stack.for_list(stack.get_list_id()),
+    };
+
+    match method {
+        "push" => {
+            let title = params
+                .title
+                .map(|title| title.trim().to_string())
+                .filter(|title| !title.is_empty())
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "push needs a title"))?;
+            Ok(json!(scoped.push(title, params.description).await?))
+        }
+        "next" => Ok(json!(scoped.first_active().await?)),
+        "done" => match target(&scoped, params.id).await? {
+            Some(task) => Ok(json!(scoped.complete_task(task.id).await?)),
+            None => Ok(Value::Null),
+        },
+        "defer" => match target(&scoped, params.id).await? {
+            Some(task) => {
+                scoped.move_to_end(task.id).await?;
+                Ok(json!(task))
+            }
+            None => Ok(Value::Null),
+        },
+        "ls" => {
+            let tasks = if params.all {
+                scoped.get_tasks().await?
+            } else {
+                scoped.get_current_tasks().await?
+            };
+            Ok(json!(tasks))
+        }
+        "lists" => Ok(list_summaries(stack).await?),
+        "switch" => {
+            let list = params
+                .list
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "switch needs a list name or id"))?;
+            let list_id = stack.find_list(&list).await?;
+            stack.set_list_id(list_id).await?;
+            Ok(json!({ "id": list_id }))
+        }
+        "export" => {
+            let current = || scoped.export_list(scoped.get_list_id());
+            let contents = match params.format.as_deref().unwrap_or("json") {
+                "json" => {
+                    let mut document = scoped.export().await?;
+                    // Like the other formats, only the list worked on unless all are asked for
+                    if !params.all {
+                        document
+                            .lists
+                            .retain(|list| list.id == scoped.get_list_id());
+                    }
+                    formats::json::to_string(&document)?
+                }
+                "markdown" => formats::markdown::export(&current().await?),
+                "todotxt" => formats::todotxt::export(&current().await?),
+                "ics" => formats::ical::export(&current().await?),
+                "csv" => {
+                    let lists = if params.all {
+                        scoped.export().await?.lists
+                    } else {
+                        vec![current().await?]
+                    };
+                    formats::csv::export(&lists, &ExportOptions::default())?.0
+                }
+                format => {
+                    return Err(RpcError::new(
+                        INVALID_PARAMS,
+                        format!("Unknown format {format}"),
+                    ))
+                }
+            };
+            Ok(json!(contents))
+        }
+        method => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method {method}"),
+        )),
+    }
+}
+
+/// Answers one request line, using `stack` unless the database isn't open.
+/// Notifications are run but get no answer.
+pub async fn handle(stack: Option<&TaskStack>, line: &str) -> Option<String> {
+    let (id, outcome) = match serde_json::from_str::<Value>(line) {
+        Err(e) => (
+            Some(Value::Null),
+            Err(RpcError::new(PARSE_ERROR, e.to_string())),
+        ),
+        Ok(value) => match serde_json::from_value::<Request>(value) {
+            Err(e) => (
+                Some(Value::Null),
+                Err(RpcError::new(INVALID_REQUEST, e.to_string())),
+            ),
+            Ok(request) => match stack {
+                Some(stack) => (
+                    request.id,
+                    call(stack, &request.method, request.params).await,
+                ),
+                None => (
+                    request.id,
+                    Err(RpcError::new(NOT_OPEN, "The database isn't open")),
+                ),
+            },
+        },
+    };
+    let (result, error) = match outcome {
+        Ok(result) => (Some(result), None),
+        Err(error) => (None, Some(error)),
+    };
+    let response = Response {
+        jsonrpc: "2.0".to_string(),
+        id: id?,
+        result,
+        error,
+    };
+    Some(serde_json::to_string(&response).expect("Response to serialize"))
+}
+
+#[cfg(unix)]
+mod socket {
+    use super::{handle, Response, RpcError, FAILED, SOCKET_DIR, SOCKET_FILE};
+    use crate::tasks::TaskStack;
+    use serde_json::{json, Value};
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use tauri::{AppHandle, Manager};
+
+    /// Listens on the socket for as long as the app runs, replacing one left
+    /// behind by an earlier run.
+    pub fn serve(app: &AppHandle, app_data_dir: &Path) -> Result<(), String> {
+        // Only this user may drive the app. The socket is created with the
+        // umask's mode, so it's bound in a directory no one else can enter
+        let dir = app_data_dir.join(SOCKET_DIR);
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)
+            .map_err(|e| e.to_string())?;
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).map_err(|e| e.to_string())?;
+        // Left in the app data directory itself by earlier versions
+        let _ = fs::remove_file(app_data_dir.join(SOCKET_FILE));
+        let path = dir.join(SOCKET_FILE);
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+        }
+        let listener = UnixListener::bind(&path).map_err(|e| e.to_string())?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+
+        let app = app.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let app = app.clone();
+                        std::thread::spawn(move || answer(&app, stream));
+                    }
+                    Err(e) => eprintln!("Failed to accept an RPC connection: {}", e),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn answer(app: &AppHandle, stream: UnixStream) {
+        let mut writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(e) => {
+                eprintln!("Failed to answer an RPC connection: {}", e);
+                return;
+            }
+        };
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let stack = app.try_state::<TaskStack>();
+            let response = tauri::async_runtime::block_on(handle(stack.as_deref(), &line));
+            if let Some(response) = response {
+                if writeln!(writer, "{response}").is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// A connection to the running app.
+    pub struct Client {
+        reader: BufReader<UnixStream>,
+        writer: UnixStream,
+        next_id: u64,
+    }
+
+    impl Client {
+        /// Nothing when the app isn't running.
+        pub fn connect(app_data_dir: &Path) -> Option<Self> {
+            let stream =
+                UnixStream::connect(app_data_dir.join(SOCKET_DIR).join(SOCKET_FILE)).ok()?;
+            let writer = stream.try_clone().ok()?;
+            Some(Self {
+                reader: BufReader::new(stream),
+                writer,
+                next_id: 1,
+            })
+        }
+
+        pub fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+            let failed = |e: std::io::Error| RpcError {
+                code: FAILED,
+                message: format!("Lost the connection to Task Stack: {e}"),
+            };
+            let id = self.next_id;
+            self.next_id += 1;
+            let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+            writeln!(self.writer, "{request}").map_err(failed)?;
+
+            let mut line = String::new();
+            self.reader.read_line(&mut line).map_err(failed)?;
+            let response: Response = serde_json::from_str(&line).map_err(|e| RpcError {
+                code: FAILED,
+                message: format!("Unexpected answer from Task Stack: {e}"),
+            })?;
+            match response.error {
+                Some(error) => Err(error),
+                None => Ok(response.result.unwrap_or_default()),
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use socket::{serve, Client};
+
+/// Named pipes aren't supported, so the CLI opens the database itself.
+#[cfg(not(unix))]
+pub fn serve(_app: &tauri::AppHandle, _app_data_dir: &std::path::Path) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub struct Client;
+
+#[cfg(not(unix))]
+impl Client {
+    pub fn connect(_app_data_dir: &std::path::Path) -> Option<Self> {
+        None
+    }
+
+    pub fn call(&mut self, _method: &str, _params: Value) -> Result<Value, RpcError> {
+        Err(RpcError::new(
+            NOT_OPEN,
+            "Task Stack can't be reached on this platform",
+        ))
+    }
+}
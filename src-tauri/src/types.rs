@@ -1,3 +1,5 @@
+use crate::settings::Settings;
+use crate::tasks::TaskStack;
 use serde::Serialize;
 
 #[derive(Clone, Debug, Serialize)]
@@ -8,6 +10,17 @@ pub struct Notification {
     pub duration: Option<u32>,
 }
 
+impl Notification {
+    /// Shown for as long as `settings` say.
+    pub fn new(settings: &Settings, message: String, notification_type: &str) -> Self {
+        Self {
+            message,
+            notification_type: notification_type.to_string(),
+            duration: Some(settings.notification_duration_ms),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct PageResponse {
     pub updates: Vec<DomUpdate>,
@@ -39,19 +52,28 @@ impl PageResponse {
         }
     }
 
-    pub fn with_notification(
+    /// `update` with a notification shown for as long as the stack's settings say.
+    pub fn notify(
+        stack: &TaskStack,
         update: DomUpdate,
         message: String,
         notification_type: &str,
-        duration: Option<u32>,
     ) -> Self {
         Self {
             updates: vec![update],
-            notification: Some(Notification {
+            notification: Some(Notification::new(
+                &stack.get_settings(),
                 message,
-                notification_type: notification_type.to_string(),
-                duration,
-            }),
+                notification_type,
+            )),
+        }
+    }
+
+    /// A notification alone, leaving the page as it is.
+    pub fn notification(notification: Notification) -> Self {
+        Self {
+            updates: vec![],
+            notification: Some(notification),
         }
     }
 }
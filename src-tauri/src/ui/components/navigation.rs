@@ -6,9 +6,8 @@ pub async fn navigation(current_view: &str, stack: &TaskStack) -> Node {
     let lists = stack
         .get_lists()
         .await
-        .inspect_err(|e| println!("Failed to get lists: {}", e))
+        .inspect_err(|e| eprintln!("Failed to get lists: {}", e))
         .unwrap_or_default();
-    println!("lists: {:?}", lists);
 
     html! {
         <nav class="flex items-center justify-between max-w-3xl mx-auto px-4">
@@ -40,6 +39,15 @@ pub async fn navigation(current_view: &str, stack: &TaskStack) -> Node {
                 >
                     { text!("Lists") }
                 </button>
+                <button
+                    class={format!("cursor-pointer text-gray-600 hover:text-gray-900 transition-colors {}",
+                        if current_view == "settings" { "text-blue-500" } else { "" }
+                    )}
+                    data-command="settings"
+                    data-trigger="click"
+                >
+                    { text!("Settings") }
+                </button>
             </div>
             <div id="list-selector" class="relative flex items-center">
                 <select
@@ -3,6 +3,7 @@ use html_node::{html, text, Node};
 
 pub async fn card(current_pos: usize, task: &Task, stack: &TaskStack, show_stack: bool) -> Node {
     let total_tasks = stack.size().await.unwrap_or(0);
+    let stack_depth = stack.get_settings().stack_depth;
     let remaining_tasks = if task.state == TaskState::Active {
         total_tasks.saturating_sub(current_pos)
     } else {
@@ -10,10 +11,10 @@ pub async fn card(current_pos: usize, task: &Task, stack: &TaskStack, show_stack
     };
 
     html! {
-      <div class="relative">
+      <div class="relative" id={format!("task-{}", task.id)}>
           {
               if show_stack {
-                  (1..=3.min(remaining_tasks)).rev().map(|i| {
+                  (1..=stack_depth.min(remaining_tasks)).rev().map(|i| {
                       let offset = i * 8;
                       let width_adjustment = i * 4;
                       html! {
@@ -0,0 +1,160 @@
+use crate::{
+    api::ApiConfig,
+    tasks::TaskStack,
+    ui::components::{navigation, notification},
+};
+use html_node::{html, text, Node};
+
+const ENDPOINTS: [(&str, &str); 8] = [
+    ("GET /lists", "Every list with its task counts"),
+    (
+        "GET /tasks",
+        "The current tasks; add ?all=true for every task",
+    ),
+    ("GET /tasks/current", "The task at the top of the stack"),
+    ("POST /tasks", "Push { title, description? } onto the stack"),
+    ("POST /tasks/{id}/complete", "Complete a task"),
+    (
+        "POST /tasks/{id}/defer",
+        "Move a task to the end of the stack",
+    ),
+    (
+        "POST /tasks/{id}/move",
+        "Move a task to { position }, counting from 0",
+    ),
+    ("PUT /tasks/order", "Put the tasks in the order of { ids }"),
+];
+
+/// `status` is the port being served, or why the server couldn't start.
+pub async fn render(
+    stack: &TaskStack,
+    config: &ApiConfig,
+    status: Result<Option<u16>, String>,
+) -> Node {
+    let example = format!(
+        "curl -H 'Authorization: Bearer {}' http://127.0.0.1:{}/tasks/current",
+        config.token, config.port
+    );
+
+    html! {
+        <div class="min-h-screen bg-gray-50 py-8" data-view="api">
+            { notification::render() }
+            <div class="max-w-3xl mx-auto px-4">
+                <header class="text-center mb-12">
+                    <h1 class="text-4xl font-bold text-gray-900">{ text!("Local API") }</h1>
+                    <p class="mt-2 text-gray-600">{ text!("Let scripts on this computer read and change your tasks over HTTP") }</p>
+                </header>
+                { navigation::navigation("settings", stack).await }
+                <main class="mt-8 flex flex-col gap-8">
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-6">
+                        <div class="flex items-center justify-between">
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Server") }</h2>
+                            {
+                                match &status {
+                                    Ok(Some(port)) => html! {
+                                        <span class="inline-flex items-center px-2.5 py-0.5 rounded-full text-xs font-medium bg-green-100 text-green-800">
+                                            { text!("Listening on 127.0.0.1:{}", port) }
+                                        </span>
+                                    },
+                                    Ok(None) => html! {
+                                        <span class="inline-flex items-center px-2.5 py-0.5 rounded-full text-xs font-medium bg-gray-100 text-gray-800">
+                                            { text!("Off") }
+                                        </span>
+                                    },
+                                    Err(_) => html! {
+                                        <span class="inline-flex items-center px-2.5 py-0.5 rounded-full text-xs font-medium bg-red-100 text-red-800">
+                                            { text!("Not running") }
+                                        </span>
+                                    },
+                                }
+                            }
+                        </div>
+                        {
+                            match &status {
+                                Err(error) => html! {
+                                    <p class="text-sm text-red-600">{ text!("The server couldn't start: {}", error) }</p>
+                                },
+                                _ => Node::default(),
+                            }
+                        }
+                        <form
+                            id="api-form"
+                            data-command="update_api"
+                            data-trigger="submit"
+                            data-payload="{
+                                enabled: $event.target.enabled.checked,
+                                port: $event.target.port.valueAsNumber
+                            }"
+                        >
+                            <div class="mb-4 flex items-center gap-2">
+                                {
+                                    if config.enabled {
+                                        html! { <input type="checkbox" name="enabled" id="enabled" checked="" class="rounded border-gray-300 text-blue-600 focus:ring-blue-500" /> }
+                                    } else {
+                                        html! { <input type="checkbox" name="enabled" id="enabled" class="rounded border-gray-300 text-blue-600 focus:ring-blue-500" /> }
+                                    }
+                                }
+                                <label for="enabled" class="text-sm font-medium text-gray-700">{ text!("Serve the API on localhost") }</label>
+                            </div>
+                            <div class="mb-4">
+                                <label for="port" class="block text-sm font-medium text-gray-700">{ text!("Port") }</label>
+                                <input
+                                    type="number"
+                                    name="port"
+                                    id="port"
+                                    min="1024"
+                                    max="65535"
+                                    step="1"
+                                    value={config.port.to_string()}
+                                    class="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                    required=""
+                                />
+                            </div>
+                            <button
+                                type="submit"
+                                class="w-full flex justify-center py-2 px-4 border border-transparent rounded-md shadow-sm text-sm font-medium text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                            >
+                                { text!("Save") }
+                            </button>
+                        </form>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-4">
+                        <h2 class="text-lg font-medium text-gray-900">{ text!("Token") }</h2>
+                        <p class="text-sm text-gray-600">
+                            { text!("Send it with every request as an Authorization: Bearer header. Anyone with the token can change your tasks, so regenerate it if it leaks.") }
+                        </p>
+                        <input
+                            type="text"
+                            readonly=""
+                            value={config.token.clone()}
+                            class="block w-full rounded-md border-gray-300 bg-gray-50 font-mono text-sm shadow-sm"
+                        />
+                        <button
+                            data-command="regenerate_api_token"
+                            class="w-full flex justify-center py-2 px-4 border border-gray-300 rounded-md shadow-sm text-sm font-medium text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Regenerate Token") }
+                        </button>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-4">
+                        <h2 class="text-lg font-medium text-gray-900">{ text!("Endpoints") }</h2>
+                        <p class="text-sm text-gray-600">
+                            { text!("Everything speaks JSON and works on the current list unless given ?list= with a list's name or id. Failed requests answer with an error field saying why.") }
+                        </p>
+                        <dl class="divide-y divide-gray-200">
+                            {
+                                ENDPOINTS.iter().map(|(endpoint, description)| html! {
+                                    <div class="py-2 flex justify-between gap-4">
+                                        <dt class="font-mono text-sm text-gray-900">{ text!("{}", endpoint) }</dt>
+                                        <dd class="text-sm text-gray-600 text-right">{ text!("{}", description) }</dd>
+                                    </div>
+                                }).collect::<Vec<_>>()
+                            }
+                        </dl>
+                        <pre class="bg-gray-50 rounded-md p-3 text-xs text-gray-800 overflow-x-auto">{ text!("{}", example) }</pre>
+                    </section>
+                </main>
+            </div>
+        </div>
+    }
+}
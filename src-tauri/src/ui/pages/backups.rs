@@ -0,0 +1,100 @@
+use crate::{
+    backup,
+    tasks::TaskStack,
+    ui::components::{navigation, notification},
+};
+use html_node::{html, text, Node};
+use std::path::Path;
+
+pub async fn render(stack: &TaskStack, backup_dir: &Path) -> Node {
+    let passphrase = stack.get_passphrase();
+    let backups = match stack.get_db_path() {
+        Some(db_path) => backup::list(&db_path, passphrase.as_deref(), backup_dir).await,
+        None => Ok(vec![]),
+    }
+    .inspect_err(|e| eprintln!("Failed to list backups: {}", e))
+    .unwrap_or_default();
+
+    html! {
+        <div class="min-h-screen bg-gray-50 py-8" data-view="backups">
+            { notification::render() }
+            <div class="max-w-3xl mx-auto px-4">
+                <header class="text-center mb-12">
+                    <h1 class="text-4xl font-bold text-gray-900">{ text!("Backups") }</h1>
+                    <p class="mt-2 text-gray-600">{ text!("Snapshots are taken at startup and every few hours") }</p>
+                </header>
+                { navigation::navigation("settings", stack).await }
+                <main class="mt-8 flex flex-col gap-4">
+                    <div class="flex justify-end">
+                        <button
+                            data-command="create_backup"
+                            class="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Back Up Now") }
+                        </button>
+                    </div>
+                    <div class="bg-white rounded-xl shadow-sm overflow-hidden">
+                        <div class="divide-y divide-gray-200">
+                            {
+                                if backups.is_empty() {
+                                    html! {
+                                        <div class="p-6 text-center text-gray-500">
+                                            { text!("No backups yet.") }
+                                        </div>
+                                    }
+                                } else {
+                                    html! {
+                                        <div>
+                                            {
+                                                backups.into_iter().map(|backup| {
+                                                    html! {
+                                                        <div class="p-6 flex items-center justify-between">
+                                                            <div>
+                                                                <h3 class="text-lg font-medium text-gray-900">
+                                                                    { text!("{}", backup.created_at.format("%b %d, %Y %H:%M UTC")) }
+                                                                </h3>
+                                                                {
+                                                                    match &backup.summary {
+                                                                        Ok(summary) => html! {
+                                                                            <p class="mt-1 text-sm text-gray-500">
+                                                                                { text!("{} lists, {} tasks", summary.list_count, summary.task_count) }
+                                                                            </p>
+                                                                        },
+                                                                        Err(error) => html! {
+                                                                            <p class="mt-1 text-sm text-red-600">
+                                                                                { text!("Can't be restored: {}", error) }
+                                                                            </p>
+                                                                        },
+                                                                    }
+                                                                }
+                                                            </div>
+                                                            {
+                                                                if backup.summary.is_ok() {
+                                                                    html! {
+                                                                        <button
+                                                                            class="inline-flex items-center px-3 py-1.5 border border-gray-300 text-xs font-medium rounded-full shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                                                                            data-command="restore_backup"
+                                                                            data-payload={serde_json::json!({ "name": backup.file_name() }).to_string()}
+                                                                        >
+                                                                            { text!("Restore") }
+                                                                        </button>
+                                                                    }
+                                                                } else {
+                                                                    Node::default()
+                                                                }
+                                                            }
+                                                        </div>
+                                                    }
+                                                }).collect::<Vec<_>>()
+                                            }
+                                        </div>
+                                    }
+                                }
+                            }
+                        </div>
+                    </div>
+                </main>
+            </div>
+        </div>
+    }
+}
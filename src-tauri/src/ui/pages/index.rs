@@ -1,12 +1,27 @@
 use crate::{
-    tasks::TaskStack,
+    tasks::{TaskList, TaskStack},
     ui::components::{navigation, notification, task},
 };
 use html_node::{html, text, Node};
 
+/// A task filled in for the user to check before adding it, e.g. from a link.
+#[derive(Default)]
+pub struct Draft<'a> {
+    pub title: &'a str,
+    pub description: &'a str,
+    /// Another list to add it to, which becomes the current one once it's added.
+    pub list: Option<&'a TaskList>,
+}
+
 pub async fn render(stack: &TaskStack) -> Node {
+    render_draft(stack, &Draft::default()).await
+}
+
+/// The page with its form filled in from `draft`.
+pub async fn render_draft(stack: &TaskStack, draft: &Draft<'_>) -> Node {
     let task = stack.first_active().await.unwrap_or(None);
     let total_tasks = stack.size().await.unwrap_or(0);
+    let stack_depth = stack.get_settings().stack_depth;
     let current_pos = if let Some(ref task) = task {
         stack.find_task_position(task).await.unwrap_or(0) + 1
     } else {
@@ -14,7 +29,7 @@ pub async fn render(stack: &TaskStack) -> Node {
     };
 
     html! {
-        <div class="min-h-screen bg-gray-50 py-8">
+        <div class="min-h-screen bg-gray-50 py-8" data-view="index">
             { notification::render() }
             <div class="max-w-3xl mx-auto px-4">
                 <header class="text-center mb-12">
@@ -23,7 +38,7 @@ pub async fn render(stack: &TaskStack) -> Node {
                 </header>
                 { navigation::navigation("index", stack).await }
                 <main class="flex flex-col gap-12">
-                    <div class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-12" style={format!("padding-bottom: {}px;", 3.min(total_tasks.saturating_sub(1)) * 2 + 24)}>
+                    <div class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-12" style={format!("padding-bottom: {}px;", stack_depth.min(total_tasks.saturating_sub(1)) * 2 + 24)}>
                         <div id="task-list" class="space-y-4">
                         {
                             if let Some(task) = task {
@@ -38,6 +53,17 @@ pub async fn render(stack: &TaskStack) -> Node {
                             data-command="add_task"
                             data-trigger="submit"
                         >
+                            {
+                                match draft.list {
+                                    Some(list) => html! {
+                                        <p class="mb-4 text-sm text-gray-600">
+                                            <input type="hidden" name="listId" value={list.id.to_string()} />
+                                            { text!("Adding to {}", list.name) }
+                                        </p>
+                                    },
+                                    None => Node::default(),
+                                }
+                            }
                             <div class="mb-4">
                                 <label for="title" class="block text-sm font-medium text-gray-700">Task Title</label>
                                 <input
@@ -45,12 +71,13 @@ pub async fn render(stack: &TaskStack) -> Node {
                                     name="title"
                                     id="title"
                                     class="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                    value={draft.title}
                                     required
                                 />
                             </div>
                             <div class="mb-4">
                                 <label for="description" class="block text-sm font-medium text-gray-700">{ text!("Description (optional)") }</label>
-                                <textarea name="description" id="description" rows="3" class="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500" placeholder="Add any additional details about the task..."/>
+                                <textarea name="description" id="description" rows="3" class="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500" placeholder="Add any additional details about the task...">{ text!("{}", draft.description) }</textarea>
                             </div>
                             <button
                                 type="submit"
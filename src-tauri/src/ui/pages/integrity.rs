@@ -0,0 +1,92 @@
+use crate::{
+    tasks::TaskStack,
+    ui::components::{navigation, notification},
+};
+use html_node::{html, text, Node};
+
+pub async fn render(stack: &TaskStack) -> Node {
+    let problems = stack.check_integrity().await;
+    let repairable = problems
+        .as_ref()
+        .map(|problems| problems.iter().any(|problem| problem.is_repairable()))
+        .unwrap_or(false);
+
+    html! {
+        <div class="min-h-screen bg-gray-50 py-8" data-view="integrity">
+            { notification::render() }
+            <div class="max-w-3xl mx-auto px-4">
+                <header class="text-center mb-12">
+                    <h1 class="text-4xl font-bold text-gray-900">{ text!("Integrity") }</h1>
+                    <p class="mt-2 text-gray-600">{ text!("Problems found in your task database") }</p>
+                </header>
+                { navigation::navigation("settings", stack).await }
+                <main class="mt-8 flex flex-col gap-4">
+                    <div class="bg-white rounded-xl shadow-sm overflow-hidden">
+                        <div class="divide-y divide-gray-200">
+                            {
+                                match problems {
+                                    Err(error) => html! {
+                                        <div class="p-6 text-red-600">
+                                            { text!("The check could not run: {}", error) }
+                                        </div>
+                                    },
+                                    Ok(problems) if problems.is_empty() => html! {
+                                        <div class="p-6 text-center text-gray-500">
+                                            { text!("No problems found.") }
+                                        </div>
+                                    },
+                                    Ok(problems) => html! {
+                                        <ul class="divide-y divide-gray-200">
+                                            {
+                                                problems.into_iter().map(|problem| {
+                                                    html! {
+                                                        <li class="p-4 text-sm text-gray-700">
+                                                            { text!("{}", problem) }
+                                                            {
+                                                                if problem.is_repairable() {
+                                                                    Node::default()
+                                                                } else {
+                                                                    html! {
+                                                                        <p class="mt-1 text-xs text-red-600">
+                                                                            { text!("Can't be repaired automatically. Restore a backup instead.") }
+                                                                        </p>
+                                                                    }
+                                                                }
+                                                            }
+                                                        </li>
+                                                    }
+                                                }).collect::<Vec<_>>()
+                                            }
+                                        </ul>
+                                    },
+                                }
+                            }
+                        </div>
+                    </div>
+                    <div class="flex justify-end gap-2">
+                        <button
+                            data-command="integrity"
+                            class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Check Again") }
+                        </button>
+                        {
+                            if repairable {
+                                html! {
+                                    <button
+                                        data-command="repair_database"
+                                        class="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                                    >
+                                        { text!("Repair") }
+                                    </button>
+                                }
+                            } else {
+                                Node::default()
+                            }
+                        }
+                    </div>
+                </main>
+            </div>
+        </div>
+    }
+}
@@ -6,7 +6,7 @@ pub async fn render(stack: &TaskStack) -> Node {
     let tasks = stack.get_tasks().await.unwrap_or(vec![]);
 
     html! {
-        <div class="min-h-screen bg-gray-50 py-8">
+        <div class="min-h-screen bg-gray-50 py-8" data-view="list">
             { notification::render() }
             <div class="max-w-3xl mx-auto px-4">
                 <header class="text-center mb-12">
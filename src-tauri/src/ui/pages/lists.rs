@@ -1,61 +1,23 @@
-use crate::{tasks::TaskStack, ui::components::navigation};
+use crate::{
+    tasks::TaskStack,
+    ui::components::{navigation, notification},
+};
 use html_node::{html, text, Node};
-use std::collections::HashMap;
-use ulid::Ulid;
 
 pub async fn render(stack: &TaskStack) -> Node {
     let lists = stack.get_lists().await.unwrap_or_default();
 
-    // Get task counts for all lists in a single query
-    let db = stack.get_db();
+    let counts = stack
+        .count_tasks()
+        .await
+        .inspect_err(|e| eprintln!("Failed to count tasks: {}", e))
+        .unwrap_or_default();
 
-    // Create a HashMap to store counts for each list
-    let mut active_counts: HashMap<Ulid, usize> = HashMap::new();
-    let mut total_counts: HashMap<Ulid, usize> = HashMap::new();
-
-    // Get active task counts for all lists
-    if let Ok(conn) = db.connect() {
-        // Query for active tasks
-        if let Ok(mut stmt) = conn
-            .prepare("SELECT list_id, COUNT(*) FROM tasks WHERE state = 'Active' GROUP BY list_id")
-            .await
-        {
-            if let Ok(mut rows) = stmt.query(libsql::params![]).await {
-                while let Ok(Some(row)) = rows.next().await {
-                    let list_id_str: Result<String, _> = row.get(0);
-                    let count: Result<i64, _> = row.get(1);
-
-                    if let (Ok(list_id_str), Ok(count)) = (list_id_str, count) {
-                        if let Ok(list_id) = Ulid::from_string(&list_id_str) {
-                            active_counts.insert(list_id, count as usize);
-                        }
-                    }
-                }
-            }
-        }
-
-        // Query for total tasks
-        if let Ok(mut stmt) = conn
-            .prepare("SELECT list_id, COUNT(*) FROM tasks GROUP BY list_id")
-            .await
-        {
-            if let Ok(mut rows) = stmt.query(libsql::params![]).await {
-                while let Ok(Some(row)) = rows.next().await {
-                    let list_id_str: Result<String, _> = row.get(0);
-                    let count: Result<i64, _> = row.get(1);
-
-                    if let (Ok(list_id_str), Ok(count)) = (list_id_str, count) {
-                        if let Ok(list_id) = Ulid::from_string(&list_id_str) {
-                            total_counts.insert(list_id, count as usize);
-                        }
-                    }
-                }
-            }
-        }
-    }
+    let deletable = lists.len() > 1;
 
     html! {
-        <div class="min-h-screen bg-gray-50 py-8">
+        <div class="min-h-screen bg-gray-50 py-8" data-view="lists">
+            { notification::render() }
             <div class="max-w-3xl mx-auto px-4">
                 <header class="text-center mb-12">
                     <h1 class="text-4xl font-bold text-gray-900">{ text!("Lists Overview") }</h1>
@@ -77,11 +39,7 @@ pub async fn render(stack: &TaskStack) -> Node {
                                         <div>
                                             {
                                                 lists.into_iter().map(|list| {
-                                                    let list_id = list.id;
-
-                                                    // Get counts from our HashMaps
-                                                    let active_count = active_counts.get(&list_id).copied().unwrap_or(0);
-                                                    let total_count = total_counts.get(&list_id).copied().unwrap_or(0);
+                                                    let count = counts.get(&list.id).copied().unwrap_or_default();
 
                                                     html! {
                                                         <div class="p-6 hover:bg-gray-50 transition-colors">
@@ -94,10 +52,10 @@ pub async fn render(stack: &TaskStack) -> Node {
                                                                 </div>
                                                                 <div class="text-right">
                                                                     <p class="text-sm font-medium text-gray-900">
-                                                                        { text!("{} active tasks", active_count) }
+                                                                        { text!("{} active tasks", count.active) }
                                                                     </p>
                                                                     <p class="mt-1 text-sm text-gray-500">
-                                                                        { text!("{} total tasks", total_count) }
+                                                                        { text!("{} total tasks", count.total) }
                                                                     </p>
                                                                 </div>
                                                             </div>
@@ -110,6 +68,22 @@ pub async fn render(stack: &TaskStack) -> Node {
                                                                 >
                                                                     { text!("Switch to list") }
                                                                 </button>
+                                                                {
+                                                                    if deletable {
+                                                                        html! {
+                                                                            <button
+                                                                                class="ml-2 inline-flex items-center px-3 py-1.5 border border-gray-300 text-xs font-medium rounded-full shadow-sm text-gray-700 bg-white hover:bg-red-50 hover:text-red-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-red-500 cursor-pointer"
+                                                                                data-command="delete_list"
+                                                                                data-trigger="click"
+                                                                                data-payload={{ format!("{{ listId: '{}' }}", list.id) }}
+                                                                            >
+                                                                                { text!("Delete list and its tasks") }
+                                                                            </button>
+                                                                        }
+                                                                    } else {
+                                                                        Node::default()
+                                                                    }
+                                                                }
                                                             </div>
                                                         </div>
                                                     }
@@ -1,3 +1,12 @@
+pub mod api;
+pub mod backups;
 pub mod index;
+pub mod integrity;
 pub mod list;
 pub mod lists;
+pub mod preferences;
+pub mod profiles;
+pub mod recovery;
+pub mod settings;
+pub mod transfer;
+pub mod unlock;
@@ -0,0 +1,84 @@
+use crate::{
+    tasks::TaskStack,
+    ui::components::{navigation, notification},
+};
+use html_node::{html, text, Node};
+
+pub async fn render(stack: &TaskStack) -> Node {
+    let settings = stack.get_settings();
+
+    html! {
+        <div class="min-h-screen bg-gray-50 py-8" data-view="preferences">
+            { notification::render() }
+            <div class="max-w-3xl mx-auto px-4">
+                <header class="text-center mb-12">
+                    <h1 class="text-4xl font-bold text-gray-900">{ text!("Preferences") }</h1>
+                    <p class="mt-2 text-gray-600">{ text!("Tune how Task Stack behaves") }</p>
+                </header>
+                { navigation::navigation("settings", stack).await }
+                <main class="mt-8">
+                    <form
+                        id="preferences-form"
+                        class="bg-white rounded-xl shadow-sm p-6"
+                        data-command="update_settings"
+                        data-trigger="submit"
+                        data-payload="{ settings: {
+                            completed_window_hours: $event.target.completed_window_hours.valueAsNumber,
+                            stack_depth: $event.target.stack_depth.valueAsNumber,
+                            notification_duration_ms: Math.round($event.target.notification_duration.valueAsNumber * 1000)
+                        } }"
+                    >
+                        <div class="mb-4">
+                            <label for="completed_window_hours" class="block text-sm font-medium text-gray-700">{ text!("Keep completed tasks current for (hours)") }</label>
+                            <input
+                                type="number"
+                                name="completed_window_hours"
+                                id="completed_window_hours"
+                                min="0"
+                                max="720"
+                                step="1"
+                                value={settings.completed_window_hours.to_string()}
+                                class="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                        </div>
+                        <div class="mb-4">
+                            <label for="stack_depth" class="block text-sm font-medium text-gray-700">{ text!("Cards shown behind the current task") }</label>
+                            <input
+                                type="number"
+                                name="stack_depth"
+                                id="stack_depth"
+                                min="0"
+                                max="10"
+                                step="1"
+                                value={settings.stack_depth.to_string()}
+                                class="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                        </div>
+                        <div class="mb-4">
+                            <label for="notification_duration" class="block text-sm font-medium text-gray-700">{ text!("Show notifications for (seconds)") }</label>
+                            <input
+                                type="number"
+                                name="notification_duration"
+                                id="notification_duration"
+                                min="0.5"
+                                max="60"
+                                step="0.5"
+                                value={(settings.notification_duration_ms as f64 / 1000.0).to_string()}
+                                class="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                        </div>
+                        <button
+                            type="submit"
+                            class="w-full flex justify-center py-2 px-4 border border-transparent rounded-md shadow-sm text-sm font-medium text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Save Preferences") }
+                        </button>
+                    </form>
+                </main>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{profiles::Profiles, ui::components::notification};
+use html_node::{html, text, Node};
+
+pub fn render(profiles: &Profiles) -> Node {
+    html! {
+        <div class="min-h-screen bg-gray-50 py-8" data-view="profiles">
+            { notification::render() }
+            <div class="max-w-3xl mx-auto px-4">
+                <header class="text-center mb-12">
+                    <h1 class="text-4xl font-bold text-gray-900">{ text!("Profiles") }</h1>
+                    <p class="mt-2 text-gray-600">{ text!("Each profile keeps its tasks in its own database file") }</p>
+                </header>
+                <div class="flex justify-center">
+                    <button
+                        data-command="start"
+                        class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                    >
+                        { text!("Back to Tasks") }
+                    </button>
+                </div>
+                <main class="mt-8 flex flex-col gap-8">
+                    <section class="bg-white rounded-xl shadow-sm overflow-hidden">
+                        <div class="divide-y divide-gray-200">
+                            {
+                                profiles.profiles.iter().map(|profile| {
+                                    let current = profile.name == profiles.current;
+                                    html! {
+                                        <div class="p-6 flex items-center justify-between">
+                                            <div>
+                                                <h3 class="text-lg font-medium text-gray-900">{ text!("{}", profile.name) }</h3>
+                                                <p class="mt-1 text-sm text-gray-500">{ text!("{}", profile.path.display()) }</p>
+                                            </div>
+                                            {
+                                                if current {
+                                                    html! {
+                                                        <span class="inline-flex items-center px-2.5 py-0.5 rounded-full text-xs font-medium bg-green-100 text-green-800">
+                                                            { text!("Current") }
+                                                        </span>
+                                                    }
+                                                } else {
+                                                    html! {
+                                                        <button
+                                                            class="inline-flex items-center px-3 py-1.5 border border-transparent text-xs font-medium rounded-full shadow-sm text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                                                            data-command="switch_profile"
+                                                            data-payload={serde_json::json!({ "name": profile.name }).to_string()}
+                                                        >
+                                                            { text!("Switch") }
+                                                        </button>
+                                                    }
+                                                }
+                                            }
+                                        </div>
+                                    }
+                                }).collect::<Vec<_>>()
+                            }
+                        </div>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6">
+                        <h2 class="text-lg font-medium text-gray-900">{ text!("New profile") }</h2>
+                        <form
+                            id="create-profile-form"
+                            class="mt-4 flex gap-2"
+                            data-command="create_profile"
+                            data-trigger="submit"
+                        >
+                            <input
+                                type="text"
+                                name="name"
+                                placeholder="Work"
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                            <button
+                                type="submit"
+                                class="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                            >
+                                { text!("Create") }
+                            </button>
+                        </form>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6">
+                        <h2 class="text-lg font-medium text-gray-900">{ text!("Open a database file") }</h2>
+                        <p class="mt-1 text-sm text-gray-600">{ text!("The file is added as a profile named after it") }</p>
+                        <form
+                            id="open-profile-form"
+                            class="mt-4 flex gap-2"
+                            data-command="open_profile_file"
+                            data-trigger="submit"
+                        >
+                            <input
+                                type="text"
+                                name="path"
+                                placeholder="/path/to/tasks.db"
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                            <button
+                                type="submit"
+                                class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                            >
+                                { text!("Open") }
+                            </button>
+                        </form>
+                    </section>
+                </main>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{backup, ui::components::notification};
+use html_node::{html, text, Node};
+use std::path::Path;
+
+pub async fn render(db_path: &Path, error: &str, backup_dir: &Path) -> Node {
+    let backups = backup::list(db_path, None, backup_dir)
+        .await
+        .inspect_err(|e| eprintln!("Failed to list backups: {}", e))
+        .unwrap_or_default();
+
+    html! {
+        <div class="min-h-screen bg-gray-50 py-8">
+            { notification::render() }
+            <div class="max-w-3xl mx-auto px-4">
+                <header class="text-center mb-12">
+                    <h1 class="text-4xl font-bold text-gray-900">{ text!("Task Stack") }</h1>
+                    <p class="mt-2 text-gray-600">{ text!("Your tasks couldn't be opened") }</p>
+                </header>
+                <main class="flex flex-col gap-8">
+                    <div class="bg-red-100 border-l-4 border-red-500 text-red-700 p-4 rounded">
+                        <p class="text-sm font-medium">{ text!("{}", db_path.display()) }</p>
+                        <p class="mt-1 text-sm">{ text!("{}", error) }</p>
+                    </div>
+                    <section class="bg-white rounded-xl shadow-sm overflow-hidden">
+                        <div class="p-6">
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Restore a backup") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("The damaged file is kept next to the original, ending in .broken") }</p>
+                        </div>
+                        <div class="divide-y divide-gray-200 border-t border-gray-200">
+                            {
+                                if backups.is_empty() {
+                                    html! {
+                                        <div class="p-6 text-center text-gray-500">
+                                            { text!("No backups found.") }
+                                        </div>
+                                    }
+                                } else {
+                                    html! {
+                                        <div>
+                                            {
+                                                backups.into_iter().map(|backup| {
+                                                    let restorable = backup.encrypted || backup.summary.is_ok();
+                                                    html! {
+                                                        <div class="p-6 flex items-center justify-between">
+                                                            <div>
+                                                                <h3 class="text-sm font-medium text-gray-900">
+                                                                    { text!("{}", backup.created_at.format("%b %d, %Y %H:%M UTC")) }
+                                                                </h3>
+                                                                <p class="mt-1 text-sm text-gray-500">
+                                                                    {
+                                                                        match (&backup.summary, backup.encrypted) {
+                                                                            (Ok(summary), _) => text!("{} lists, {} tasks", summary.list_count, summary.task_count),
+                                                                            (Err(_), true) => text!("Encrypted, you'll be asked for its passphrase"),
+                                                                            (Err(error), false) => text!("Can't be restored: {}", error),
+                                                                        }
+                                                                    }
+                                                                </p>
+                                                            </div>
+                                                            {
+                                                                if restorable {
+                                                                    html! {
+                                                                        <button
+                                                                            class="inline-flex items-center px-3 py-1.5 border border-gray-300 text-xs font-medium rounded-full shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                                                                            data-command="recover_from_backup"
+                                                                            data-payload={serde_json::json!({ "name": backup.file_name() }).to_string()}
+                                                                        >
+                                                                            { text!("Restore") }
+                                                                        </button>
+                                                                    }
+                                                                } else {
+                                                                    Node::default()
+                                                                }
+                                                            }
+                                                        </div>
+                                                    }
+                                                }).collect::<Vec<_>>()
+                                            }
+                                        </div>
+                                    }
+                                }
+                            }
+                        </div>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6">
+                        <h2 class="text-lg font-medium text-gray-900">{ text!("Open a different file") }</h2>
+                        <form
+                            id="open-file-form"
+                            class="mt-4 flex gap-2"
+                            data-command="recover_open_file"
+                            data-trigger="submit"
+                        >
+                            <input
+                                type="text"
+                                name="path"
+                                placeholder="/path/to/tasks.db"
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                            <button
+                                type="submit"
+                                class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                            >
+                                { text!("Open") }
+                            </button>
+                        </form>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex items-center justify-between">
+                        <div>
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Start fresh") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("Create an empty database. The damaged one is kept alongside it.") }</p>
+                        </div>
+                        <button
+                            data-command="recover_start_fresh"
+                            class="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Start Fresh") }
+                        </button>
+                    </section>
+                </main>
+            </div>
+        </div>
+    }
+}
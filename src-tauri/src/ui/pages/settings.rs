@@ -0,0 +1,166 @@
+use crate::{
+    tasks::TaskStack,
+    ui::components::{navigation, notification},
+};
+use html_node::{html, text, Node};
+
+pub async fn render(stack: &TaskStack) -> Node {
+    let encrypted = stack.is_encrypted();
+
+    html! {
+        <div class="min-h-screen bg-gray-50 py-8" data-view="settings">
+            { notification::render() }
+            <div class="max-w-3xl mx-auto px-4">
+                <header class="text-center mb-12">
+                    <h1 class="text-4xl font-bold text-gray-900">{ text!("Settings") }</h1>
+                    <p class="mt-2 text-gray-600">{ text!("Configure how Task Stack stores your tasks") }</p>
+                </header>
+                { navigation::navigation("settings", stack).await }
+                <main class="mt-8 flex flex-col gap-8">
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-6">
+                        <div class="flex items-center justify-between">
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Encryption") }</h2>
+                            {
+                                if encrypted {
+                                    html! {
+                                        <span class="inline-flex items-center px-2.5 py-0.5 rounded-full text-xs font-medium bg-green-100 text-green-800">
+                                            { text!("Encrypted") }
+                                        </span>
+                                    }
+                                } else {
+                                    html! {
+                                        <span class="inline-flex items-center px-2.5 py-0.5 rounded-full text-xs font-medium bg-gray-100 text-gray-800">
+                                            { text!("Not encrypted") }
+                                        </span>
+                                    }
+                                }
+                            }
+                        </div>
+                        <p class="text-sm text-gray-600">
+                            { text!("Encrypting protects this profile's database at rest. You'll be asked for the passphrase every time Task Stack starts, and it cannot be recovered if lost.") }
+                        </p>
+                        <form
+                            id="passphrase-form"
+                            data-command="set_passphrase"
+                            data-trigger="submit"
+                        >
+                            <div class="mb-4">
+                                <label for="passphrase" class="block text-sm font-medium text-gray-700">{ text!("New passphrase") }</label>
+                                <input
+                                    type="password"
+                                    name="passphrase"
+                                    id="passphrase"
+                                    class="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                    required=""
+                                />
+                            </div>
+                            <div class="mb-4">
+                                <label for="confirm" class="block text-sm font-medium text-gray-700">{ text!("Confirm passphrase") }</label>
+                                <input
+                                    type="password"
+                                    name="confirm"
+                                    id="confirm"
+                                    class="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                    required=""
+                                />
+                            </div>
+                            <button
+                                type="submit"
+                                class="w-full flex justify-center py-2 px-4 border border-transparent rounded-md shadow-sm text-sm font-medium text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                            >
+                                { text!("{}", if encrypted { "Change Passphrase" } else { "Encrypt Database" }) }
+                            </button>
+                        </form>
+                        {
+                            if encrypted {
+                                html! {
+                                    <button
+                                        data-command="set_passphrase"
+                                        data-payload="{ passphrase: '', confirm: '' }"
+                                        class="w-full flex justify-center py-2 px-4 border border-gray-300 rounded-md shadow-sm text-sm font-medium text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                                    >
+                                        { text!("Remove Encryption") }
+                                    </button>
+                                }
+                            } else {
+                                Node::default()
+                            }
+                        }
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex items-center justify-between">
+                        <div>
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Profiles") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("Keep separate sets of tasks in separate files") }</p>
+                        </div>
+                        <button
+                            data-command="profiles"
+                            class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Manage Profiles") }
+                        </button>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex items-center justify-between">
+                        <div>
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Preferences") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("Stack depth, completed task window and notifications") }</p>
+                        </div>
+                        <button
+                            data-command="preferences"
+                            class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Edit Preferences") }
+                        </button>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex items-center justify-between">
+                        <div>
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Integrity") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("Check the database for damaged or inconsistent data") }</p>
+                        </div>
+                        <button
+                            data-command="integrity"
+                            class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Check Database") }
+                        </button>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex items-center justify-between">
+                        <div>
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Import & Export") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("Move lists and tasks in and out of Task Stack") }</p>
+                        </div>
+                        <button
+                            data-command="transfer"
+                            class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Import & Export") }
+                        </button>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex items-center justify-between">
+                        <div>
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Local API") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("Let scripts and other apps work with your tasks over HTTP") }</p>
+                        </div>
+                        <button
+                            data-command="api"
+                            class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Set Up API") }
+                        </button>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex items-center justify-between">
+                        <div>
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Backups") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("Browse snapshots of your tasks and restore one") }</p>
+                        </div>
+                        <button
+                            data-command="backups"
+                            class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Manage Backups") }
+                        </button>
+                    </section>
+                </main>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,396 @@
+use crate::{
+    formats::{csv::Column, Preview},
+    importers::IMPORTERS,
+    tasks::{TaskList, TaskStack},
+    ui::components::{navigation, notification},
+};
+use html_node::{html, text, Node};
+use ulid::Ulid;
+
+pub async fn render(stack: &TaskStack) -> Node {
+    let lists = stack.get_lists().await.unwrap_or_default();
+    let current_list = stack.get_list_id();
+    let column_names = Column::ALL
+        .iter()
+        .map(|column| column.name())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    html! {
+        <div class="min-h-screen bg-gray-50 py-8" data-view="transfer">
+            { notification::render() }
+            <div class="max-w-3xl mx-auto px-4">
+                <header class="text-center mb-12">
+                    <h1 class="text-4xl font-bold text-gray-900">{ text!("Import & Export") }</h1>
+                    <p class="mt-2 text-gray-600">{ text!("Move lists and tasks in and out of Task Stack") }</p>
+                </header>
+                { navigation::navigation("settings", stack).await }
+                <main class="mt-8 flex flex-col gap-8">
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-6">
+                        <div>
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("JSON") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("Every list and task, with positions, states and timestamps") }</p>
+                        </div>
+                        <form
+                            id="export-json-form"
+                            class="flex gap-2"
+                            data-command="export_json"
+                            data-trigger="submit"
+                        >
+                            <input
+                                type="text"
+                                name="path"
+                                placeholder="/path/to/tasks.json"
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                            <button
+                                type="submit"
+                                class="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                            >
+                                { text!("Export") }
+                            </button>
+                        </form>
+                        <form
+                            id="import-json-form"
+                            class="flex gap-2"
+                            data-command="import_json"
+                            data-trigger="submit"
+                        >
+                            <input
+                                type="text"
+                                name="path"
+                                placeholder="/path/to/tasks.json"
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                            <select
+                                name="mode"
+                                class="rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                            >
+                                <option value="merge">{ text!("Merge") }</option>
+                                <option value="restore">{ text!("Restore into empty") }</option>
+                            </select>
+                            <button
+                                type="submit"
+                                class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                            >
+                                { text!("Import") }
+                            </button>
+                        </form>
+                    </section>
+                    { text_format(&TextFormat {
+                        id: "markdown",
+                        name: "Markdown",
+                        description: "A checklist of the current list, or checklist items read into a list",
+                        placeholder: "/path/to/tasks.md",
+                        example: "Or paste a checklist: - [ ] Task",
+                    }, &lists, current_list) }
+                    { text_format(&TextFormat {
+                        id: "todotxt",
+                        name: "todo.txt",
+                        description: "The current list as todo.txt, or todo.txt tasks read into the lists their +project names",
+                        placeholder: "/path/to/todo.txt",
+                        example: "Or paste tasks: (A) 2024-01-31 Call Mom +Family @phone",
+                    }, &lists, current_list) }
+                    { text_format(&TextFormat {
+                        id: "ics",
+                        name: "iCalendar",
+                        description: "The current list as to-dos in an .ics file, or the to-dos from calendar and task apps read into a list",
+                        placeholder: "/path/to/tasks.ics",
+                        example: "Or paste the contents of an .ics file",
+                    }, &lists, current_list) }
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-6">
+                        <div>
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("CSV") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("Spreadsheets of tasks, with the columns you choose. Columns: {}", column_names) }</p>
+                        </div>
+                        <form
+                            id="export-csv-form"
+                            class="flex flex-col gap-2"
+                            data-command="export_csv"
+                            data-trigger="submit"
+                        >
+                            <input
+                                type="text"
+                                name="path"
+                                placeholder="/path/to/tasks.csv"
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                            <input
+                                type="text"
+                                name="columns"
+                                value={column_names.clone()}
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                            <div class="flex gap-2 items-center justify-end">
+                                <label class="text-sm text-gray-600">{ text!("Completed from") }</label>
+                                <input
+                                    type="date"
+                                    name="from"
+                                    class="rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                />
+                                <label class="text-sm text-gray-600">{ text!("to") }</label>
+                                <input
+                                    type="date"
+                                    name="to"
+                                    class="rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                />
+                                <select
+                                    name="scope"
+                                    class="rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                >
+                                    <option value="current">{ text!("Current list") }</option>
+                                    <option value="all">{ text!("All lists") }</option>
+                                </select>
+                                <button
+                                    type="submit"
+                                    class="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                                >
+                                    { text!("Export") }
+                                </button>
+                            </div>
+                        </form>
+                        <form
+                            id="import-csv-form"
+                            class="flex flex-col gap-2"
+                            data-command="import_csv"
+                            data-trigger="submit"
+                        >
+                            <input
+                                type="text"
+                                name="path"
+                                placeholder="/path/to/tasks.csv"
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                            />
+                            <textarea
+                                name="text"
+                                rows="5"
+                                placeholder="Or paste CSV with a header row"
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                            ></textarea>
+                            <input
+                                type="text"
+                                name="mapping"
+                                placeholder="Column mapping, e.g. title=Task, description=Notes, completed_at=Done"
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                            />
+                            <div class="flex gap-2 justify-end">
+                                { list_select(&lists, current_list) }
+                                <button
+                                    type="submit"
+                                    class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                                >
+                                    { text!("Import") }
+                                </button>
+                            </div>
+                        </form>
+                    </section>
+                    <section class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-6">
+                        <div>
+                            <h2 class="text-lg font-medium text-gray-900">{ text!("Other apps") }</h2>
+                            <p class="mt-1 text-sm text-gray-600">{ text!("Bring tasks over from an export file. Projects and boards become lists") }</p>
+                        </div>
+                        <ul class="text-sm text-gray-600 list-disc pl-5">
+                            {
+                                IMPORTERS.iter().map(|importer| html! {
+                                    <li>
+                                        <span class="font-medium text-gray-900">{ text!("{}", importer.name()) }</span>
+                                        { text!(": {}", importer.hint()) }
+                                    </li>
+                                }).collect::<Vec<_>>()
+                            }
+                        </ul>
+                        <form
+                            id="import-from-form"
+                            class="flex flex-col gap-2"
+                            data-command="import_from"
+                            data-trigger="submit"
+                        >
+                            <input
+                                type="text"
+                                name="path"
+                                placeholder="/path/to/export.json"
+                                class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                            />
+                            <div class="flex gap-2 items-center justify-end">
+                                <select
+                                    name="source"
+                                    class="rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                >
+                                    {
+                                        IMPORTERS.iter().map(|importer| html! {
+                                            <option value={importer.id()}>{ text!("{}", importer.name()) }</option>
+                                        }).collect::<Vec<_>>()
+                                    }
+                                </select>
+                                { list_select(&lists, current_list) }
+                                <label class="inline-flex items-center gap-1 text-sm text-gray-600">
+                                    <input
+                                        type="checkbox"
+                                        name="dry_run"
+                                        checked=""
+                                        class="rounded border-gray-300 text-blue-600 focus:ring-blue-500"
+                                    />
+                                    { text!("Preview only") }
+                                </label>
+                                <button
+                                    type="submit"
+                                    class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                                >
+                                    { text!("Import") }
+                                </button>
+                            </div>
+                        </form>
+                        <div id="import-preview"></div>
+                    </section>
+                </main>
+            </div>
+        </div>
+    }
+}
+
+/// A plain-text format that moves one list at a time.
+struct TextFormat {
+    /// Used in form ids and command names.
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    placeholder: &'static str,
+    example: &'static str,
+}
+
+fn text_format(format: &TextFormat, lists: &[TaskList], current_list: Ulid) -> Node {
+    html! {
+        <section class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-6">
+            <div>
+                <h2 class="text-lg font-medium text-gray-900">{ text!("{}", format.name) }</h2>
+                <p class="mt-1 text-sm text-gray-600">{ text!("{}", format.description) }</p>
+            </div>
+            <form
+                id={format!("export-{}-form", format.id)}
+                class="flex gap-2"
+                data-command={format!("export_{}", format.id)}
+                data-trigger="submit"
+            >
+                <input
+                    type="text"
+                    name="path"
+                    placeholder={format.placeholder}
+                    class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                    required=""
+                />
+                <button
+                    type="submit"
+                    class="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                >
+                    { text!("Export") }
+                </button>
+            </form>
+            <form
+                id={format!("import-{}-form", format.id)}
+                class="flex flex-col gap-2"
+                data-command={format!("import_{}", format.id)}
+                data-trigger="submit"
+            >
+                <input
+                    type="text"
+                    name="path"
+                    placeholder={format.placeholder}
+                    class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                />
+                <textarea
+                    name="text"
+                    rows="5"
+                    placeholder={format.example}
+                    class="block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                ></textarea>
+                <div class="flex gap-2 justify-end">
+                    { list_select(lists, current_list) }
+                    <button
+                        type="submit"
+                        class="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                    >
+                        { text!("Import") }
+                    </button>
+                </div>
+            </form>
+        </section>
+    }
+}
+
+/// Where an import goes: a new list or an existing one, the current list first selected.
+fn list_select(lists: &[TaskList], current_list: Ulid) -> Node {
+    html! {
+        <select
+            name="list"
+            class="rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+        >
+            <option value="new">{ text!("New list") }</option>
+            {
+                lists.iter().map(|list| {
+                    if list.id == current_list {
+                        html! {
+                            <option value={list.id.to_string()} selected="">{ text!("{}", list.name) }</option>
+                        }
+                    } else {
+                        html! {
+                            <option value={list.id.to_string()}>{ text!("{}", list.name) }</option>
+                        }
+                    }
+                }).collect::<Vec<_>>()
+            }
+        </select>
+    }
+}
+
+/// What an import would do, shown before anything is written.
+pub fn preview(source: &str, preview: &Preview) -> Node {
+    html! {
+        <div class="rounded-md bg-gray-50 p-4 text-sm text-gray-700 flex flex-col gap-2">
+            <p class="font-medium text-gray-900">
+                { text!("{} tasks from {} would be imported", preview.task_count(), source) }
+            </p>
+            <ul class="divide-y divide-gray-200">
+                {
+                    preview.lists.iter().map(|list| html! {
+                        <li class="py-2 flex justify-between">
+                            <span>
+                                { text!("{}", list.name) }
+                                {
+                                    if list.new {
+                                        html! {
+                                            <span class="ml-2 inline-flex items-center px-2 py-0.5 rounded-full text-xs font-medium bg-blue-100 text-blue-800">
+                                                { text!("New list") }
+                                            </span>
+                                        }
+                                    } else {
+                                        Node::default()
+                                    }
+                                }
+                            </span>
+                            <span class="text-gray-500">
+                                { text!("{} active, {} completed", list.active, list.completed) }
+                            </span>
+                        </li>
+                    }).collect::<Vec<_>>()
+                }
+            </ul>
+            {
+                if preview.duplicates > 0 {
+                    html! {
+                        <p class="text-gray-500">
+                            { text!("{} tasks are already here and would be skipped", preview.duplicates) }
+                        </p>
+                    }
+                } else {
+                    Node::default()
+                }
+            }
+            <p class="text-gray-500">{ text!("Uncheck Preview only and import again to write them") }</p>
+        </div>
+    }
+}
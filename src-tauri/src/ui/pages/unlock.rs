@@ -0,0 +1,79 @@
+use crate::profiles::Profiles;
+use html_node::{html, text, Node};
+
+pub fn render(error: Option<&str>, profiles: &Profiles) -> Node {
+    let others: Vec<_> = profiles
+        .profiles
+        .iter()
+        .filter(|profile| profile.name != profiles.current)
+        .collect();
+
+    html! {
+        <div class="min-h-screen bg-gray-50 py-8">
+            <div class="max-w-md mx-auto px-4">
+                <header class="text-center mb-12">
+                    <h1 class="text-4xl font-bold text-gray-900">{ text!("Task Stack") }</h1>
+                    <p class="mt-2 text-gray-600">{ text!("{} is encrypted. Enter your passphrase to unlock it.", profiles.current().name) }</p>
+                </header>
+                <main class="bg-white rounded-xl shadow-sm p-6 flex flex-col gap-6">
+                    {
+                        if let Some(error) = error {
+                            html! {
+                                <div class="bg-red-100 border-l-4 border-red-500 text-red-700 p-4 rounded">
+                                    <p class="text-sm font-medium">{ text!("{}", error) }</p>
+                                </div>
+                            }
+                        } else {
+                            Node::default()
+                        }
+                    }
+                    <form
+                        id="unlock-form"
+                        data-command="unlock_database"
+                        data-trigger="submit"
+                    >
+                        <div class="mb-4">
+                            <label for="passphrase" class="block text-sm font-medium text-gray-700">{ text!("Passphrase") }</label>
+                            <input
+                                type="password"
+                                name="passphrase"
+                                id="passphrase"
+                                class="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500"
+                                required=""
+                                autofocus=""
+                            />
+                        </div>
+                        <button
+                            type="submit"
+                            class="w-full flex justify-center py-2 px-4 border border-transparent rounded-md shadow-sm text-sm font-medium text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                        >
+                            { text!("Unlock") }
+                        </button>
+                    </form>
+                    {
+                        if others.is_empty() {
+                            Node::default()
+                        } else {
+                            html! {
+                                <div class="border-t border-gray-200 pt-4 flex flex-wrap items-center gap-2">
+                                    <span class="text-sm text-gray-600">{ text!("Or switch to") }</span>
+                                    {
+                                        others.into_iter().map(|profile| html! {
+                                            <button
+                                                class="inline-flex items-center px-3 py-1.5 border border-gray-300 text-xs font-medium rounded-full shadow-sm text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 cursor-pointer"
+                                                data-command="switch_profile"
+                                                data-payload={serde_json::json!({ "name": profile.name }).to_string()}
+                                            >
+                                                { text!("{}", profile.name) }
+                                            </button>
+                                        }).collect::<Vec<_>>()
+                                    }
+                                </div>
+                            }
+                        }
+                    }
+                </main>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,200 @@
+//! Answers HTTP API requests against an in-memory stack in a mock app.
+
+use serde_json::Value;
+use std::sync::Arc;
+use task_stack_core::repository::InMemoryRepository;
+use task_stack_core::TaskStack;
+use task_stack_lib::api::{self, ApiConfig, MAX_BODY_BYTES};
+use tauri::test::MockRuntime;
+use tauri::{App, Manager};
+use tiny_http::{Header, Method, TestRequest};
+use ulid::Ulid;
+
+const TOKEN: &str = "secret";
+
+fn app() -> App<MockRuntime> {
+    let app = tauri::test::mock_app();
+    let stack =
+        tauri::async_runtime::block_on(TaskStack::load(Arc::new(InMemoryRepository::new())))
+            .expect("Stack to load");
+    app.manage(stack);
+    app
+}
+
+fn request(method: Method, path: &str, body: &str) -> TestRequest {
+    TestRequest::new()
+        .with_method(method)
+        .with_path(path)
+        .with_body(body.to_string().leak())
+}
+
+fn authorized(request: TestRequest) -> TestRequest {
+    request.with_header(
+        Header::from_bytes(&b"Authorization"[..], format!("Bearer {TOKEN}").as_bytes())
+            .expect("Header to be valid"),
+    )
+}
+
+/// The status and JSON body of the answer to `request`.
+fn send(app: &App<MockRuntime>, request: TestRequest) -> (u16, Value) {
+    let response = api::respond(app.handle(), TOKEN, &mut request.into());
+    let status = response.status_code().0;
+    let body =
+        serde_json::from_slice(&response.into_reader().into_inner()).expect("Response to be JSON");
+    (status, body)
+}
+
+fn call(app: &App<MockRuntime>, method: Method, path: &str, body: &str) -> (u16, Value) {
+    send(app, authorized(request(method, path, body)))
+}
+
+#[test]
+fn needs_the_token() {
+    let app = app();
+    let (status, _) = send(&app, request(Method::Get, "/lists", ""));
+    assert_eq!(status, 401);
+
+    let wrong = request(Method::Get, "/lists", "")
+        .with_header(Header::from_bytes(&b"Authorization"[..], &b"Bearer secreT"[..]).unwrap());
+    assert_eq!(send(&app, wrong).0, 401);
+    let unprefixed = request(Method::Get, "/lists", "")
+        .with_header(Header::from_bytes(&b"Authorization"[..], TOKEN.as_bytes()).unwrap());
+    assert_eq!(send(&app, unprefixed).0, 401);
+
+    let (status, lists) = call(&app, Method::Get, "/lists", "");
+    assert_eq!(status, 200);
+    assert_eq!(lists.as_array().map(Vec::len), Some(1));
+}
+
+#[test]
+fn refuses_requests_while_no_database_is_open() {
+    let app = tauri::test::mock_app();
+    assert_eq!(call(&app, Method::Get, "/tasks", "").0, 503);
+}
+
+#[test]
+fn pushes_moves_and_completes_tasks() {
+    let app = app();
+    assert_eq!(call(&app, Method::Get, "/tasks/current", "").0, 404);
+
+    let (status, first) = call(&app, Method::Post, "/tasks", r#"{"title":" First "}"#);
+    assert_eq!(status, 200);
+    assert_eq!(first["title"], "First");
+    let first = first["id"].as_str().unwrap().to_string();
+    let (_, second) = call(
+        &app,
+        Method::Post,
+        "/tasks",
+        r#"{"title":"Second","description":"Notes"}"#,
+    );
+    let second = second["id"].as_str().unwrap().to_string();
+    let (_, current) = call(&app, Method::Get, "/tasks/current", "");
+    assert_eq!(current["id"], first.as_str());
+
+    let (status, _) = call(&app, Method::Post, &format!("/tasks/{first}/defer"), "");
+    assert_eq!(status, 200);
+    let (_, current) = call(&app, Method::Get, "/tasks/current", "");
+    assert_eq!(current["id"], second.as_str());
+
+    let (status, _) = call(
+        &app,
+        Method::Post,
+        &format!("/tasks/{first}/move"),
+        r#"{"position":0}"#,
+    );
+    assert_eq!(status, 200);
+    let (_, current) = call(&app, Method::Get, "/tasks/current", "");
+    assert_eq!(current["id"], first.as_str());
+
+    let (status, tasks) = call(
+        &app,
+        Method::Put,
+        "/tasks/order",
+        &format!(r#"{{"ids":["{second}","{first}"]}}"#),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(tasks[0]["id"], second.as_str());
+
+    let (status, completed) = call(&app, Method::Post, &format!("/tasks/{second}/complete"), "");
+    assert_eq!(status, 200);
+    assert!(completed["completed_at"].is_string());
+    let (_, current) = call(&app, Method::Get, "/tasks/current", "");
+    assert_eq!(current["id"], first.as_str());
+    let (_, all) = call(&app, Method::Get, "/tasks?all=true", "");
+    assert_eq!(all.as_array().map(Vec::len), Some(2));
+}
+
+#[test]
+fn works_on_lists_by_name_without_switching() {
+    let app = app();
+    let stack = app.state::<TaskStack>();
+    let errands = tauri::async_runtime::block_on(stack.create_new_list("Errands")).unwrap();
+    let current = stack.get_list_id();
+
+    let (status, task) = call(
+        &app,
+        Method::Post,
+        "/tasks",
+        r#"{"title":"Buy milk","list":"errands"}"#,
+    );
+    assert_eq!(status, 200);
+    assert_eq!(task["list_id"], errands.to_string());
+    let (_, tasks) = call(&app, Method::Get, "/tasks?list=Errands", "");
+    assert_eq!(tasks[0]["title"], "Buy milk");
+    assert_eq!(stack.get_list_id(), current);
+
+    // A task is only reached through its own list
+    let id = task["id"].as_str().unwrap();
+    assert_eq!(
+        call(&app, Method::Post, &format!("/tasks/{id}/complete"), "").0,
+        404
+    );
+    assert_eq!(call(&app, Method::Get, "/tasks?list=Nowhere", "").0, 404);
+}
+
+#[test]
+fn answers_bad_requests_with_their_status() {
+    let app = app();
+    let missing = Ulid::new();
+    let cases = [
+        (Method::Post, "/tasks".to_string(), r#"{"title":"  "}"#, 400),
+        (Method::Post, "/tasks".to_string(), "not json", 400),
+        (
+            Method::Post,
+            "/tasks/not-an-id/complete".to_string(),
+            "",
+            400,
+        ),
+        (Method::Post, format!("/tasks/{missing}/defer"), "", 404),
+        (Method::Delete, "/tasks".to_string(), "", 404),
+        (Method::Get, "/nowhere".to_string(), "", 404),
+    ];
+    for (method, path, body, expected) in cases {
+        let (status, answer) = call(&app, method.clone(), &path, body);
+        assert_eq!(status, expected, "{method} {path}");
+        assert!(answer["error"].is_string(), "{method} {path}");
+    }
+
+    let large = format!(r#"{{"title":"{}"}}"#, "a".repeat(MAX_BODY_BYTES as usize));
+    assert_eq!(call(&app, Method::Post, "/tasks", &large).0, 413);
+}
+
+#[cfg(unix)]
+#[test]
+fn saves_the_token_for_the_user_alone() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("task-stack-test-{}", Ulid::new()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(api::API_FILE);
+    // Saved before the mode was narrowed
+    std::fs::write(&path, "{}").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    let config = ApiConfig::default();
+    config.save(&dir).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(ApiConfig::load(&dir).token, config.token);
+    let _ = std::fs::remove_dir_all(&dir);
+}
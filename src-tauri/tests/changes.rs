@@ -0,0 +1,61 @@
+//! Announces changes to the open database to every window of a mock app.
+
+mod common;
+
+use common::TempDir;
+use serde_json::{json, Value};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::Duration;
+use task_stack_core::repository::LibsqlRepository;
+use task_stack_core::TaskStack;
+use task_stack_lib::{open_database, watch_outside_changes, DATA_CHANGED};
+use tauri::{Listener, Manager};
+
+fn next(changes: &Receiver<Value>) -> Value {
+    changes
+        .recv_timeout(Duration::from_secs(5))
+        .expect("A change to be announced")
+}
+
+#[test]
+fn announces_writes_from_the_app_and_from_outside() {
+    let dir = TempDir::new();
+    let path = dir.join("tasks.db");
+    let app = tauri::test::mock_app();
+    let handle = app.handle().clone();
+
+    let (sender, changes) = mpsc::channel();
+    handle.listen_any(DATA_CHANGED, move |event| {
+        let _ = sender.send(serde_json::from_str(event.payload()).expect("Payload to be JSON"));
+    });
+    tauri::async_runtime::block_on(open_database(&handle, path.clone()));
+    let stack = handle.state::<TaskStack>();
+    let list_id = stack.get_list_id();
+
+    tauri::async_runtime::block_on(stack.push("From the app".to_string(), None)).unwrap();
+    assert_eq!(
+        next(&changes),
+        json!({ "kind": "tasks", "list_id": list_id.to_string() })
+    );
+
+    // Started after the app's own write, so only the outside one is seen
+    watch_outside_changes(&handle, Duration::from_millis(50));
+    std::thread::sleep(Duration::from_millis(200));
+    tauri::async_runtime::block_on(async {
+        let repo = LibsqlRepository::open(path.clone(), None)
+            .await
+            .expect("Database to open a second time");
+        let outside = TaskStack::load(Arc::new(repo))
+            .await
+            .expect("Stack to load");
+        outside
+            .push("From the CLI".to_string(), None)
+            .await
+            .expect("Task to be pushed");
+    });
+    assert_eq!(next(&changes), json!({ "kind": "database" }));
+
+    let tasks = tauri::async_runtime::block_on(stack.get_tasks()).unwrap();
+    assert_eq!(tasks.len(), 2);
+}
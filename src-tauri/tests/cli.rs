@@ -0,0 +1,137 @@
+//! Runs the `task-stack` binary on a database of its own.
+
+mod common;
+
+use common::TempDir;
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+use task_stack_core::repository::LibsqlRepository;
+use task_stack_core::TaskStack;
+use task_stack_lib::cli::{EXIT_EMPTY, EXIT_ERROR, EXIT_OK, EXIT_USAGE};
+
+/// An empty database at `path`, as the app leaves it when first opened.
+fn create(path: &Path) {
+    tauri::async_runtime::block_on(async {
+        let repo = LibsqlRepository::open(path.to_path_buf(), None)
+            .await
+            .expect("Database to open");
+        TaskStack::load(Arc::new(repo))
+            .await
+            .expect("Stack to load");
+    });
+}
+
+/// The exit code and standard output of the CLI run with `args` on `db`.
+fn cli(db: &Path, args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_task-stack"))
+        .arg("--db")
+        .arg(db)
+        .args(args)
+        .env_remove("TASK_STACK_PASSPHRASE")
+        .output()
+        .expect("CLI to run");
+    (
+        output.status.code().expect("CLI to exit"),
+        String::from_utf8(output.stdout).expect("Output to be UTF-8"),
+    )
+}
+
+fn json(db: &Path, args: &[&str]) -> (i32, Value) {
+    let (code, stdout) = cli(db, &[&["--json"][..], args].concat());
+    (
+        code,
+        serde_json::from_str(&stdout).expect("Output to be JSON"),
+    )
+}
+
+#[test]
+fn tells_an_empty_stack_from_a_failure() {
+    let dir = TempDir::new();
+    let db = dir.join("tasks.db");
+    assert_eq!(cli(&db, &["next"]).0, EXIT_ERROR);
+
+    create(&db);
+    assert_eq!(
+        cli(&db, &["next"]),
+        (EXIT_EMPTY, "The stack is empty\n".to_string())
+    );
+    assert_eq!(json(&db, &["done"]), (EXIT_EMPTY, Value::Null));
+    assert_eq!(cli(&db, &["defer"]).0, EXIT_EMPTY);
+    assert_eq!(cli(&db, &["ls"]), (EXIT_OK, "\n".to_string()));
+    assert_eq!(cli(&db, &["switch", "Nowhere"]).0, EXIT_ERROR);
+}
+
+#[test]
+fn refuses_bad_usage() {
+    let dir = TempDir::new();
+    let db = dir.join("tasks.db");
+    create(&db);
+    for args in [
+        &["fly"][..],
+        &["--bogus", "next"],
+        &["push"],
+        &["push", "  "],
+        &["--list"],
+        &["done", "not-an-id"],
+        &["switch"],
+        &["status", "--format", "xml"],
+    ] {
+        assert_eq!(cli(&db, args).0, EXIT_USAGE, "{args:?}");
+    }
+
+    let (code, help) = cli(&db, &["--help"]);
+    assert_eq!(code, EXIT_OK);
+    assert!(help.starts_with("Usage: task-stack ["));
+    assert_eq!(cli(&db, &[]).0, EXIT_OK);
+}
+
+#[test]
+fn works_the_stack_in_text_and_json() {
+    let dir = TempDir::new();
+    let db = dir.join("tasks.db");
+    create(&db);
+
+    let (code, pushed) = cli(&db, &["push", "Write", "the", "report", "-d", "Notes"]);
+    assert_eq!(code, EXIT_OK);
+    assert!(pushed.starts_with("Pushed "));
+    assert!(pushed.ends_with("  [ ] Write the report\n"));
+    let (code, second) = json(&db, &["push", "Second"]);
+    assert_eq!(code, EXIT_OK);
+    assert_eq!(second["title"], "Second");
+
+    let (_, next) = cli(&db, &["next"]);
+    assert!(next.ends_with("Write the report\n\nNotes\n"));
+    let (_, deferred) = json(&db, &["defer"]);
+    assert_eq!(deferred["title"], "Write the report");
+    let (_, next) = json(&db, &["next"]);
+    assert_eq!(next["title"], "Second");
+
+    let id = second["id"].as_str().unwrap();
+    let (code, done) = json(&db, &["done", id]);
+    assert_eq!(code, EXIT_OK);
+    assert_eq!(done["state"], "Completed");
+    let (_, next) = json(&db, &["next"]);
+    assert_eq!(next["title"], "Write the report");
+
+    let (_, tasks) = json(&db, &["ls", "--all"]);
+    let titles: Vec<_> = tasks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, ["Second", "Write the report"]);
+
+    let (_, lists) = json(&db, &["lists"]);
+    assert_eq!(lists[0]["current"], true);
+    assert_eq!(lists[0]["total"], 2);
+
+    let output = dir.join("tasks.md");
+    let output_arg = output.to_str().unwrap();
+    let (code, _) = cli(&db, &["export", "--format", "markdown", "-o", output_arg]);
+    assert_eq!(code, EXIT_OK);
+    let markdown = std::fs::read_to_string(&output).unwrap();
+    assert!(markdown.contains("- [ ] Write the report"));
+}
@@ -0,0 +1,26 @@
+//! Helpers shared by the integration tests, like the core crate's `testing`
+//! module, which isn't visible outside it.
+
+use std::path::PathBuf;
+use ulid::Ulid;
+
+/// A fresh directory under the system temp directory, removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("task-stack-test-{}", Ulid::new()));
+        std::fs::create_dir_all(&path).expect("Temp dir to be created");
+        Self(path)
+    }
+
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
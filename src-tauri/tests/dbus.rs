@@ -0,0 +1,186 @@
+//! Serves the D-Bus interface from a mock app on a private session bus.
+#![cfg(target_os = "linux")]
+
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+use task_stack_core::repository::InMemoryRepository;
+use task_stack_core::tasks::Change;
+use task_stack_core::TaskStack;
+use task_stack_lib::dbus::{self, BUS_NAME, OBJECT_PATH};
+use tauri::Manager;
+use zbus::fdo::PropertiesProxy;
+use zbus::names::InterfaceName;
+use zbus::zvariant::OwnedValue;
+use zbus::{Connection, MatchRule, MessageStream};
+
+const INTERFACE: &str = "net.thekwoka.TaskStack1";
+
+/// A `dbus-daemon` of our own, stopped when dropped.
+struct Daemon(Child);
+
+impl Daemon {
+    fn start() -> (Self, String) {
+        let mut child = Command::new("dbus-daemon")
+            .args(["--session", "--print-address", "--nofork", "--nopidfile"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("dbus-daemon to be on the PATH, as the D-Bus tests need it");
+        let mut address = String::new();
+        let stdout = child.stdout.take().expect("Daemon stdout to be piped");
+        BufReader::new(stdout)
+            .read_line(&mut address)
+            .expect("Daemon to print its address");
+        (Self(child), address.trim().to_string())
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+async fn call<B>(client: &Connection, method: &str, body: &B) -> zbus::Result<zbus::Message>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    client
+        .call_method(Some(BUS_NAME), OBJECT_PATH, Some(INTERFACE), method, body)
+        .await
+}
+
+#[test]
+fn serves_properties_methods_and_changes() {
+    let (_daemon, address) = Daemon::start();
+    std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &address);
+
+    let app = tauri::test::mock_app();
+    let handle = app.handle().clone();
+    tauri::async_runtime::block_on(async move {
+        let stack = TaskStack::load(Arc::new(InMemoryRepository::new()))
+            .await
+            .expect("Stack to load");
+        handle.manage(stack);
+        dbus::serve(&handle).await.expect("Service to be served");
+
+        let client = Connection::session().await.expect("Client to connect");
+        let properties = PropertiesProxy::builder(&client)
+            .destination(BUS_NAME)
+            .unwrap()
+            .path(OBJECT_PATH)
+            .unwrap()
+            .build()
+            .await
+            .expect("Proxy to be built");
+        let interface = InterfaceName::try_from(INTERFACE).unwrap();
+        let property = |name: &'static str| {
+            let properties = &properties;
+            let interface = interface.clone();
+            async move {
+                properties
+                    .get(interface, name)
+                    .await
+                    .expect("Property to be read")
+            }
+        };
+
+        // An empty stack
+        let all = properties
+            .get_all(interface.clone().into())
+            .await
+            .expect("Properties to be read");
+        let text = |name: &str| String::try_from(all[name].try_clone().unwrap()).unwrap();
+        assert_eq!(text("CurrentTask"), "");
+        assert_eq!(u32::try_from(&all["ActiveCount"]).unwrap(), 0);
+        let lists = handle.state::<TaskStack>().get_lists().await.unwrap();
+        assert_eq!(text("CurrentList"), lists[0].name);
+
+        // Methods
+        let id: String = call(&client, "Push", &("First", ""))
+            .await
+            .expect("Push to be answered")
+            .body()
+            .deserialize()
+            .unwrap();
+        call(&client, "Push", &("Second", "Notes"))
+            .await
+            .expect("Push to be answered");
+        assert_eq!(
+            String::try_from(property("CurrentTaskId").await).unwrap(),
+            id
+        );
+        assert_eq!(u32::try_from(property("ActiveCount").await).unwrap(), 2);
+
+        let moved: bool = call(&client, "MoveCurrentToEnd", &())
+            .await
+            .expect("MoveCurrentToEnd to be answered")
+            .body()
+            .deserialize()
+            .unwrap();
+        assert!(moved);
+        assert_eq!(
+            String::try_from(property("CurrentTask").await).unwrap(),
+            "Second"
+        );
+
+        let completed: bool = call(&client, "CompleteCurrent", &())
+            .await
+            .expect("CompleteCurrent to be answered")
+            .body()
+            .deserialize()
+            .unwrap();
+        assert!(completed);
+        assert_eq!(
+            String::try_from(property("CurrentTask").await).unwrap(),
+            "First"
+        );
+        assert_eq!(u32::try_from(property("ActiveCount").await).unwrap(), 1);
+
+        assert!(call(&client, "SwitchList", &("Nowhere",)).await.is_err());
+
+        // PropertiesChanged
+        let rule = MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface("org.freedesktop.DBus.Properties")
+            .unwrap()
+            .member("PropertiesChanged")
+            .unwrap()
+            .build();
+        let mut signals = MessageStream::for_match_rule(rule, &client, None)
+            .await
+            .expect("Signals to be subscribed to");
+        let list_id = handle.state::<TaskStack>().get_list_id();
+        dbus::announce(&handle, Change::Tasks { list_id })
+            .await
+            .expect("Change to be announced");
+        // Each property is announced in a signal of its own
+        let mut changed = HashMap::new();
+        while !changed.contains_key("ActiveCount") {
+            let signal = tokio::time::timeout(Duration::from_secs(5), signals.next())
+                .await
+                .expect("PropertiesChanged to arrive")
+                .expect("Signal stream to stay open")
+                .expect("Signal to be received");
+            let (changed_interface, properties, _): (
+                String,
+                HashMap<String, OwnedValue>,
+                Vec<String>,
+            ) = signal
+                .body()
+                .deserialize()
+                .expect("Signal body to deserialize");
+            assert_eq!(changed_interface, INTERFACE);
+            changed.extend(properties);
+        }
+        assert_eq!(
+            String::try_from(changed["CurrentTask"].try_clone().unwrap()).unwrap(),
+            "First"
+        );
+        assert!(!changed.contains_key("CurrentList"));
+    });
+}
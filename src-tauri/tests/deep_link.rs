@@ -0,0 +1,106 @@
+//! Parses `taskstack://` links and opens them against an in-memory stack,
+//! in a mock app for links that arrive before the window listens.
+
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+use task_stack_core::repository::InMemoryRepository;
+use task_stack_core::TaskStack;
+use task_stack_lib::deep_link::{self, Link, PendingLink, MAX_TITLE_LEN, OPEN_LINK};
+use task_stack_lib::types::{DomUpdate, PageResponse};
+use tauri::{Listener, Manager, Url};
+
+fn parse(url: &str) -> Result<Link, String> {
+    Link::parse(&Url::parse(url).expect("Link to be a URL"))
+}
+
+#[test]
+fn limits_what_add_links_carry() {
+    assert_eq!(
+        parse("taskstack://add?title=Call%20back&description=Line%0Aby%20line&list=Work"),
+        Ok(Link::Add {
+            title: "Call back".to_string(),
+            description: Some("Line\nby line".to_string()),
+            list: Some("Work".to_string()),
+        })
+    );
+
+    let long = "a".repeat(MAX_TITLE_LEN + 1);
+    assert!(parse(&format!("taskstack://add?title={long}")).is_err());
+    assert!(parse("taskstack://add?title=Bell%07").is_err());
+    assert!(parse("taskstack://add?title=Two%0Alines").is_err());
+    assert!(parse("taskstack://add?title=Call&description=Escape%1B%5B2J").is_err());
+}
+
+#[test]
+fn add_links_wait_for_confirmation() {
+    tauri::async_runtime::block_on(async {
+        let stack = TaskStack::load(Arc::new(InMemoryRepository::new()))
+            .await
+            .expect("Stack to load");
+        let errands = stack.create_new_list("Errands").await.unwrap();
+
+        let link = parse("taskstack://add?title=Buy%20milk&description=Oat&list=errands").unwrap();
+        let opened = deep_link::open(&stack, link).await.unwrap();
+        let counts = stack.count_tasks().await.unwrap();
+        assert!(counts.values().all(|count| count.total == 0));
+        // The list changes only once the task is added
+        assert_ne!(stack.get_list_id(), errands);
+
+        let html = opened.response.updates[0].html.clone();
+        assert!(html.contains(r#"value="Buy milk""#));
+        assert!(html.contains(">Oat</textarea>"));
+        assert!(html.contains(&format!(r#"name="listId" value="{errands}""#)));
+        assert!(html.contains("Adding to Errands"));
+        assert_eq!(opened.focus.as_deref(), Some("task-form"));
+    });
+}
+
+#[test]
+fn keeps_links_opened_before_the_window_listens() {
+    let app = tauri::test::mock_app();
+    let handle = app.handle().clone();
+    let stack =
+        tauri::async_runtime::block_on(TaskStack::load(Arc::new(InMemoryRepository::new())))
+            .expect("Stack to load");
+    handle.manage(stack);
+    handle.manage(PendingLink::default());
+
+    let (sender, emitted) = mpsc::channel();
+    handle.listen_any(OPEN_LINK, move |event| {
+        let _ = sender.send(event.payload().to_string());
+    });
+    let url = |title: &str| Url::parse(&format!("taskstack://add?title={title}")).unwrap();
+
+    // As on a cold start, links are opened before the window has started
+    let links = vec![
+        url("Before"),
+        Url::parse("taskstack://list/Nowhere").unwrap(),
+    ];
+    tauri::async_runtime::block_on(deep_link::handle(&handle, links)).unwrap();
+    assert!(emitted.try_recv().is_err());
+
+    let pending = deep_link::take_pending(&handle);
+    assert_eq!(pending.len(), 2);
+    assert!(deep_link::take_pending(&handle).is_empty());
+
+    // The failed link is reported without replacing the draft
+    let start = PageResponse::new(DomUpdate {
+        html: "<p>Last view</p>".to_string(),
+        target: "#app".to_string(),
+        action: "replace".to_string(),
+    });
+    let shown = deep_link::show_pending(pending, start);
+    assert!(shown.updates[0].html.contains(r#"value="Before""#));
+    let notification = shown.notification.expect("The failure to be reported");
+    assert_eq!(notification.notification_type, "error");
+    assert!(notification.message.contains("Nowhere"));
+
+    // Once the window has started, links go straight to it
+    tauri::async_runtime::block_on(deep_link::handle(&handle, vec![url("After")])).unwrap();
+    let payload = emitted
+        .recv_timeout(Duration::from_secs(5))
+        .expect("The link to be emitted");
+    assert!(payload.contains("After"));
+    assert!(deep_link::take_pending(&handle).is_empty());
+}
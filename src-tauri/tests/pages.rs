@@ -0,0 +1,45 @@
+//! Renders pages against an in-memory stack.
+
+use std::sync::Arc;
+use task_stack_core::repository::InMemoryRepository;
+use task_stack_core::TaskStack;
+use task_stack_lib::ui::pages;
+
+async fn stack() -> TaskStack {
+    TaskStack::load(Arc::new(InMemoryRepository::new()))
+        .await
+        .expect("Stack to load")
+}
+
+#[test]
+fn index_shows_the_first_active_task() {
+    tauri::async_runtime::block_on(async {
+        let stack = stack().await;
+        let empty = pages::index::render(&stack).await.to_string();
+        assert!(empty.contains(r#"data-view="index""#));
+
+        stack
+            .push("Write the report".to_string(), None)
+            .await
+            .unwrap();
+        stack.push("Send it".to_string(), None).await.unwrap();
+        let html = pages::index::render(&stack).await.to_string();
+        assert!(html.contains("Write the report"));
+        assert!(!html.contains("Send it"));
+    });
+}
+
+#[test]
+fn lists_shows_every_list_with_its_counts() {
+    tauri::async_runtime::block_on(async {
+        let stack = stack().await;
+        stack.push("Task".to_string(), None).await.unwrap();
+        stack.create_new_list("Errands").await.unwrap();
+
+        let html = pages::lists::render(&stack).await.to_string();
+        assert!(html.contains(r#"data-view="lists""#));
+        assert!(html.contains("Initial List"));
+        assert!(html.contains("Errands"));
+        assert!(html.contains("1 active tasks"));
+    });
+}
@@ -0,0 +1,54 @@
+//! Opens the databases of other profiles in a mock app.
+
+mod common;
+
+use common::TempDir;
+use std::sync::Mutex;
+use task_stack_core::database::Unopened;
+use task_stack_core::repository::{LibsqlRepository, TaskRepository};
+use task_stack_core::TaskStack;
+use task_stack_lib::open_database;
+use tauri::Manager;
+
+#[test]
+fn switching_to_a_locked_profile_closes_the_last_one() {
+    let dir = TempDir::new();
+    let plain = dir.join("plain.db");
+    let locked = dir.join("locked.db");
+
+    let app = tauri::test::mock_app();
+    let handle = app.handle().clone();
+    tauri::async_runtime::block_on(async move {
+        let repo = LibsqlRepository::open(locked.clone(), None)
+            .await
+            .expect("Database to open");
+        repo.set_passphrase(Some("secret"))
+            .await
+            .expect("Database to be encrypted");
+        drop(repo);
+
+        open_database(&handle, plain.clone()).await;
+        let stack = handle.state::<TaskStack>();
+        stack
+            .push("Kept".to_string(), None)
+            .await
+            .expect("Task to be pushed");
+
+        open_database(&handle, locked.clone()).await;
+        let unopened = handle.state::<Mutex<Option<Unopened>>>();
+        assert!(matches!(
+            &*unopened.lock().unwrap(),
+            Some(Unopened::Locked(path)) if *path == locked
+        ));
+        // Nothing reaches the last profile's database while this one is locked
+        assert!(stack.push("Lost".to_string(), None).await.is_err());
+        assert!(stack.get_tasks().await.is_err());
+        assert_eq!(stack.get_db_path(), None);
+
+        open_database(&handle, plain.clone()).await;
+        assert!(unopened.lock().unwrap().is_none());
+        let tasks = stack.get_tasks().await.expect("Tasks to be read");
+        let titles: Vec<_> = tasks.iter().map(|task| task.title.as_str()).collect();
+        assert_eq!(titles, ["Kept"]);
+    });
+}
@@ -0,0 +1,83 @@
+//! Answers JSON-RPC lines against an in-memory stack.
+
+use serde_json::Value;
+use std::sync::Arc;
+use task_stack_core::repository::InMemoryRepository;
+use task_stack_core::TaskStack;
+use task_stack_lib::rpc::{self, NOT_OPEN, PARSE_ERROR};
+
+fn answer(stack: Option<&TaskStack>, line: &str) -> Option<Value> {
+    tauri::async_runtime::block_on(rpc::handle(stack, line))
+        .map(|response| serde_json::from_str(&response).expect("Response to be JSON"))
+}
+
+fn stack() -> TaskStack {
+    tauri::async_runtime::block_on(TaskStack::load(Arc::new(InMemoryRepository::new())))
+        .expect("Stack to load")
+}
+
+#[test]
+fn answers_requests_with_their_id() {
+    let stack = stack();
+    let response = answer(
+        Some(&stack),
+        r#"{"jsonrpc":"2.0","id":7,"method":"push","params":{"title":"Write"}}"#,
+    )
+    .expect("A request to be answered");
+    assert_eq!(response["id"], 7);
+    assert_eq!(response["result"]["title"], "Write");
+
+    // A null id is still an id
+    let response = answer(
+        Some(&stack),
+        r#"{"jsonrpc":"2.0","id":null,"method":"next"}"#,
+    )
+    .expect("A request to be answered");
+    assert_eq!(response["result"]["title"], "Write");
+}
+
+#[test]
+fn runs_notifications_without_answering() {
+    let stack = stack();
+    let response = answer(
+        Some(&stack),
+        r#"{"jsonrpc":"2.0","method":"push","params":{"title":"Quietly"}}"#,
+    );
+    assert!(response.is_none());
+    let tasks = tauri::async_runtime::block_on(stack.get_tasks()).unwrap();
+    assert_eq!(tasks[0].title, "Quietly");
+
+    // Even when they fail
+    assert!(answer(None, r#"{"jsonrpc":"2.0","method":"next"}"#).is_none());
+}
+
+#[test]
+fn answers_errors_it_cant_tie_to_a_request() {
+    let response = answer(None, "not json").expect("A parse error to be answered");
+    assert_eq!(response["id"], Value::Null);
+    assert_eq!(response["error"]["code"], PARSE_ERROR);
+
+    let response = answer(None, r#"{"jsonrpc":"2.0","id":1,"method":"next"}"#)
+        .expect("A request to be answered");
+    assert_eq!(response["error"]["code"], NOT_OPEN);
+}
+
+#[test]
+fn exports_only_the_list_worked_on_unless_all_are_asked_for() {
+    let stack = stack();
+    tauri::async_runtime::block_on(stack.create_new_list("Errands")).unwrap();
+    let export = |params: &str| {
+        let line = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"export","params":{params}}}"#);
+        let response = answer(Some(&stack), &line).expect("A request to be answered");
+        let contents = response["result"].as_str().expect("Contents").to_string();
+        serde_json::from_str::<Value>(&contents).expect("Export to be JSON")
+    };
+
+    let scoped = export(r#"{"list":"Errands"}"#);
+    let lists = scoped["lists"].as_array().unwrap();
+    assert_eq!(lists.len(), 1);
+    assert_eq!(lists[0]["name"], "Errands");
+
+    let all = export(r#"{"list":"Errands","all":true}"#);
+    assert_eq!(all["lists"].as_array().unwrap().len(), 2);
+}
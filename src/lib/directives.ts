@@ -1,4 +1,4 @@
-import { invoke } from "@tauri-apps/api/core";
+import { invokeLocal } from "./live";
 import { handlePageResponse, type PageResponse } from "./ui";
 
 type DirectiveHandler = (el: HTMLElement) => void;
@@ -38,7 +38,7 @@ const commandHandler = async (event: Event) => {
       Object.assign(payload, formPayload);
     }
 
-    const response = await invoke<PageResponse>(command, payload);
+    const response = await invokeLocal<PageResponse>(command, payload);
     console.log('invoke response', response);
     handlePageResponse(response);
 
@@ -0,0 +1,98 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { handlePageResponse, type PageResponse } from "./ui";
+
+// Emitted by the backend whenever tasks, lists or settings change
+const DATA_CHANGED = "data-changed";
+// Emitted by the backend with the page a taskstack:// link opened
+const OPEN_LINK = "open-link";
+
+// Changes announced while this window's own commands run, or just after, are
+// already on screen from the command's response
+const SETTLE_MS = 300;
+// Bursts of changes, like an import, refresh once
+const DEBOUNCE_MS = 100;
+
+let running = 0;
+let settledAt = 0;
+let stale = false;
+let timer: ReturnType<typeof setTimeout> | undefined;
+
+// Runs a command from this window, so the changes it makes aren't refreshed twice
+export async function invokeLocal<T>(
+  command: string,
+  payload?: Record<string, unknown>,
+): Promise<T> {
+  running++;
+  try {
+    return await invoke<T>(command, payload);
+  } finally {
+    running--;
+    settledAt = Date.now();
+  }
+}
+
+// Whether refreshing would throw away something being typed
+function isEditing() {
+  const el = document.activeElement;
+  return (
+    (el instanceof HTMLInputElement || el instanceof HTMLTextAreaElement) &&
+    !el.readOnly &&
+    el.value !== "" &&
+    el.closest("#app") !== null
+  );
+}
+
+// Re-runs the command that rendered the current page
+async function refresh() {
+  const view = document.querySelector<HTMLElement>("#app [data-view]")?.dataset
+    .view;
+  if (!view) return;
+
+  if (isEditing()) {
+    stale = true;
+    return;
+  }
+  stale = false;
+
+  try {
+    handlePageResponse(await invoke<PageResponse>(view));
+  } catch (error) {
+    console.error(`Error refreshing ${view}:`, error);
+  }
+}
+
+export async function initLiveRefresh() {
+  const unlisten = await listen(DATA_CHANGED, () => {
+    if (running > 0 || Date.now() - settledAt < SETTLE_MS) return;
+    clearTimeout(timer);
+    timer = setTimeout(refresh, DEBOUNCE_MS);
+  });
+
+  const unlistenLinks = await listen<{
+    response: PageResponse;
+    focus: string | null;
+  }>(OPEN_LINK, ({ payload }) => {
+    // The link's own changes are already on the page it sent
+    settledAt = Date.now();
+    clearTimeout(timer);
+    handlePageResponse(payload.response);
+    if (payload.focus) {
+      document
+        .getElementById(payload.focus)
+        ?.scrollIntoView({ block: "center", behavior: "smooth" });
+    }
+  });
+
+  // Catch up once the user stops editing
+  const onFocusOut = () => {
+    if (stale) setTimeout(refresh, 0);
+  };
+  document.addEventListener("focusout", onFocusOut);
+
+  return () => {
+    unlisten();
+    unlistenLinks();
+    document.removeEventListener("focusout", onFocusOut);
+  };
+}
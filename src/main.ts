@@ -1,10 +1,15 @@
 import "./index.css";
 import { initDirectives } from "./lib/directives";
+import { initLiveRefresh } from "./lib/live";
 
 // Initialize directives when the DOM is loaded
 document.addEventListener("DOMContentLoaded", () => {
   const cleanup = initDirectives();
+  const stopRefreshing = initLiveRefresh();
 
-  // Clean up observer when window unloads
-  window.addEventListener("unload", cleanup);
+  // Clean up observer and event listener when window unloads
+  window.addEventListener("unload", () => {
+    cleanup();
+    stopRefreshing.then((stop) => stop());
+  });
 });
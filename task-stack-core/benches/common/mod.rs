@@ -0,0 +1,77 @@
+//! Setup shared by the benches, here and in the app's `render` bench.
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use task_stack_core::TaskStack;
+
+const LISTS: usize = 5;
+const TASKS_PER_LIST: usize = 50;
+const ITERATIONS: u32 = 200;
+
+/// A fresh directory under the system temp directory, removed when dropped.
+pub struct BenchDir(PathBuf);
+
+impl BenchDir {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("task-stack-bench-{}", ulid::Ulid::new()));
+        std::fs::create_dir_all(&path).expect("Bench dir to be created");
+        Self(path)
+    }
+
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for BenchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Fills `stack` with `LISTS` lists of `TASKS_PER_LIST` tasks, the first of
+/// each completed, leaving the last list current.
+pub async fn seed(stack: &TaskStack) {
+    for list in 0..LISTS {
+        let list_id = stack
+            .create_new_list(&format!("List {list}"))
+            .await
+            .expect("List to be created");
+        stack
+            .set_list_id(list_id)
+            .await
+            .expect("List to be selected");
+        for task in 0..TASKS_PER_LIST {
+            stack
+                .push(format!("Task {task}"), None)
+                .await
+                .expect("Task to be pushed");
+        }
+        let first = stack
+            .first_active()
+            .await
+            .expect("Query to run")
+            .expect("List to have tasks");
+        stack
+            .complete_task(first.id)
+            .await
+            .expect("Task to be completed");
+    }
+}
+
+/// Prints how long `run` takes on average, after a warm-up run so caches and
+/// prepared statements are in place.
+pub async fn time<F, Fut>(name: &str, unit: &str, mut run: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future,
+{
+    run().await;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(run().await);
+    }
+    let per_run: Duration = start.elapsed() / ITERATIONS;
+    println!("{name:<28} {per_run:>12.2?} per {unit}");
+}
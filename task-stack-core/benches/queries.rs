@@ -0,0 +1,83 @@
+//! Times the queries rendering the `index` and `lists` pages makes, through
+//! a stack's session with its cached statements, against a new connection
+//! on a database kept open, preparing each statement anew per query as
+//! before sessions. Both sides run the repository's own queries.
+//!
+//! Run with `cargo bench --bench queries`, which prints the time per render
+//! of each with 5 lists of 50 tasks.
+
+mod common;
+
+use common::{seed, time, BenchDir};
+use std::sync::Arc;
+use task_stack_core::database::{self, Session};
+use task_stack_core::repository::LibsqlRepository;
+use task_stack_core::TaskStack;
+use ulid::Ulid;
+
+async fn through_session(stack: &TaskStack) {
+    let task = stack.first_active().await.expect("Query to run");
+    std::hint::black_box(stack.size().await.expect("Query to run"));
+    if let Some(task) = task {
+        std::hint::black_box(stack.find_task_position(&task).await.expect("Query to run"));
+    }
+    std::hint::black_box(stack.get_lists().await.expect("Query to run"));
+    std::hint::black_box(stack.count_tasks().await.expect("Query to run"));
+}
+
+async fn connection_per_query(base: &Session, list_id: &Ulid) {
+    // A new connection on the database kept open, preparing its statement anew
+    let session = || async { base.fork().await.expect("Connection to open") };
+    let task = database::get_first_active_task(&session().await, list_id)
+        .await
+        .expect("Query to run");
+    std::hint::black_box(
+        database::get_all_tasks(&session().await, list_id)
+            .await
+            .expect("Query to run"),
+    );
+    if task.is_some() {
+        std::hint::black_box(
+            database::get_all_tasks(&session().await, list_id)
+                .await
+                .expect("Query to run"),
+        );
+    }
+    std::hint::black_box(
+        database::get_lists(&session().await)
+            .await
+            .expect("Query to run"),
+    );
+    std::hint::black_box(
+        database::count_tasks(&session().await)
+            .await
+            .expect("Query to run"),
+    );
+}
+
+fn main() {
+    let dir = BenchDir::new();
+    let path = dir.join("tasks.db");
+
+    let runtime = tokio::runtime::Runtime::new().expect("Runtime to start");
+    runtime.block_on(async {
+        let repo = LibsqlRepository::open(path.clone(), None)
+            .await
+            .expect("Database to open");
+        let stack = TaskStack::load(Arc::new(repo))
+            .await
+            .expect("Stack to load");
+        seed(&stack).await;
+        time("session", "render", || through_session(&stack)).await;
+
+        let list_id = stack.get_list_id();
+        let db = database::open_read_only(&path, None)
+            .await
+            .expect("Database to open");
+        let base = Session::open(db).await.expect("Connection to open");
+        time("connection per query", "render", || {
+            connection_per_query(&base, &list_id)
+        })
+        .await;
+    });
+}
@@ -0,0 +1,56 @@
+//! Times reading the status a status bar or shell prompt polls for, opening
+//! the database each time as `task-stack status` does, both idle and
+//! while another connection holds the write lock partway through a write.
+//!
+//! Run with `cargo bench --bench status`, which prints the time per read of
+//! each with 5 lists of 50 tasks.
+
+mod common;
+
+use common::{seed, time, BenchDir};
+use libsql::params;
+use std::path::Path;
+use std::sync::Arc;
+use task_stack_core::repository::LibsqlRepository;
+use task_stack_core::{database, status, TaskStack};
+
+async fn read(path: &Path) {
+    status::read(path, None, None)
+        .await
+        .expect("Status to be read");
+}
+
+fn main() {
+    let dir = BenchDir::new();
+    let path = dir.join("tasks.db");
+
+    let runtime = tokio::runtime::Runtime::new().expect("Runtime to start");
+    runtime.block_on(async {
+        let repo = LibsqlRepository::open(path.clone(), None)
+            .await
+            .expect("Database to open");
+        let stack = TaskStack::load(Arc::new(repo))
+            .await
+            .expect("Stack to load");
+        seed(&stack).await;
+        time("idle", "read", || read(&path)).await;
+
+        let db = database::init_database(&path, None)
+            .await
+            .expect("Database to open");
+        let writer = database::connect(&db).await.expect("Connection to open");
+        writer
+            .execute("BEGIN IMMEDIATE", params![])
+            .await
+            .expect("Write lock to be taken");
+        writer
+            .execute("UPDATE tasks SET state = 'Completed'", params![])
+            .await
+            .expect("Tasks to be updated");
+        time("writer holding the lock", "read", || read(&path)).await;
+        writer
+            .execute("ROLLBACK", params![])
+            .await
+            .expect("Write to be rolled back");
+    });
+}
@@ -0,0 +1,466 @@
+use crate::{database, tasks::TaskStack};
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where snapshots live, relative to the app data directory.
+pub const BACKUP_DIR: &str = "backups";
+pub const BACKUP_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+pub const DAILY_BACKUPS: usize = 7;
+pub const WEEKLY_BACKUPS: usize = 4;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+/// Snapshots taken before names had milliseconds.
+const SECONDS_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub encrypted: bool,
+    /// The snapshot's contents, or why it can't be restored.
+    pub summary: Result<Summary, String>,
+}
+
+impl Backup {
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub list_count: i64,
+    pub task_count: i64,
+}
+
+fn db_stem(db_path: &Path) -> String {
+    db_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "tasks".to_string())
+}
+
+fn no_file() -> String {
+    "Tasks aren't stored in a file".to_string()
+}
+
+fn sidecar(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(db_path.as_os_str());
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// The directory holding `db_path`'s snapshots, so profiles whose files
+/// share a name don't list, prune or restore each other's. It is named after
+/// the file and a hash of its canonical path, which stays the same from run
+/// to run where `DefaultHasher` may not.
+fn profile_dir(db_path: &Path, backup_dir: &Path) -> PathBuf {
+    // The file itself may not exist yet, so only its directory is resolved
+    let canonical = match (db_path.parent(), db_path.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent)
+            .map(|parent| parent.join(name))
+            .unwrap_or_else(|_| db_path.to_path_buf()),
+        _ => db_path.to_path_buf(),
+    };
+    // FNV-1a
+    let hash = canonical
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+    backup_dir.join(format!("{}-{hash:016x}", db_stem(db_path)))
+}
+
+/// A new, timestamped snapshot file name, creating the backup directory if
+/// needed. Snapshots taken within the same millisecond, like a restore's
+/// safety snapshot, get the next free one rather than overwriting each other.
+fn snapshot_path(db_path: &Path, backup_dir: &Path) -> Result<PathBuf, String> {
+    let backup_dir = profile_dir(db_path, backup_dir);
+    fs::create_dir_all(&backup_dir).map_err(|e| e.to_string())?;
+    let mut created_at = Utc::now();
+    loop {
+        let path = backup_dir.join(format!(
+            "{}-{}.db",
+            db_stem(db_path),
+            created_at.format(TIMESTAMP_FORMAT)
+        ));
+        if !path.exists() {
+            return Ok(path);
+        }
+        created_at += chrono::Duration::milliseconds(1);
+    }
+}
+
+/// Whether writes are waiting in the WAL, which a copy of the file alone
+/// would miss, e.g. after a crash.
+pub fn has_unsaved_writes(db_path: &Path) -> bool {
+    fs::metadata(sidecar(db_path, "-wal")).is_ok_and(|wal| wal.len() > 0)
+}
+
+/// Copies the database file as it is on disk, e.g. at startup before anything
+/// has opened it. Unsaved writes are checkpointed into the file first, which
+/// takes `passphrase` if the file is encrypted.
+pub async fn snapshot_file(
+    db_path: &Path,
+    passphrase: Option<&str>,
+    backup_dir: &Path,
+) -> Result<Option<PathBuf>, String> {
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    if has_unsaved_writes(db_path) {
+        let passphrase = passphrase.filter(|_| database::is_encrypted(db_path));
+        database::retry(|| database::checkpoint_file(db_path, passphrase))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let target = snapshot_path(db_path, backup_dir)?;
+    fs::copy(db_path, &target).map_err(|e| e.to_string())?;
+    prune(db_path, backup_dir)?;
+    Ok(Some(target))
+}
+
+/// Snapshots the open database through its repository, which keeps writes out mid-copy.
+pub async fn snapshot(stack: &TaskStack, backup_dir: &Path) -> Result<PathBuf, String> {
+    let db_path = stack.get_db_path().ok_or_else(no_file)?;
+    let target = snapshot_path(&db_path, backup_dir)?;
+    stack.get_repository().snapshot(&target).await?;
+    prune(&db_path, backup_dir)?;
+    Ok(target)
+}
+
+/// Snapshots for `db_path`, newest first.
+fn snapshot_files(
+    db_path: &Path,
+    backup_dir: &Path,
+) -> Result<Vec<(PathBuf, DateTime<Utc>)>, String> {
+    let prefix = format!("{}-", db_stem(db_path));
+    let entries = match fs::read_dir(profile_dir(db_path, backup_dir)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut files: Vec<(PathBuf, DateTime<Utc>)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        // Skips the -wal and -shm files left by opening a snapshot
+        .filter(|path| path.extension().is_some_and(|extension| extension == "db"))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?;
+            let timestamp = stem.strip_prefix(&prefix)?;
+            let created_at = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+                .or_else(|_| NaiveDateTime::parse_from_str(timestamp, SECONDS_TIMESTAMP_FORMAT))
+                .ok()?;
+            Some((path.clone(), created_at.and_utc()))
+        })
+        .collect();
+    files.sort_by(|(_, a), (_, b)| b.cmp(a));
+    Ok(files)
+}
+
+/// Keeps the newest snapshot of each of the last `DAILY_BACKUPS` days and
+/// `WEEKLY_BACKUPS` weeks, deleting the rest.
+pub fn prune(db_path: &Path, backup_dir: &Path) -> Result<(), String> {
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+
+    for (path, created_at) in snapshot_files(db_path, backup_dir)? {
+        let date = created_at.date_naive();
+        let week = (date.iso_week().year(), date.iso_week().week());
+        let keep_daily = days.len() < DAILY_BACKUPS && days.insert(date);
+        let keep_weekly = weeks.len() < WEEKLY_BACKUPS && weeks.insert(week);
+        if !keep_daily && !keep_weekly {
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+            for suffix in ["-wal", "-shm"] {
+                let _ = fs::remove_file(sidecar(&path, suffix));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Opens a snapshot read-only and checks it is a healthy Task Stack database.
+pub async fn inspect(path: &Path, passphrase: Option<&str>) -> Result<Summary, String> {
+    let passphrase = passphrase.filter(|_| database::is_encrypted(path));
+    let describe = |e: libsql::Error| {
+        if database::is_wrong_passphrase(&e) {
+            "Encrypted with a different passphrase".to_string()
+        } else {
+            e.to_string()
+        }
+    };
+
+    let db = database::open_read_only(path, passphrase)
+        .await
+        .map_err(describe)?;
+    let problems = database::quick_check(&db).await.map_err(describe)?;
+    if !problems.is_empty() {
+        return Err(problems.join("; "));
+    }
+    Ok(Summary {
+        list_count: database::count_rows(&db, "tasklists")
+            .await
+            .map_err(describe)?,
+        task_count: database::count_rows(&db, "tasks").await.map_err(describe)?,
+    })
+}
+
+pub async fn list(
+    db_path: &Path,
+    passphrase: Option<&str>,
+    backup_dir: &Path,
+) -> Result<Vec<Backup>, String> {
+    let mut backups = Vec::new();
+    for (path, created_at) in snapshot_files(db_path, backup_dir)? {
+        let summary = inspect(&path, passphrase).await;
+        backups.push(Backup {
+            encrypted: database::is_encrypted(&path),
+            path,
+            created_at,
+            summary,
+        });
+    }
+    Ok(backups)
+}
+
+async fn find(
+    db_path: &Path,
+    passphrase: Option<&str>,
+    backup_dir: &Path,
+    name: &str,
+) -> Result<Backup, String> {
+    list(db_path, passphrase, backup_dir)
+        .await?
+        .into_iter()
+        .find(|backup| backup.file_name() == name)
+        .ok_or_else(|| format!("Backup {name} not found"))
+}
+
+/// Copies a snapshot next to the database, ready for `swap_in`.
+fn stage(snapshot: &Path, db_path: &Path) -> Result<PathBuf, String> {
+    let staged = sidecar(db_path, ".restore");
+    fs::copy(snapshot, &staged).map_err(|e| e.to_string())?;
+    Ok(staged)
+}
+
+fn swap_in(staged: &Path, db_path: &Path) -> Result<(), String> {
+    for suffix in ["-wal", "-shm"] {
+        match fs::remove_file(sidecar(db_path, suffix)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.to_string()),
+            _ => {}
+        }
+    }
+    fs::rename(staged, db_path).map_err(|e| e.to_string())
+}
+
+/// Moves a database that won't open out of the way, along with its journal
+/// files, so nothing in it is lost.
+pub fn set_aside(db_path: &Path) -> Result<Option<PathBuf>, String> {
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let target = sidecar(
+        db_path,
+        &format!(".broken-{}", Utc::now().format(TIMESTAMP_FORMAT)),
+    );
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let file = sidecar(db_path, suffix);
+        if file.exists() {
+            fs::rename(&file, sidecar(&target, suffix)).map_err(|e| e.to_string())?;
+        }
+    }
+    fs::rename(db_path, &target).map_err(|e| e.to_string())?;
+    Ok(Some(target))
+}
+
+/// Moves a set-aside database and its journal files back to `db_path`.
+fn put_back(aside: &Path, db_path: &Path) -> Result<(), String> {
+    fs::rename(aside, db_path).map_err(|e| e.to_string())?;
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let file = sidecar(aside, suffix);
+        if file.exists() {
+            fs::rename(&file, sidecar(db_path, suffix)).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Sets the database aside and swaps `staged` in, putting the old file back
+/// if the swap fails so the database is never left missing.
+fn replace_broken(staged: &Path, db_path: &Path) -> Result<(), String> {
+    let aside = set_aside(db_path)?;
+    let swapped = swap_in(staged, db_path);
+    if swapped.is_err() {
+        let _ = fs::remove_file(staged);
+        if let Some(aside) = aside {
+            put_back(&aside, db_path)?;
+        }
+    }
+    swapped
+}
+
+/// Validates a snapshot and swaps it in place of the open database, backing
+/// up the current state first. The database is closed while the files are
+/// swapped, so no write lands in the file being replaced.
+pub async fn restore(stack: &TaskStack, backup_dir: &Path, name: &str) -> Result<Summary, String> {
+    let db_path = stack.get_db_path().ok_or_else(no_file)?;
+    let passphrase = stack.get_passphrase();
+    let backup = find(&db_path, passphrase.as_deref(), backup_dir, name).await?;
+    let summary = backup.summary?;
+
+    // Stage the snapshot first so pruning after the safety backup can't remove it
+    let staged = stage(&backup.path, &db_path)?;
+    let closed = match snapshot(stack, backup_dir).await {
+        Ok(_) => stack.get_repository().close().await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = closed {
+        let _ = fs::remove_file(&staged);
+        return Err(e);
+    }
+    let swapped = swap_in(&staged, &db_path);
+    if swapped.is_err() {
+        let _ = fs::remove_file(&staged);
+    }
+    // Reopens the old file when the swap failed
+    stack.reopen().await?;
+    swapped?;
+
+    Ok(summary)
+}
+
+/// Replaces a database that won't open with a snapshot, setting the broken file aside.
+pub async fn recover(db_path: &Path, backup_dir: &Path, name: &str) -> Result<(), String> {
+    let backup = find(db_path, None, backup_dir, name).await?;
+    // Encrypted snapshots are checked by the unlock page once they're in place
+    if !backup.encrypted {
+        backup.summary?;
+    }
+
+    let staged = stage(&backup.path, db_path)?;
+    replace_broken(&staged, db_path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+
+    fn titles(tasks: Vec<crate::Task>) -> Vec<String> {
+        tasks.into_iter().map(|task| task.title).collect()
+    }
+
+    #[tokio::test]
+    async fn restores_while_the_stack_is_open() {
+        let dir = TempDir::new();
+        let backup_dir = dir.join("backups");
+        let db_path = dir.join("tasks.db");
+        let stack = testing::disk_stack(&db_path).await;
+        stack.push("Kept".to_string(), None).await.unwrap();
+        let backup = snapshot(&stack, &backup_dir).await.unwrap();
+        stack.push("Replaced".to_string(), None).await.unwrap();
+
+        let name = backup.file_name().unwrap().to_string_lossy().into_owned();
+        let summary = restore(&stack, &backup_dir, &name).await.unwrap();
+        assert_eq!(summary.task_count, 1);
+        assert_eq!(titles(stack.get_tasks().await.unwrap()), ["Kept"]);
+
+        // Writes after the restore land in the restored file
+        stack.push("Added".to_string(), None).await.unwrap();
+        let reopened = testing::disk_stack(&db_path).await;
+        assert_eq!(
+            titles(reopened.get_tasks().await.unwrap()),
+            ["Kept", "Added"]
+        );
+    }
+
+    #[tokio::test]
+    async fn names_snapshots_taken_in_the_same_second_apart() {
+        let dir = TempDir::new();
+        let backup_dir = dir.join("backups");
+        let db_path = dir.join("tasks.db");
+        let stack = testing::disk_stack(&db_path).await;
+        let first = snapshot(&stack, &backup_dir).await.unwrap();
+        // Even within the same millisecond
+        let next = snapshot_path(&db_path, &backup_dir).unwrap();
+        assert_ne!(first, next);
+
+        // Older names without milliseconds are still listed
+        let older = profile_dir(&db_path, &backup_dir).join("tasks-20240102T030405Z.db");
+        fs::copy(&first, &older).unwrap();
+        let backups = list(&db_path, None, &backup_dir).await.unwrap();
+        let paths: Vec<PathBuf> = backups.into_iter().map(|backup| backup.path).collect();
+        assert_eq!(paths, [first, older]);
+    }
+
+    #[tokio::test]
+    async fn keeps_backups_of_each_profile_apart() {
+        let dir = TempDir::new();
+        let backup_dir = dir.join("backups");
+        let work = dir.join("work");
+        let home = dir.join("home");
+        fs::create_dir_all(&work).unwrap();
+        fs::create_dir_all(&home).unwrap();
+        // Same file name, different profiles
+        let work = testing::disk_stack(&work.join("tasks.db")).await;
+        let home = testing::disk_stack(&home.join("tasks.db")).await;
+
+        let backup = snapshot(&work, &backup_dir).await.unwrap();
+        let name = backup.file_name().unwrap().to_string_lossy().into_owned();
+        let work_path = work.get_db_path().unwrap();
+        let home_path = home.get_db_path().unwrap();
+        assert_eq!(list(&work_path, None, &backup_dir).await.unwrap().len(), 1);
+        assert!(list(&home_path, None, &backup_dir)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(restore(&home, &backup_dir, &name).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn checkpoints_unsaved_writes_before_copying_the_file() {
+        let dir = TempDir::new();
+        let backup_dir = dir.join("backups");
+        let db_path = dir.join("tasks.db");
+        // Left open, the stack's writes stay in the WAL as after a crash
+        let stack = testing::disk_stack(&db_path).await;
+        stack.push("Unsaved".to_string(), None).await.unwrap();
+        assert!(has_unsaved_writes(&db_path));
+
+        let target = snapshot_file(&db_path, None, &backup_dir)
+            .await
+            .unwrap()
+            .expect("Database to be backed up");
+        assert!(!has_unsaved_writes(&db_path));
+        let summary = inspect(&target, None).await.unwrap();
+        assert_eq!(summary.task_count, 1);
+    }
+
+    #[test]
+    fn puts_the_database_back_when_the_swap_fails() {
+        let dir = TempDir::new();
+        let db_path = dir.join("tasks.db");
+        fs::write(&db_path, "broken").unwrap();
+        fs::write(sidecar(&db_path, "-journal"), "journal").unwrap();
+
+        // Nothing was staged, so the rename fails
+        let staged = sidecar(&db_path, ".restore");
+        assert!(replace_broken(&staged, &db_path).is_err());
+        assert_eq!(fs::read_to_string(&db_path).unwrap(), "broken");
+        assert_eq!(
+            fs::read_to_string(sidecar(&db_path, "-journal")).unwrap(),
+            "journal"
+        );
+    }
+}
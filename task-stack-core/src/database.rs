@@ -0,0 +1,888 @@
+use crate::repository::{TaskCounts, Write};
+use crate::tasks::{Task, TaskList, TaskState};
+use chrono::{DateTime, SecondsFormat, Utc};
+use libsql::{
+    de::from_row, params, Builder, Cipher, Connection, Database, EncryptionConfig, OpenFlags,
+    Statement, TransactionBehavior,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{MappedMutexGuard, MutexGuard};
+use ulid::Ulid;
+
+/// Every plaintext SQLite file starts with this header; encrypted files don't.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// SQLite's `SQLITE_NOTADB`, which is what a wrong passphrase looks like.
+const SQLITE_NOTADB: std::ffi::c_int = 26;
+
+/// SQLite's `SQLITE_BUSY` and `SQLITE_LOCKED`: another connection holds the lock.
+const SQLITE_BUSY: std::ffi::c_int = 5;
+const SQLITE_LOCKED: std::ffi::c_int = 6;
+
+/// How long a connection waits on another's lock before giving up.
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Attempts `retry` makes when the busy timeout still wasn't enough.
+const LOCKED_RETRIES: u32 = 3;
+
+/// Why no database is open yet, which decides the page shown in its place.
+#[derive(Debug, Clone)]
+pub enum Unopened {
+    /// Encrypted and waiting for its passphrase.
+    Locked(PathBuf),
+    /// Failed to open, so the recovery page explains why instead of panicking.
+    Broken { path: PathBuf, error: String },
+}
+
+/// Reads a row, or logs and skips it so one bad row doesn't stop the rest
+/// from loading. The integrity check reports and repairs it.
+fn readable<'de, T: serde::Deserialize<'de>>(row: &'de libsql::Row, what: &str) -> Option<T> {
+    from_row(row)
+        .inspect_err(|e| eprintln!("Skipping unreadable {what}: {e}"))
+        .ok()
+}
+
+/// Formats a time the way `strftime('%FT%R:%f+00:00')` does, so stored times sort as text.
+fn timestamp(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, false)
+}
+
+pub fn is_encrypted(db_path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    match std::fs::File::open(db_path).and_then(|mut file| file.read_exact(&mut header)) {
+        Ok(()) => &header != SQLITE_HEADER,
+        // Missing or empty files will be created as plaintext
+        Err(_) => false,
+    }
+}
+
+pub fn is_wrong_passphrase(error: &libsql::Error) -> bool {
+    matches!(error, libsql::Error::SqliteFailure(SQLITE_NOTADB, _))
+}
+
+pub fn is_locked(error: &libsql::Error) -> bool {
+    // Extended result codes keep the primary code in the low byte
+    matches!(error, libsql::Error::SqliteFailure(code, _) if matches!(code & 0xff, SQLITE_BUSY | SQLITE_LOCKED))
+}
+
+/// Runs `op` again, with a growing pause, while the database is locked by
+/// another process, e.g. a script writing to the file.
+pub async fn retry<T, F, Fut>(mut op: F) -> Result<T, libsql::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, libsql::Error>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Err(e) if is_locked(&e) && attempt < LOCKED_RETRIES => {
+                attempt += 1;
+                eprintln!("Database is locked, retrying ({attempt}/{LOCKED_RETRIES}): {e}");
+                tokio::time::sleep(Duration::from_millis(100 * u64::from(attempt))).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn encryption_config(passphrase: &str) -> EncryptionConfig {
+    EncryptionConfig {
+        cipher: Cipher::Aes256Cbc,
+        encryption_key: passphrase.to_string().into(),
+    }
+}
+
+/// Runs a pragma that can't take bound parameters, draining any rows it returns.
+async fn pragma(conn: &Connection, name: &str, value: &str) -> Result<(), libsql::Error> {
+    let mut rows = conn
+        .query(
+            &format!("PRAGMA {name} = '{}'", value.replace('\'', "''")),
+            params![],
+        )
+        .await?;
+    while rows.next().await?.is_some() {}
+    Ok(())
+}
+
+pub async fn init_database(
+    db_path: &Path,
+    passphrase: Option<&str>,
+) -> Result<Database, libsql::Error> {
+    let mut builder = Builder::new_local(db_path);
+    if let Some(passphrase) = passphrase {
+        builder = builder.encryption_config(encryption_config(passphrase));
+    }
+    let db = builder.build().await?;
+
+    // Create tables if they don't exist
+    let conn = db.connect()?;
+    pragma(&conn, "busy_timeout", &BUSY_TIMEOUT.as_millis().to_string()).await?;
+    // Some builds enforce them by default, which would stop `migrate` copying orphans
+    conn.execute("PRAGMA foreign_keys = OFF", params![]).await?;
+    // Readers don't block the writer, and it is stored in the file so other processes use it too
+    pragma(&conn, "journal_mode", "WAL").await?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tasklists (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        params![],
+    )
+    .await?;
+    conn.execute(TASKS_TABLE, params![]).await?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        params![],
+    )
+    .await?;
+    migrate(&conn).await?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS tasks_list_id ON tasks (list_id)",
+        params![],
+    )
+    .await?;
+
+    Ok(db)
+}
+
+/// Deleting a list deletes its tasks; a task can't point at a list that doesn't exist.
+const TASKS_TABLE: &str = "CREATE TABLE IF NOT EXISTS tasks (
+    id TEXT PRIMARY KEY,
+    list_id TEXT NOT NULL REFERENCES tasklists (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    title TEXT NOT NULL,
+    description TEXT,
+    created_at TEXT NOT NULL,
+    state TEXT NOT NULL,
+    completed_at TEXT,
+    position INTEGER
+)";
+
+const SCHEMA_VERSION: i64 = 1;
+
+/// Brings databases created by older versions up to `SCHEMA_VERSION`, tracked
+/// in SQLite's `user_version`. Runs on a connection without foreign keys
+/// enforced so rebuilt tables can be copied in any order.
+async fn migrate(conn: &Connection) -> Result<(), libsql::Error> {
+    let mut rows = conn.query("PRAGMA user_version", params![]).await?;
+    let version: i64 = match rows.next().await? {
+        Some(row) => row.get(0)?,
+        None => 0,
+    };
+    drop(rows);
+
+    if version < 1 {
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*) FROM pragma_foreign_key_list('tasks')",
+                params![],
+            )
+            .await?;
+        let foreign_keys: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+        drop(rows);
+
+        // Tables created before foreign keys existed have to be rebuilt to gain them.
+        // Orphaned tasks are copied as-is and reassigned by the integrity check.
+        if foreign_keys == 0 {
+            eprintln!("Migrating tasks table to add foreign keys");
+            let tx = conn.transaction().await?;
+            tx.execute("ALTER TABLE tasks RENAME TO tasks_v0", params![])
+                .await?;
+            tx.execute(TASKS_TABLE, params![]).await?;
+            tx.execute(
+                "INSERT INTO tasks (id, list_id, title, description, created_at, state, completed_at, position)
+                 SELECT id, list_id, title, description, created_at, state, completed_at, position
+                 FROM tasks_v0",
+                params![],
+            )
+            .await?;
+            tx.execute("DROP TABLE tasks_v0", params![]).await?;
+            tx.commit().await?;
+        }
+    }
+
+    if version < SCHEMA_VERSION {
+        conn.execute(
+            &format!("PRAGMA user_version = {SCHEMA_VERSION}"),
+            params![],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+fn busy(message: &str) -> libsql::Error {
+    libsql::Error::SqliteFailure(SQLITE_BUSY, message.to_string())
+}
+
+/// Copies the whole WAL into the database file and truncates it, failing as
+/// busy when another connection kept part of it from being copied.
+pub async fn checkpoint(conn: &Connection) -> Result<(), libsql::Error> {
+    let mut rows = conn
+        .query("PRAGMA wal_checkpoint(TRUNCATE)", params![])
+        .await?;
+    let blocked: i64 = match rows.next().await? {
+        Some(row) => row.get(0)?,
+        None => 0,
+    };
+    while rows.next().await?.is_some() {}
+    if blocked != 0 {
+        return Err(busy("The WAL couldn't be checkpointed"));
+    }
+    Ok(())
+}
+
+/// Checkpoints a database nothing has open, e.g. one a crash left with writes
+/// in its WAL, without migrating it.
+pub async fn checkpoint_file(
+    db_path: &Path,
+    passphrase: Option<&str>,
+) -> Result<(), libsql::Error> {
+    let mut builder = Builder::new_local(db_path);
+    if let Some(passphrase) = passphrase {
+        builder = builder.encryption_config(encryption_config(passphrase));
+    }
+    let db = builder.build().await?;
+    checkpoint(&connect(&db).await?).await
+}
+
+/// Checkpoints, then starts a read transaction on `conn` so nothing more is
+/// checkpointed into the file at `db_path` until it commits, leaving the file
+/// whole for copying. Fails as busy if a write got in between.
+pub async fn begin_snapshot(conn: &Connection, db_path: &Path) -> Result<(), libsql::Error> {
+    checkpoint(conn).await?;
+    conn.execute("BEGIN", params![]).await?;
+    // Reading inside a transaction takes the shared lock that keeps checkpoints out
+    let held = async {
+        let mut rows = conn
+            .query("SELECT COUNT(*) FROM tasklists", params![])
+            .await?;
+        rows.next().await?;
+        Ok(())
+    }
+    .await;
+
+    let mut wal = db_path.as_os_str().to_owned();
+    wal.push("-wal");
+    let result = match held {
+        Ok(()) if std::fs::metadata(&wal).is_ok_and(|wal| wal.len() > 0) => Err(busy(
+            "The database was written to before the snapshot began",
+        )),
+        held => held,
+    };
+    if result.is_err() {
+        conn.execute("ROLLBACK", params![]).await?;
+    }
+    result
+}
+
+/// Opens a connection with foreign keys enforced, which SQLite leaves off by
+/// default, that waits out other writers instead of failing straight away.
+pub async fn connect(db: &Database) -> Result<Connection, libsql::Error> {
+    let conn = db.connect()?;
+    pragma(&conn, "busy_timeout", &BUSY_TIMEOUT.as_millis().to_string()).await?;
+    conn.execute("PRAGMA foreign_keys = ON", params![]).await?;
+    Ok(conn)
+}
+
+/// A database handle with one connection kept open for its queries, and the
+/// statements prepared on it so far.
+pub struct Session {
+    db: Arc<Database>,
+    conn: Connection,
+    statements: tokio::sync::Mutex<HashMap<&'static str, Statement>>,
+}
+
+impl Session {
+    pub async fn open(db: Database) -> Result<Self, libsql::Error> {
+        let conn = connect(&db).await?;
+        Ok(Self {
+            db: Arc::new(db),
+            conn,
+            statements: Default::default(),
+        })
+    }
+
+    /// Another session on a new connection to the same database, with no
+    /// statements prepared yet.
+    pub async fn fork(&self) -> Result<Self, libsql::Error> {
+        let conn = connect(&self.db).await?;
+        Ok(Self {
+            db: self.db.clone(),
+            conn,
+            statements: Default::default(),
+        })
+    }
+
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+
+    /// The cached statement for `sql`, prepared on first use. Holding it keeps
+    /// other queries off the connection until its rows have been read.
+    pub async fn prepare(
+        &self,
+        sql: &'static str,
+    ) -> Result<MappedMutexGuard<'_, Statement>, libsql::Error> {
+        let mut statements = self.statements.lock().await;
+        if !statements.contains_key(sql) {
+            let stmt = self.conn.prepare(sql).await?;
+            statements.insert(sql, stmt);
+        }
+        let stmt = MutexGuard::map(statements, |statements| {
+            statements.get_mut(sql).expect("Statement to be cached")
+        });
+        stmt.reset();
+        Ok(stmt)
+    }
+}
+
+/// Opens an existing database without creating or migrating anything.
+pub async fn open_read_only(
+    db_path: &Path,
+    passphrase: Option<&str>,
+) -> Result<Database, libsql::Error> {
+    let mut builder = Builder::new_local(db_path).flags(OpenFlags::SQLITE_OPEN_READ_ONLY);
+    if let Some(passphrase) = passphrase {
+        builder = builder.encryption_config(encryption_config(passphrase));
+    }
+    builder.build().await
+}
+
+/// Re-encrypts the database with a new passphrase, or decrypts it when `None`,
+/// and returns a handle opened with the new key.
+pub async fn rekey(
+    session: &Session,
+    db_path: &Path,
+    passphrase: Option<&str>,
+) -> Result<Database, libsql::Error> {
+    // Rekeying isn't supported in WAL mode, and leaving it needs the only open
+    // connection, so this runs on the session's own; `init_database` turns WAL back on.
+    // Holding the statements keeps other queries off the connection meanwhile
+    let _statements = session.statements.lock().await;
+    pragma(&session.conn, "journal_mode", "DELETE").await?;
+    pragma(&session.conn, "rekey", passphrase.unwrap_or_default()).await?;
+    init_database(db_path, passphrase).await
+}
+
+const INSERT_TASK: &str =
+    "INSERT INTO tasks (id, list_id, title, description, created_at, state, completed_at, position)
+     VALUES (?, ?, ?, ?, ?, ?, ?, ?)";
+
+fn task_params(task: &Task, position: i64) -> impl libsql::params::IntoParams {
+    params![
+        task.id.to_string(),
+        task.list_id.to_string(),
+        task.title.clone(),
+        task.description.clone(),
+        timestamp(&task.created_at),
+        match task.state {
+            TaskState::Active => "Active",
+            TaskState::Completed => "Completed",
+        },
+        task.completed_at.map(|dt| dt.to_rfc3339()),
+        position,
+    ]
+}
+
+pub async fn insert_task(
+    session: &Session,
+    task: &Task,
+    position: i64,
+) -> Result<(), libsql::Error> {
+    session
+        .prepare(INSERT_TASK)
+        .await?
+        .execute(task_params(task, position))
+        .await?;
+    Ok(())
+}
+
+/// Adds `task` after the last in its list, working out its position in the
+/// same statement so concurrent pushes can't share one.
+pub async fn push_task(session: &Session, task: &Task) -> Result<(), libsql::Error> {
+    session
+        .prepare(
+            "INSERT INTO tasks (id, list_id, title, description, created_at, state, completed_at, position)
+             SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, COALESCE(MAX(position), ?8) + 1
+             FROM tasks WHERE list_id = ?2",
+        )
+        .await?
+        // An empty list starts at 1
+        .execute(task_params(task, 0))
+        .await?;
+    Ok(())
+}
+
+pub async fn get_all_tasks(
+    session: &Session,
+    list_id: &Ulid,
+) -> Result<Vec<(Task, i64)>, libsql::Error> {
+    let mut stmt = session
+        .prepare(
+            "SELECT id, list_id, title, description, created_at, state, completed_at, position
+             FROM tasks
+             WHERE list_id = ?
+             ORDER BY
+                CASE state
+                    WHEN 'Completed' THEN 0
+                    ELSE 1
+                END,
+                CASE state
+                    WHEN 'Completed' THEN completed_at
+                    ELSE NULL
+                END DESC NULLS LAST,
+                CASE state
+                    WHEN 'Active' THEN position
+                    ELSE NULL
+                END ASC NULLS LAST",
+        )
+        .await?;
+
+    let mut rows = stmt.query(params![list_id.to_string()]).await?;
+    let mut tasks = Vec::new();
+
+    while let Some(row) = rows.next().await? {
+        if let Some(task) = readable::<Task>(&row, "task") {
+            let position: i64 = row.get(7)?;
+            tasks.push((task, position));
+        }
+    }
+
+    Ok(tasks)
+}
+
+pub async fn get_current_tasks(
+    session: &Session,
+    list_id: &Ulid,
+    completed_window_hours: u32,
+) -> Result<Vec<(Task, i64)>, libsql::Error> {
+    let mut stmt = session
+        .prepare(
+            "SELECT id, list_id, title, description, created_at, state, completed_at, position
+         FROM tasks
+         WHERE list_id = ? AND (state = 'Active' OR (state = 'Completed' AND completed_at >= strftime('%FT%R:%f+00:00', 'now', ?)))
+         ORDER BY position ASC",
+        )
+        .await?;
+
+    let mut rows = stmt
+        .query(params![
+            list_id.to_string(),
+            format!("-{completed_window_hours} hours")
+        ])
+        .await?;
+    let mut tasks = Vec::new();
+
+    while let Some(row) = rows.next().await? {
+        if let Some(task) = readable::<Task>(&row, "task") {
+            let position: i64 = row.get(7)?;
+            tasks.push((task, position));
+        }
+    }
+    Ok(tasks)
+}
+
+pub async fn get_active_tasks(
+    session: &Session,
+    list_id: &Ulid,
+) -> Result<Vec<(Task, i64)>, libsql::Error> {
+    let mut stmt = session
+        .prepare(
+            "SELECT id, list_id, title, description, created_at, state, completed_at, position
+         FROM tasks
+         WHERE list_id = ? AND (state = 'Active')
+         ORDER BY position ASC",
+        )
+        .await?;
+
+    let mut rows = stmt.query(params![list_id.to_string()]).await?;
+    let mut tasks = Vec::new();
+
+    while let Some(row) = rows.next().await? {
+        if let Some(task) = readable::<Task>(&row, "task") {
+            let position: i64 = row.get(7)?;
+            tasks.push((task, position));
+        }
+    }
+    Ok(tasks)
+}
+
+pub async fn get_first_active_task(
+    session: &Session,
+    list_id: &Ulid,
+) -> Result<Option<Task>, libsql::Error> {
+    let mut stmt = session
+        .prepare(
+            "SELECT id, list_id, title, description, created_at, state, completed_at, position
+         FROM tasks
+         WHERE list_id = ? AND (state = 'Active')
+         ORDER BY position ASC
+         LIMIT 1",
+        )
+        .await?;
+
+    let mut rows = stmt.query(params![list_id.to_string()]).await?;
+
+    while let Some(row) = rows.next().await? {
+        if let Some(task) = readable::<Task>(&row, "task") {
+            return Ok(Some(task));
+        }
+    }
+    Ok(None)
+}
+
+pub async fn update_task_state(
+    session: &Session,
+    id: &Ulid,
+    state: TaskState,
+    completed_at: Option<DateTime<Utc>>,
+) -> Result<(), libsql::Error> {
+    let state_str = match state {
+        TaskState::Active => "Active",
+        TaskState::Completed => "Completed",
+    };
+    let completed_at_str = completed_at.map(|dt| dt.to_rfc3339());
+    session
+        .prepare(
+            "UPDATE tasks
+         SET state = ?, completed_at = ?
+         WHERE id = ?",
+        )
+        .await?
+        .execute(params![state_str, completed_at_str, id.to_string(),])
+        .await?;
+    Ok(())
+}
+
+const UPDATE_POSITION: &str = "UPDATE tasks SET position = ? WHERE id = ?";
+
+pub async fn update_task_position(
+    session: &Session,
+    id: &Ulid,
+    position: i64,
+) -> Result<(), libsql::Error> {
+    session
+        .prepare(UPDATE_POSITION)
+        .await?
+        .execute(params![position, id.to_string()])
+        .await?;
+    Ok(())
+}
+
+pub async fn move_task_to_end(session: &Session, id: &Ulid) -> Result<(), libsql::Error> {
+    session
+        .prepare(
+            "UPDATE tasks SET position = (
+                SELECT MAX(other.position) + 1 FROM tasks AS other WHERE other.list_id = tasks.list_id
+             )
+             WHERE id = ?",
+        )
+        .await?
+        .execute(params![id.to_string()])
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_task(session: &Session, id: &Ulid) -> Result<(), libsql::Error> {
+    session
+        .prepare("DELETE FROM tasks WHERE id = ?")
+        .await?
+        .execute(params![id.to_string()])
+        .await?;
+    Ok(())
+}
+
+pub async fn get_lists(session: &Session) -> Result<Vec<TaskList>, libsql::Error> {
+    let mut stmt = session
+        .prepare(
+            "SELECT id, name, created_at
+             FROM tasklists
+             ORDER BY created_at ASC, id ASC",
+        )
+        .await?;
+    let mut rows = stmt.query(params![]).await?;
+    let mut lists = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Some(list) = readable::<TaskList>(&row, "list") {
+            lists.push(list);
+        }
+    }
+    Ok(lists)
+}
+
+pub async fn create_list(session: &Session, name: &str) -> Result<Ulid, libsql::Error> {
+    let id = Ulid::new();
+    session
+        .prepare("INSERT INTO tasklists (id, name, created_at) VALUES (?, ?, strftime('%FT%R:%f+00:00'))")
+        .await?
+        .execute(params![id.to_string(), name])
+        .await?;
+    Ok(id)
+}
+
+const INSERT_LIST: &str = "INSERT INTO tasklists (id, name, created_at) VALUES (?, ?, ?)";
+
+fn list_params(list: &TaskList) -> impl libsql::params::IntoParams {
+    params![
+        list.id.to_string(),
+        list.name.clone(),
+        timestamp(&list.created_at)
+    ]
+}
+
+/// Adds a list as it is, e.g. when importing, rather than as a new one.
+pub async fn insert_list(session: &Session, list: &TaskList) -> Result<(), libsql::Error> {
+    session
+        .prepare(INSERT_LIST)
+        .await?
+        .execute(list_params(list))
+        .await?;
+    Ok(())
+}
+
+pub async fn get_highest_position(session: &Session, list_id: &Ulid) -> Result<i64, libsql::Error> {
+    let mut stmt = session
+        .prepare("SELECT MAX(position) FROM tasks WHERE list_id = ?")
+        .await?;
+    let mut rows = stmt.query(params![list_id.to_string()]).await?;
+    let mut position = None;
+    while let Some(row) = rows.next().await? {
+        let max_position: Option<i64> = row.get(0)?;
+        position = max_position;
+    }
+    Ok(position.unwrap_or(0))
+}
+
+pub async fn count_rows(db: &Database, table: &str) -> Result<i64, libsql::Error> {
+    let conn = connect(db).await?;
+    let mut rows = conn
+        .query(&format!("SELECT COUNT(*) FROM {table}"), params![])
+        .await?;
+    match rows.next().await? {
+        Some(row) => row.get(0),
+        None => Ok(0),
+    }
+}
+
+/// Runs SQLite's `quick_check`, returning the problems it found.
+pub async fn quick_check(db: &Database) -> Result<Vec<String>, libsql::Error> {
+    let conn = connect(db).await?;
+    let mut rows = conn.query("PRAGMA quick_check", params![]).await?;
+    let mut problems = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let message: String = row.get(0)?;
+        if message != "ok" {
+            problems.push(message);
+        }
+    }
+    Ok(problems)
+}
+
+const DELETE_LIST: &str = "DELETE FROM tasklists WHERE id = ?";
+
+pub async fn delete_list(session: &Session, id: &Ulid) -> Result<(), libsql::Error> {
+    session
+        .prepare(DELETE_LIST)
+        .await?
+        .execute(params![id.to_string()])
+        .await?;
+    Ok(())
+}
+
+/// Makes every write in one transaction, on a connection of its own so the
+/// session's queries can't land in the middle of it.
+pub async fn apply(db: &Database, writes: &[Write]) -> Result<(), libsql::Error> {
+    let conn = connect(db).await?;
+    // Taking the write lock up front waits out other writers instead of failing partway
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .await?;
+    for write in writes {
+        match write {
+            Write::InsertList(list) => tx.execute(INSERT_LIST, list_params(list)).await?,
+            Write::InsertTask(task, position) => {
+                tx.execute(INSERT_TASK, task_params(task, *position))
+                    .await?
+            }
+            Write::SetPosition(id, position) => {
+                tx.execute(UPDATE_POSITION, params![*position, id.to_string()])
+                    .await?
+            }
+            Write::DeleteList(id) => tx.execute(DELETE_LIST, params![id.to_string()]).await?,
+            Write::SetSetting(key, value) => {
+                tx.execute(SET_SETTING, params![key.as_str(), value.as_str()])
+                    .await?
+            }
+        };
+    }
+    tx.commit().await
+}
+
+/// Active and total task counts for every list that has tasks.
+pub async fn count_tasks(session: &Session) -> Result<HashMap<Ulid, TaskCounts>, libsql::Error> {
+    let mut stmt = session
+        .prepare("SELECT list_id, SUM(state = 'Active'), COUNT(*) FROM tasks GROUP BY list_id")
+        .await?;
+    let mut rows = stmt.query(params![]).await?;
+    let mut counts = HashMap::new();
+    while let Some(row) = rows.next().await? {
+        let list_id: String = row.get(0)?;
+        let active: i64 = row.get(1)?;
+        let total: i64 = row.get(2)?;
+        let Ok(list_id) = Ulid::from_string(&list_id)
+            .inspect_err(|e| eprintln!("Skipping counts for list {list_id:?}: {e}"))
+        else {
+            continue;
+        };
+        counts.insert(
+            list_id,
+            TaskCounts {
+                active: active as usize,
+                total: total as usize,
+            },
+        );
+    }
+    Ok(counts)
+}
+
+pub async fn get_setting(session: &Session, key: &str) -> Result<Option<String>, libsql::Error> {
+    let mut stmt = session
+        .prepare("SELECT value FROM settings WHERE key = ?")
+        .await?;
+    let mut rows = stmt.query(params![key]).await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row.get(0)?)),
+        None => Ok(None),
+    }
+}
+
+const SET_SETTING: &str = "INSERT INTO settings (key, value) VALUES (?, ?)
+     ON CONFLICT(key) DO UPDATE SET value = excluded.value";
+
+pub async fn set_setting(session: &Session, key: &str, value: &str) -> Result<(), libsql::Error> {
+    session
+        .prepare(SET_SETTING)
+        .await?
+        .execute(params![key, value])
+        .await?;
+    Ok(())
+}
+
+/// Bumped whenever another connection commits, but not by this session's own writes.
+pub async fn data_version(session: &Session) -> Result<i64, libsql::Error> {
+    let mut stmt = session.prepare("PRAGMA data_version").await?;
+    let mut rows = stmt.query(params![]).await?;
+    match rows.next().await? {
+        Some(row) => row.get(0),
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    async fn count(conn: &Connection, sql: &str) -> i64 {
+        let mut rows = conn.query(sql, params![]).await.unwrap();
+        rows.next().await.unwrap().unwrap().get(0).unwrap()
+    }
+
+    #[tokio::test]
+    async fn migrates_tasks_without_foreign_keys() {
+        let dir = TempDir::new();
+        let path = dir.join("tasks.db");
+        let list_id = Ulid::new().to_string();
+        {
+            // The schema from before foreign keys, with an orphaned task
+            let conn = Builder::new_local(&path)
+                .build()
+                .await
+                .unwrap()
+                .connect()
+                .unwrap();
+            conn.execute("PRAGMA foreign_keys = OFF", params![])
+                .await
+                .unwrap();
+            conn.execute_batch(
+                "CREATE TABLE tasklists (id TEXT PRIMARY KEY, name TEXT NOT NULL, created_at TEXT NOT NULL);
+                 CREATE TABLE tasks (
+                     id TEXT PRIMARY KEY, list_id TEXT NOT NULL, title TEXT NOT NULL,
+                     description TEXT, created_at TEXT NOT NULL, state TEXT NOT NULL,
+                     completed_at TEXT, position INTEGER
+                 );",
+            )
+            .await
+            .unwrap();
+            conn.execute(
+                "INSERT INTO tasklists VALUES (?, 'Inbox', '2024-01-01T00:00:00.000+00:00')",
+                params![list_id.clone()],
+            )
+            .await
+            .unwrap();
+            for (title, list_id) in [
+                ("Kept", list_id.clone()),
+                ("Orphan", Ulid::new().to_string()),
+            ] {
+                conn.execute(
+                    "INSERT INTO tasks (id, list_id, title, created_at, state, position)
+                     VALUES (?, ?, ?, '2024-01-01T00:00:00.000+00:00', 'Active', 0)",
+                    params![Ulid::new().to_string(), list_id, title],
+                )
+                .await
+                .unwrap();
+            }
+        }
+
+        let db = init_database(&path, None).await.unwrap();
+        let conn = connect(&db).await.unwrap();
+        assert_eq!(count(&conn, "PRAGMA user_version").await, SCHEMA_VERSION);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM pragma_foreign_key_list('tasks')"
+            )
+            .await,
+            1
+        );
+        // Orphans survive for the integrity check to reassign
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM tasks").await, 2);
+
+        // Opening again leaves the migrated table alone
+        drop(conn);
+        let db = init_database(&path, None).await.unwrap();
+        let conn = connect(&db).await.unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM tasks").await, 2);
+    }
+
+    #[tokio::test]
+    async fn foreign_keys_tie_tasks_to_their_list() {
+        let dir = TempDir::new();
+        let db = init_database(&dir.join("tasks.db"), None).await.unwrap();
+        let session = Session::open(db).await.unwrap();
+        let list_id = create_list(&session, "Inbox").await.unwrap();
+        insert_task(&session, &Task::new("Task".to_string(), list_id), 0)
+            .await
+            .unwrap();
+
+        // A task can't be added to a list that doesn't exist
+        let orphan = Task::new("Orphan".to_string(), Ulid::new());
+        assert!(insert_task(&session, &orphan, 0).await.is_err());
+
+        // Deleting the list deletes its tasks
+        delete_list(&session, &list_id).await.unwrap();
+        assert_eq!(count(&session.conn, "SELECT COUNT(*) FROM tasks").await, 0);
+    }
+}
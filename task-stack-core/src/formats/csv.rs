@@ -0,0 +1,425 @@
+use super::{start_of_day, Entry, ListRecord, TaskRecord, DATE_FORMAT};
+use crate::tasks::TaskState;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashMap;
+use ulid::Ulid;
+
+/// A column that can be exported, or mapped to when importing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Column {
+    Id,
+    List,
+    Title,
+    Description,
+    State,
+    CreatedAt,
+    CompletedAt,
+    Position,
+    /// Hours from creation to completion, or until now for active tasks.
+    TimeInStack,
+}
+
+impl Column {
+    pub const ALL: [Column; 9] = [
+        Column::Id,
+        Column::List,
+        Column::Title,
+        Column::Description,
+        Column::State,
+        Column::CreatedAt,
+        Column::CompletedAt,
+        Column::Position,
+        Column::TimeInStack,
+    ];
+
+    /// The header the column is written with and named by in settings.
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::List => "list",
+            Column::Title => "title",
+            Column::Description => "description",
+            Column::State => "state",
+            Column::CreatedAt => "created_at",
+            Column::CompletedAt => "completed_at",
+            Column::Position => "position",
+            Column::TimeInStack => "time_in_stack",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Column, String> {
+        Column::ALL
+            .into_iter()
+            .find(|column| column.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("Unknown column {name}"))
+    }
+
+    /// Reads a comma-separated list of column names.
+    pub fn parse_list(names: &str) -> Result<Vec<Column>, String> {
+        names
+            .split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(Column::from_name)
+            .collect()
+    }
+
+    fn value(self, list: &ListRecord, task: &TaskRecord, now: DateTime<Utc>) -> String {
+        match self {
+            Column::Id => task.id.to_string(),
+            Column::List => list.name.clone(),
+            Column::Title => task.title.clone(),
+            Column::Description => task.description.clone().unwrap_or_default(),
+            Column::State => match task.state {
+                TaskState::Active => "active".to_string(),
+                TaskState::Completed => "completed".to_string(),
+            },
+            Column::CreatedAt => task.created_at.to_rfc3339(),
+            Column::CompletedAt => task
+                .completed_at
+                .map(|at| at.to_rfc3339())
+                .unwrap_or_default(),
+            Column::Position => task.position.to_string(),
+            Column::TimeInStack => {
+                let end = task.completed_at.unwrap_or(now);
+                format!("{:.2}", (end - task.created_at).num_minutes() as f64 / 60.0)
+            }
+        }
+    }
+}
+
+/// Which tasks and columns an export writes.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub columns: Vec<Column>,
+    /// Only tasks completed on or after this day.
+    pub completed_from: Option<NaiveDate>,
+    /// Only tasks completed on or before this day.
+    pub completed_to: Option<NaiveDate>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            columns: Column::ALL.to_vec(),
+            completed_from: None,
+            completed_to: None,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// With either end of the range set, only completed tasks inside it are kept.
+    fn includes(&self, task: &TaskRecord) -> bool {
+        if self.completed_from.is_none() && self.completed_to.is_none() {
+            return true;
+        }
+        let Some(completed_on) = task.completed_at.map(|at| at.date_naive()) else {
+            return false;
+        };
+        self.completed_from.is_none_or(|from| completed_on >= from)
+            && self.completed_to.is_none_or(|to| completed_on <= to)
+    }
+}
+
+/// Writes the lists' tasks in stack order, one row each, with a header row.
+/// Returns the CSV and how many tasks it holds.
+pub fn export(lists: &[ListRecord], options: &ExportOptions) -> Result<(String, usize), String> {
+    if options.columns.is_empty() {
+        return Err("Choose at least one column".to_string());
+    }
+    let now = Utc::now();
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(options.columns.iter().map(|column| column.name()))
+        .map_err(|e| e.to_string())?;
+    let mut count = 0;
+    for list in lists {
+        for task in list.tasks.iter().filter(|task| options.includes(task)) {
+            writer
+                .write_record(
+                    options
+                        .columns
+                        .iter()
+                        .map(|column| column.value(list, task, now)),
+                )
+                .map_err(|e| e.to_string())?;
+            count += 1;
+        }
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    let csv = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+    Ok((csv, count))
+}
+
+/// Reads `column=Header` pairs separated by commas or new lines. Columns
+/// left out are read from a header with their own name.
+pub fn parse_mapping(mapping: &str) -> Result<HashMap<Column, String>, String> {
+    let mut columns = HashMap::new();
+    for pair in mapping
+        .split([',', '\n'])
+        .filter(|pair| !pair.trim().is_empty())
+    {
+        let (column, header) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("Expected column=Header, got {}", pair.trim()))?;
+        columns.insert(Column::from_name(column)?, header.trim().to_string());
+    }
+    Ok(columns)
+}
+
+fn parse_date_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, DATE_FORMAT)
+                .ok()
+                .map(start_of_day)
+        })
+}
+
+fn is_completed(state: &str) -> bool {
+    ["completed", "complete", "done", "x", "true", "yes"]
+        .iter()
+        .any(|word| state.eq_ignore_ascii_case(word))
+}
+
+/// Reads rows as tasks, taking each column from the header `mapping` names
+/// for it. Rows are ordered by their position where one is mapped, file order
+/// otherwise, and go to the list their list column names.
+pub fn parse(csv: &str, mapping: &HashMap<Column, String>) -> Result<Vec<Entry>, String> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(::csv::Trim::All)
+        .from_reader(csv.as_bytes());
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let index: HashMap<Column, usize> = Column::ALL
+        .into_iter()
+        .filter_map(|column| {
+            let header = mapping
+                .get(&column)
+                .map(String::as_str)
+                .unwrap_or(column.name());
+            headers
+                .iter()
+                .position(|h| h.eq_ignore_ascii_case(header))
+                .map(|i| (column, i))
+        })
+        .collect();
+    if !index.contains_key(&Column::Title) {
+        return Err(format!(
+            "No {} column; map one with title=Header",
+            mapping
+                .get(&Column::Title)
+                .map(String::as_str)
+                .unwrap_or("title")
+        ));
+    }
+
+    let now = Utc::now();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let field = |column: Column| {
+            index
+                .get(&column)
+                .and_then(|&i| record.get(i))
+                .filter(|value| !value.is_empty())
+        };
+        let Some(title) = field(Column::Title) else {
+            continue;
+        };
+        let completed_at = field(Column::CompletedAt).and_then(parse_date_time);
+        let completed = field(Column::State).map_or(completed_at.is_some(), is_completed);
+        rows.push((
+            field(Column::Position).and_then(|p| p.parse::<i64>().ok()),
+            Entry {
+                list: field(Column::List).map(str::to_string),
+                task: TaskRecord {
+                    id: field(Column::Id)
+                        .and_then(|id| Ulid::from_string(id).ok())
+                        .unwrap_or_else(Ulid::new),
+                    title: title.to_string(),
+                    description: field(Column::Description).map(str::to_string),
+                    created_at: field(Column::CreatedAt)
+                        .and_then(parse_date_time)
+                        .unwrap_or(now),
+                    state: if completed {
+                        TaskState::Completed
+                    } else {
+                        TaskState::Active
+                    },
+                    completed_at: completed.then(|| completed_at.unwrap_or(now)),
+                    position: 0,
+                },
+            },
+        ));
+    }
+
+    // A stable sort, so rows without a position keep their file order at the end.
+    rows.sort_by_key(|(position, _)| position.unwrap_or(i64::MAX));
+    Ok(rows
+        .into_iter()
+        .enumerate()
+        .map(|(position, (_, mut entry))| {
+            entry.task.position = position as i64;
+            entry
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        parse_date_time(value).unwrap()
+    }
+
+    fn task(title: &str, created_at: &str, completed_at: Option<&str>) -> TaskRecord {
+        TaskRecord {
+            id: Ulid::new(),
+            title: title.to_string(),
+            description: None,
+            created_at: at(created_at),
+            state: if completed_at.is_some() {
+                TaskState::Completed
+            } else {
+                TaskState::Active
+            },
+            completed_at: completed_at.map(at),
+            position: 0,
+        }
+    }
+
+    fn list(name: &str, tasks: Vec<TaskRecord>) -> ListRecord {
+        ListRecord {
+            id: Ulid::new(),
+            name: name.to_string(),
+            created_at: Utc::now(),
+            tasks,
+        }
+    }
+
+    #[test]
+    fn writes_chosen_columns_for_tasks_completed_in_range() {
+        let lists = [list(
+            "Home",
+            vec![
+                task("Water the plants", "2024-03-01T09:00:00Z", None),
+                task(
+                    "Pay the rent",
+                    "2024-03-01T09:00:00Z",
+                    Some("2024-03-02T12:30:00Z"),
+                ),
+                task(
+                    "File taxes",
+                    "2024-03-01T09:00:00Z",
+                    Some("2024-03-10T09:00:00Z"),
+                ),
+            ],
+        )];
+        let options = ExportOptions {
+            columns: Column::parse_list("title, state,time_in_stack,").unwrap(),
+            completed_from: NaiveDate::from_ymd_opt(2024, 3, 1),
+            completed_to: NaiveDate::from_ymd_opt(2024, 3, 5),
+        };
+        assert_eq!(
+            export(&lists, &options).unwrap(),
+            (
+                "title,state,time_in_stack\nPay the rent,completed,27.50\n".to_string(),
+                1
+            )
+        );
+
+        // Either end alone
+        let from = ExportOptions {
+            completed_to: None,
+            ..options.clone()
+        };
+        assert_eq!(export(&lists, &from).unwrap().1, 2);
+        let to = ExportOptions {
+            completed_from: None,
+            ..options.clone()
+        };
+        assert_eq!(export(&lists, &to).unwrap().1, 1);
+        assert_eq!(export(&lists, &ExportOptions::default()).unwrap().1, 3);
+
+        let none = ExportOptions {
+            columns: vec![],
+            ..options
+        };
+        assert!(export(&lists, &none).is_err());
+        assert!(Column::parse_list("title,due").is_err());
+    }
+
+    #[test]
+    fn round_trips_its_own_export() {
+        let mut rent = task("Pay the rent, \"on time\"", "2024-03-01T09:00:00Z", None);
+        rent.description = Some("Line one\nLine two".to_string());
+        let done = task("Done", "2024-03-01T09:00:00Z", Some("2024-03-02T12:00:00Z"));
+        let lists = [
+            list("Home", vec![rent.clone(), done.clone()]),
+            list("Work", vec![task("Report", "2024-03-03T09:00:00Z", None)]),
+        ];
+        let (csv, count) = export(&lists, &ExportOptions::default()).unwrap();
+        assert_eq!(count, 3);
+
+        let entries = parse(&csv, &HashMap::new()).unwrap();
+        let read: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.list.as_deref(), entry.task.title.as_str()))
+            .collect();
+        assert_eq!(
+            read,
+            [
+                (Some("Home"), rent.title.as_str()),
+                (Some("Home"), "Done"),
+                (Some("Work"), "Report"),
+            ]
+        );
+        assert_eq!(entries[0].task.id, rent.id);
+        assert_eq!(entries[0].task.description, rent.description);
+        assert_eq!(entries[0].task.created_at, rent.created_at);
+        assert_eq!(entries[1].task.state, TaskState::Completed);
+        assert_eq!(entries[1].task.completed_at, done.completed_at);
+    }
+
+    #[test]
+    fn reads_other_headers_through_a_mapping() {
+        let mapping = parse_mapping("title=Task Name, state = Status\nposition=Order\n").unwrap();
+        assert_eq!(mapping[&Column::Title], "Task Name");
+        assert_eq!(mapping[&Column::State], "Status");
+        assert!(parse_mapping("title").is_err());
+        assert!(parse_mapping("due=Due").is_err());
+
+        let csv = "Task Name,Status,Order,completed_at\n\
+                   Second,open,2,\n\
+                   Unordered,Done,,2024-03-02\n\
+                   First,,1,\n\
+                   ,done,3,\n";
+        let entries = parse(csv, &mapping).unwrap();
+        let read: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.task.title.as_str(),
+                    entry.task.state.clone(),
+                    entry.task.position,
+                )
+            })
+            .collect();
+        assert_eq!(
+            read,
+            [
+                ("First", TaskState::Active, 0),
+                ("Second", TaskState::Active, 1),
+                ("Unordered", TaskState::Completed, 2),
+            ]
+        );
+        assert_eq!(entries[2].task.completed_at, Some(at("2024-03-02")));
+
+        let error = parse("Name\nFirst\n", &HashMap::new()).unwrap_err();
+        assert!(error.contains("title=Header"));
+    }
+}
@@ -0,0 +1,377 @@
+use super::{start_of_day, Checklist, ListRecord, TaskRecord};
+use crate::tasks::TaskState;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use ulid::Ulid;
+
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+/// The sort order property Apple Reminders and Nextcloud Tasks read and write.
+const SORT_ORDER: &str = "X-APPLE-SORT-ORDER";
+/// Lines longer than this many bytes are folded.
+const LINE_LIMIT: usize = 75;
+
+/// Escapes text values. The standard only names `\n`, but a bare carriage
+/// return would end the content line, so it's written as `\r`.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Appends a content line, folded so no line is longer than the limit.
+fn push_line(ics: &mut String, line: &str) {
+    let mut limit = LINE_LIMIT;
+    let mut rest = line;
+    while rest.len() > limit {
+        let mut split = limit;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        ics.push_str(&rest[..split]);
+        ics.push_str("\r\n ");
+        rest = &rest[split..];
+        // Continuation lines start with a space.
+        limit = LINE_LIMIT - 1;
+    }
+    ics.push_str(rest);
+    ics.push_str("\r\n");
+}
+
+fn date_time(at: &DateTime<Utc>) -> String {
+    at.format(DATE_TIME_FORMAT).to_string()
+}
+
+/// Writes the list as a calendar with one `VTODO` per task, in stack order.
+pub fn export(list: &ListRecord) -> String {
+    let now = date_time(&Utc::now());
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//Task Stack//Task Stack//EN");
+    push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape(&list.name)));
+    for task in &list.tasks {
+        push_line(&mut ics, "BEGIN:VTODO");
+        push_line(&mut ics, &format!("UID:{}", task.id));
+        push_line(&mut ics, &format!("DTSTAMP:{now}"));
+        push_line(
+            &mut ics,
+            &format!("CREATED:{}", date_time(&task.created_at)),
+        );
+        push_line(&mut ics, &format!("SUMMARY:{}", escape(&task.title)));
+        if let Some(description) = &task.description {
+            push_line(&mut ics, &format!("DESCRIPTION:{}", escape(description)));
+        }
+        match task.state {
+            TaskState::Active => push_line(&mut ics, "STATUS:NEEDS-ACTION"),
+            TaskState::Completed => {
+                push_line(&mut ics, "STATUS:COMPLETED");
+                let completed_at = task.completed_at.unwrap_or(task.created_at);
+                push_line(&mut ics, &format!("COMPLETED:{}", date_time(&completed_at)));
+            }
+        }
+        push_line(&mut ics, &format!("{SORT_ORDER}:{}", task.position));
+        push_line(&mut ics, "END:VTODO");
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// Joins folded lines back together.
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// The id for a `UID` that isn't a ULID, the same each time the file is read
+/// so importing it again skips what was imported before. FNV-1a, as std's
+/// hashers may change between releases.
+fn uid_id(uid: &str) -> Ulid {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    Ulid::from(uid.bytes().fold(OFFSET, |hash, byte| {
+        (hash ^ u128::from(byte)).wrapping_mul(PRIME)
+    }))
+}
+
+/// Splits a content line into its upper-cased name and its value, ignoring
+/// parameters. Colons inside quoted parameter values don't end the name.
+fn property(line: &str) -> Option<(String, &str)> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    })?;
+    let name = line[..colon].split(';').next()?;
+    Some((name.to_ascii_uppercase(), &line[colon + 1..]))
+}
+
+/// Reads a DATE or DATE-TIME value. Floating and zoned times are taken as UTC.
+fn parse_date_time(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim_end_matches('Z');
+    if value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .map(start_of_day);
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(|at| at.and_utc())
+}
+
+#[derive(Default)]
+struct Todo {
+    uid: Option<Ulid>,
+    summary: Option<String>,
+    description: Option<String>,
+    completed: bool,
+    completed_at: Option<DateTime<Utc>>,
+    created_at: Option<DateTime<Utc>>,
+    sort_order: Option<i64>,
+}
+
+/// Reads every `VTODO` in the calendar. Tasks keep their `UID` when it's a
+/// ULID, get one made from it otherwise, and are ordered by sort order where
+/// given, file order otherwise.
+pub fn parse(ics: &str) -> Result<Checklist, String> {
+    let lines = unfold(ics);
+    if !lines
+        .iter()
+        .any(|line| line.eq_ignore_ascii_case("BEGIN:VCALENDAR"))
+    {
+        return Err("Not an iCalendar file".to_string());
+    }
+
+    let mut name = None;
+    let mut todos: Vec<Todo> = Vec::new();
+    let mut todo: Option<Todo> = None;
+    // Components nested in a to-do, like alarms, whose properties aren't the to-do's.
+    let mut nested = 0;
+
+    for line in &lines {
+        let Some((property, value)) = property(line) else {
+            continue;
+        };
+        let value = value.trim_end();
+        match (property.as_str(), todo.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => {
+                todo = Some(Todo::default());
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VTODO") => {
+                todos.extend(todo.take());
+            }
+            ("X-WR-CALNAME", None) => name = Some(unescape(value)),
+            (_, Some(_)) if nested > 0 => {}
+            ("UID", Some(todo)) if !value.is_empty() => {
+                todo.uid = Some(Ulid::from_string(value).unwrap_or_else(|_| uid_id(value)));
+            }
+            ("SUMMARY", Some(todo)) => todo.summary = Some(unescape(value)),
+            ("DESCRIPTION", Some(todo)) => todo.description = Some(unescape(value)),
+            ("STATUS", Some(todo)) => todo.completed |= value.eq_ignore_ascii_case("COMPLETED"),
+            ("COMPLETED", Some(todo)) => {
+                todo.completed = true;
+                todo.completed_at = parse_date_time(value);
+            }
+            ("CREATED", Some(todo)) => todo.created_at = parse_date_time(value),
+            (SORT_ORDER, Some(todo)) => todo.sort_order = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    // A stable sort, so to-dos without a sort order keep their file order at the end.
+    todos.sort_by_key(|todo| todo.sort_order.unwrap_or(i64::MAX));
+    let now = Utc::now();
+    let mut tasks = Vec::new();
+    for todo in todos {
+        let Some(title) = todo
+            .summary
+            .map(|summary| summary.trim().to_string())
+            .filter(|title| !title.is_empty())
+        else {
+            continue;
+        };
+        tasks.push(TaskRecord {
+            id: todo.uid.unwrap_or_else(Ulid::new),
+            title,
+            description: todo.description.filter(|d| !d.trim().is_empty()),
+            created_at: todo.created_at.unwrap_or(now),
+            state: if todo.completed {
+                TaskState::Completed
+            } else {
+                TaskState::Active
+            },
+            completed_at: todo.completed.then(|| todo.completed_at.unwrap_or(now)),
+            position: tasks.len() as i64,
+        });
+    }
+
+    Ok(Checklist {
+        name: name.filter(|name| !name.is_empty()),
+        tasks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REMINDERS: &str = "BEGIN:VCALENDAR
+VERSION:2.0
+X-WR-CALNAME:Errands\\, weekly
+BEGIN:VTODO
+UID:reminder-1@example.com
+SUMMARY:Post the letter
+X-APPLE-SORT-ORDER:2
+END:VTODO
+BEGIN:VTODO
+UID:reminder-2@example.com
+SUMMARY:Buy milk
+DESCRIPTION:Oat
+BEGIN:VALARM
+ACTION:DISPLAY
+DESCRIPTION:Reminder
+SUMMARY:Alarm
+END:VALARM
+STATUS:COMPLETED
+COMPLETED:20240302T090000Z
+X-APPLE-SORT-ORDER:1
+END:VTODO
+BEGIN:VTODO
+UID:reminder-3@example.com
+SUMMARY:Call the
+  bank
+END:VTODO
+END:VCALENDAR
+";
+
+    fn task(title: &str) -> TaskRecord {
+        TaskRecord {
+            id: Ulid::new(),
+            title: title.to_string(),
+            description: None,
+            created_at: Utc::now(),
+            state: TaskState::Active,
+            completed_at: None,
+            position: 0,
+        }
+    }
+
+    fn list(tasks: Vec<TaskRecord>) -> ListRecord {
+        ListRecord {
+            id: Ulid::new(),
+            name: "Errands".to_string(),
+            created_at: Utc::now(),
+            tasks,
+        }
+    }
+
+    #[test]
+    fn reads_to_dos_in_sort_order_without_their_alarms() {
+        let checklist = parse(REMINDERS).unwrap();
+        assert_eq!(checklist.name.as_deref(), Some("Errands, weekly"));
+        let tasks: Vec<_> = checklist
+            .tasks
+            .iter()
+            .map(|task| {
+                (
+                    task.title.as_str(),
+                    task.description.as_deref(),
+                    task.state.clone(),
+                    task.position,
+                )
+            })
+            .collect();
+        assert_eq!(
+            tasks,
+            [
+                ("Buy milk", Some("Oat"), TaskState::Completed, 0),
+                ("Post the letter", None, TaskState::Active, 1),
+                // No sort order, so after the rest
+                ("Call the bank", None, TaskState::Active, 2),
+            ]
+        );
+        assert_eq!(
+            checklist.tasks[0].completed_at,
+            parse_date_time("20240302T090000Z")
+        );
+        assert!(parse("BEGIN:VTODO\nEND:VTODO\n").is_err());
+    }
+
+    #[test]
+    fn makes_the_same_id_from_the_same_uid() {
+        let first = parse(REMINDERS).unwrap().tasks;
+        let again = parse(REMINDERS).unwrap().tasks;
+        let ids = |tasks: &[TaskRecord]| tasks.iter().map(|task| task.id).collect::<Vec<_>>();
+        assert_eq!(ids(&first), ids(&again));
+        assert_ne!(first[0].id, first[1].id);
+
+        // ULIDs are kept as they are
+        let id = Ulid::new();
+        let ics = format!("BEGIN:VCALENDAR\nBEGIN:VTODO\nUID:{id}\nSUMMARY:Kept\nEND:VTODO\n");
+        assert_eq!(parse(&ics).unwrap().tasks[0].id, id);
+    }
+
+    #[test]
+    fn folds_long_lines_between_characters() {
+        let title = format!("Buy {}", "é".repeat(80));
+        let ics = export(&list(vec![task(&title)]));
+        let lines: Vec<&str> = ics.trim_end_matches("\r\n").split("\r\n").collect();
+        assert!(lines.iter().all(|line| line.len() <= LINE_LIMIT));
+        let summary = lines
+            .iter()
+            .position(|line| line.starts_with("SUMMARY:"))
+            .unwrap();
+        assert!(lines[summary + 1].starts_with(' '));
+        assert_eq!(parse(&ics).unwrap().tasks[0].title, title);
+    }
+
+    #[test]
+    fn round_trips_escaped_text() {
+        let mut rent = task("Pay rent, bills; and \\fees");
+        rent.description = Some("First line\r\nSecond line\nThird".to_string());
+        rent.state = TaskState::Completed;
+        rent.completed_at = parse_date_time("20240302T090000Z");
+        let ics = export(&list(vec![rent.clone()]));
+        assert!(ics.contains("SUMMARY:Pay rent\\, bills\\; and \\\\fees\r\n"));
+        assert!(ics.contains("DESCRIPTION:First line\\r\\nSecond line\\nThird\r\n"));
+        // Every carriage return left ends a line
+        assert_eq!(ics.matches('\r').count(), ics.matches("\r\n").count());
+
+        let read = &parse(&ics).unwrap().tasks[0];
+        assert_eq!(read.id, rent.id);
+        assert_eq!(read.title, rent.title);
+        assert_eq!(read.description, rent.description);
+        assert_eq!(read.completed_at, rent.completed_at);
+        assert_eq!(unescape(&escape("a\\nb")), "a\\nb");
+    }
+}
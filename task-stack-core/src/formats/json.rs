@@ -0,0 +1,21 @@
+use super::{Document, FORMAT_VERSION};
+
+pub fn to_string(document: &Document) -> Result<String, String> {
+    serde_json::to_string_pretty(document).map_err(|e| e.to_string())
+}
+
+/// Parses a document, checking its version first so newer files get a clear error.
+pub fn from_str(json: &str) -> Result<Document, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("Not a JSON file: {e}"))?;
+    let version = value
+        .get("version")
+        .and_then(serde_json::Value::as_u64)
+        .ok_or_else(|| "Not a Task Stack export: version is missing".to_string())?;
+    if version > u64::from(FORMAT_VERSION) {
+        return Err(format!(
+            "This file is from a newer version of Task Stack (format {version})"
+        ));
+    }
+    serde_json::from_value(value).map_err(|e| format!("Not a Task Stack export: {e}"))
+}
@@ -0,0 +1,180 @@
+use super::{start_of_day, Checklist, ListRecord, TaskRecord, DATE_FORMAT};
+use crate::tasks::TaskState;
+use chrono::{NaiveDate, Utc};
+use ulid::Ulid;
+
+const COMPLETED_PREFIX: &str = " (completed ";
+
+/// Writes the list as a checklist in stack order, descriptions indented under
+/// their task and completion dates after completed titles.
+pub fn export(list: &ListRecord) -> String {
+    let mut markdown = format!("# {}\n\n", list.name);
+    for task in &list.tasks {
+        let checked = if task.state == TaskState::Completed {
+            'x'
+        } else {
+            ' '
+        };
+        markdown.push_str(&format!("- [{checked}] {}", task.title));
+        if let Some(completed_at) = task.completed_at {
+            markdown.push_str(&format!(
+                "{COMPLETED_PREFIX}{})",
+                completed_at.format(DATE_FORMAT)
+            ));
+        }
+        markdown.push('\n');
+        for line in task.description.iter().flat_map(|d| d.lines()) {
+            markdown.push_str(&format!("  {line}\n"));
+        }
+    }
+    markdown
+}
+
+/// A top-level list item: its text and whether it was ticked.
+fn item(line: &str) -> Option<(&str, bool)> {
+    let rest = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))?;
+    // A box with nothing after it is still a box, leaving an empty title
+    let checkbox = |mark: &str| {
+        rest.strip_prefix(mark)
+            .filter(|title| title.is_empty() || title.starts_with(' '))
+    };
+    if let Some(title) = checkbox("[ ]") {
+        Some((title, false))
+    } else if let Some(title) = checkbox("[x]").or_else(|| checkbox("[X]")) {
+        Some((title, true))
+    } else {
+        Some((rest, false))
+    }
+}
+
+/// Splits `(completed 2024-01-31)` off the end of a title.
+fn completion_date(title: &str) -> (&str, Option<NaiveDate>) {
+    if let Some(start) = title.rfind(COMPLETED_PREFIX) {
+        if let Some(date) = title[start + COMPLETED_PREFIX.len()..].strip_suffix(')') {
+            if let Ok(date) = NaiveDate::parse_from_str(date, DATE_FORMAT) {
+                return (&title[..start], Some(date));
+            }
+        }
+    }
+    (title, None)
+}
+
+/// Reads list items as tasks, ticked ones as completed, and indented lines
+/// under an item as its description. Everything else is ignored.
+pub fn parse(markdown: &str) -> Checklist {
+    let mut checklist = Checklist::default();
+    let mut descriptions: Vec<Vec<&str>> = Vec::new();
+
+    for line in markdown.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some(description) = descriptions.last_mut() {
+                let text = line.trim();
+                if !text.is_empty() || !description.is_empty() {
+                    description.push(text);
+                }
+            }
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("# ") {
+            checklist
+                .name
+                .get_or_insert_with(|| name.trim().to_string());
+            continue;
+        }
+        let Some((title, checked)) = item(line) else {
+            continue;
+        };
+        let (title, date) = completion_date(title.trim());
+        if title.is_empty() {
+            continue;
+        }
+        let now = Utc::now();
+        checklist.tasks.push(TaskRecord {
+            id: Ulid::new(),
+            title: title.to_string(),
+            description: None,
+            created_at: now,
+            state: if checked {
+                TaskState::Completed
+            } else {
+                TaskState::Active
+            },
+            completed_at: checked.then(|| date.map(start_of_day).unwrap_or(now)),
+            position: checklist.tasks.len() as i64,
+        });
+        descriptions.push(Vec::new());
+    }
+
+    for (task, lines) in checklist.tasks.iter_mut().zip(descriptions) {
+        let description = lines.join("\n").trim_end().to_string();
+        if !description.is_empty() {
+            task.description = Some(description);
+        }
+    }
+    checklist
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = include_str!("../../tests/fixtures/markdown/export.md");
+
+    #[test]
+    fn round_trips_its_own_export() {
+        let checklist = parse(EXPORT);
+        assert_eq!(checklist.name.as_deref(), Some("Errands"));
+        let tasks: Vec<_> = checklist
+            .tasks
+            .iter()
+            .map(|task| {
+                (
+                    task.title.as_str(),
+                    task.description.as_deref(),
+                    task.state.clone(),
+                    task.position,
+                )
+            })
+            .collect();
+        assert_eq!(
+            tasks,
+            [
+                ("Buy milk", Some("Oat, not soy"), TaskState::Completed, 0),
+                (
+                    "Call the bank",
+                    Some("Ask about the fee\nThen the rate"),
+                    TaskState::Active,
+                    1
+                ),
+                ("Post the letter", None, TaskState::Active, 2),
+            ]
+        );
+        let date = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+        assert_eq!(checklist.tasks[0].completed_at, Some(start_of_day(date)));
+        assert!(checklist.tasks[1].completed_at.is_none());
+
+        let list = ListRecord {
+            id: Ulid::new(),
+            name: "Errands".to_string(),
+            created_at: Utc::now(),
+            tasks: checklist.tasks,
+        };
+        assert_eq!(export(&list), EXPORT);
+    }
+
+    #[test]
+    fn reads_other_bullets_and_skips_the_rest() {
+        let checklist =
+            parse("Notes first\n* [X] Done\n+ Plain item\n- [ ] \n- [ ]\n* [x]\n1. Numbered\n");
+        assert_eq!(checklist.name, None);
+        let tasks: Vec<_> = checklist
+            .tasks
+            .iter()
+            .map(|task| (task.title.as_str(), task.completed_at.is_some()))
+            .collect();
+        assert_eq!(tasks, [("Done", true), ("Plain item", false)]);
+    }
+}
@@ -0,0 +1,515 @@
+pub mod csv;
+pub mod ical;
+pub mod json;
+pub mod markdown;
+pub mod todotxt;
+
+use crate::repository::{TaskRepository, Write};
+use crate::tasks::{Task, TaskList, TaskState};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use ulid::Ulid;
+
+/// Version written to exported documents; newer documents are refused on import.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// How plain-text formats write dates.
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Midnight UTC, for formats that only keep the date.
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
+/// Every list and task in a database, in the shape files are written from and read into.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Document {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub lists: Vec<ListRecord>,
+}
+
+impl Document {
+    pub fn task_count(&self) -> usize {
+        self.lists.iter().map(|list| list.tasks.len()).sum()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListRecord {
+    pub id: Ulid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// In stack order.
+    pub tasks: Vec<TaskRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub id: Ulid,
+    pub title: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub state: TaskState,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub position: i64,
+}
+
+impl TaskRecord {
+    fn into_task(self, list_id: Ulid) -> Task {
+        Task {
+            id: self.id,
+            list_id,
+            title: self.title,
+            description: self.description,
+            created_at: self.created_at,
+            state: self.state,
+            completed_at: self.completed_at,
+        }
+    }
+}
+
+/// One list read from a file that holds a single list, in file order.
+#[derive(Debug, Clone, Default)]
+pub struct Checklist {
+    /// The name the file gives the list, if any.
+    pub name: Option<String>,
+    pub tasks: Vec<TaskRecord>,
+}
+
+impl Checklist {
+    /// The tasks as entries that don't name a list, for `append_entries`.
+    pub fn into_entries(self) -> Vec<Entry> {
+        self.tasks
+            .into_iter()
+            .map(|task| Entry { list: None, task })
+            .collect()
+    }
+}
+
+/// A task read from a file that can hold several lists.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// The name of the list the file puts it in, if any.
+    pub list: Option<String>,
+    pub task: TaskRecord,
+}
+
+/// Where imported tasks go when the file doesn't say.
+#[derive(Debug, Clone)]
+pub enum Destination {
+    List(Ulid),
+    /// A new list with this name, created only if something goes into it.
+    New(String),
+}
+
+/// What importing a set of entries would do.
+#[derive(Debug, Clone, Default)]
+pub struct Preview {
+    /// In the order tasks would first go into them.
+    pub lists: Vec<ListPreview>,
+    /// Tasks skipped because they're already in the database.
+    pub duplicates: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListPreview {
+    pub name: String,
+    /// Whether the list would be created.
+    pub new: bool,
+    pub active: usize,
+    pub completed: usize,
+}
+
+impl Preview {
+    pub fn task_count(&self) -> usize {
+        self.lists
+            .iter()
+            .map(|list| list.active + list.completed)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Only into a database without tasks, replacing its empty lists.
+    #[default]
+    Restore,
+    /// Alongside what's there, skipping lists and tasks that already exist.
+    Merge,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    pub lists_added: usize,
+    pub lists_skipped: usize,
+    pub tasks_added: usize,
+    pub tasks_skipped: usize,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Added {} lists and {} tasks",
+            self.lists_added, self.tasks_added
+        )?;
+        if self.lists_skipped > 0 || self.tasks_skipped > 0 {
+            write!(
+                f,
+                ", skipped {} lists and {} tasks that already existed",
+                self.lists_skipped, self.tasks_skipped
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a list's tasks in stack order.
+pub async fn list_record(repo: &dyn TaskRepository, list: TaskList) -> Result<ListRecord, String> {
+    let mut tasks: Vec<TaskRecord> = repo
+        .get_all_tasks(&list.id)
+        .await?
+        .into_iter()
+        .map(|(task, position)| TaskRecord {
+            id: task.id,
+            title: task.title,
+            description: task.description,
+            created_at: task.created_at,
+            state: task.state,
+            completed_at: task.completed_at,
+            position,
+        })
+        .collect();
+    tasks.sort_by_key(|task| task.position);
+    Ok(ListRecord {
+        id: list.id,
+        name: list.name,
+        created_at: list.created_at,
+        tasks,
+    })
+}
+
+/// Reads every list and task.
+pub async fn collect(repo: &dyn TaskRepository) -> Result<Document, String> {
+    let mut lists = Vec::new();
+    for list in repo.get_lists().await? {
+        lists.push(list_record(repo, list).await?);
+    }
+    Ok(Document {
+        version: FORMAT_VERSION,
+        exported_at: Utc::now(),
+        lists,
+    })
+}
+
+/// The id of every task in the database, to skip ones already imported.
+async fn known_task_ids(
+    repo: &dyn TaskRepository,
+    lists: &[TaskList],
+) -> Result<HashSet<Ulid>, String> {
+    let mut known_tasks = HashSet::new();
+    for list in lists {
+        for (task, _) in repo.get_all_tasks(&list.id).await? {
+            known_tasks.insert(task.id);
+        }
+    }
+    Ok(known_tasks)
+}
+
+/// Queues a new list, known to later entries by name before it is written.
+fn new_list(name: String, lists: &mut Vec<TaskList>, writes: &mut Vec<Write>) -> Ulid {
+    let list = TaskList {
+        id: Ulid::new(),
+        name,
+        created_at: Utc::now(),
+    };
+    let id = list.id;
+    writes.push(Write::InsertList(list.clone()));
+    lists.push(list);
+    id
+}
+
+/// Adds each entry to the list `matches` pairs with its list name, creating
+/// a list with that name if none does, and entries without one to
+/// `destination`. Entries already in the database are skipped, and lists are
+/// only created for ones that aren't. Returns how many tasks were added.
+pub async fn append_entries(
+    repo: &dyn TaskRepository,
+    entries: Vec<Entry>,
+    mut destination: Destination,
+    matches: impl Fn(&TaskList, &str) -> bool,
+) -> Result<usize, String> {
+    let mut lists = repo.get_lists().await?;
+    let mut known_tasks = known_task_ids(repo, &lists).await?;
+    let mut next_positions: HashMap<Ulid, i64> = HashMap::new();
+    let mut writes = Vec::new();
+    let mut added = 0;
+
+    for entry in entries {
+        if !known_tasks.insert(entry.task.id) {
+            continue;
+        }
+        let list_id = match (entry.list, &destination) {
+            (None, Destination::List(id)) => *id,
+            (Some(name), _) => match lists.iter().find(|list| matches(list, &name)) {
+                Some(list) => list.id,
+                None => new_list(name, &mut lists, &mut writes),
+            },
+            (None, Destination::New(name)) => {
+                let id = new_list(name.clone(), &mut lists, &mut writes);
+                destination = Destination::List(id);
+                id
+            }
+        };
+
+        let next_position = match next_positions.get_mut(&list_id) {
+            Some(next_position) => next_position,
+            None => next_positions
+                .entry(list_id)
+                .or_insert(repo.get_highest_position(&list_id).await? + 1),
+        };
+        writes.push(Write::InsertTask(
+            entry.task.into_task(list_id),
+            *next_position,
+        ));
+        *next_position += 1;
+        added += 1;
+    }
+    repo.apply(writes).await?;
+    Ok(added)
+}
+
+/// Works out where entries would go, matching list names exactly as
+/// importing them does, without writing anything.
+pub async fn preview(
+    repo: &dyn TaskRepository,
+    entries: &[Entry],
+    destination: &Destination,
+) -> Result<Preview, String> {
+    let lists = repo.get_lists().await?;
+    let mut known_tasks = known_task_ids(repo, &lists).await?;
+
+    let mut preview = Preview::default();
+    for entry in entries {
+        if !known_tasks.insert(entry.task.id) {
+            preview.duplicates += 1;
+            continue;
+        }
+        let (name, new) = match (&entry.list, destination) {
+            (Some(name), _) => (name.clone(), !lists.iter().any(|list| list.name == *name)),
+            (None, Destination::New(name)) => (name.clone(), true),
+            (None, Destination::List(id)) => (
+                lists
+                    .iter()
+                    .find(|list| list.id == *id)
+                    .map(|list| list.name.clone())
+                    .ok_or_else(|| "List not found".to_string())?,
+                false,
+            ),
+        };
+        let index = match preview
+            .lists
+            .iter()
+            .position(|list| list.name == name && list.new == new)
+        {
+            Some(index) => index,
+            None => {
+                preview.lists.push(ListPreview {
+                    name,
+                    new,
+                    active: 0,
+                    completed: 0,
+                });
+                preview.lists.len() - 1
+            }
+        };
+        match entry.task.state {
+            TaskState::Active => preview.lists[index].active += 1,
+            TaskState::Completed => preview.lists[index].completed += 1,
+        }
+    }
+    Ok(preview)
+}
+
+/// Writes `document` into the repository. Merged tasks go after the tasks
+/// already in their list, keeping their order.
+pub async fn import(
+    repo: &dyn TaskRepository,
+    document: Document,
+    mode: ImportMode,
+) -> Result<ImportReport, String> {
+    if document.version > FORMAT_VERSION {
+        return Err(format!(
+            "This file is from a newer version of Task Stack (format {})",
+            document.version
+        ));
+    }
+
+    let existing_lists = repo.get_lists().await?;
+    // Everything is written in one go, so a failure partway leaves the database as it was
+    let mut writes = Vec::new();
+    if mode == ImportMode::Restore {
+        let counts = repo.count_tasks().await?;
+        if counts.values().any(|count| count.total > 0) {
+            return Err("Restoring needs an empty database; merge instead".to_string());
+        }
+        writes.extend(existing_lists.iter().map(|list| Write::DeleteList(list.id)));
+    }
+
+    let mut known_lists: HashSet<Ulid> = HashSet::new();
+    let mut known_tasks: HashSet<Ulid> = HashSet::new();
+    if mode == ImportMode::Merge {
+        for list in &existing_lists {
+            known_lists.insert(list.id);
+            for (task, _) in repo.get_all_tasks(&list.id).await? {
+                known_tasks.insert(task.id);
+            }
+        }
+    }
+    let mut report = ImportReport::default();
+    // Nothing is written until the end, so positions handed out so far are kept here
+    let mut next_positions: HashMap<Ulid, i64> = HashMap::new();
+
+    for list in document.lists {
+        if known_lists.insert(list.id) {
+            writes.push(Write::InsertList(TaskList {
+                id: list.id,
+                name: list.name,
+                created_at: list.created_at,
+            }));
+            next_positions.insert(list.id, 1);
+            report.lists_added += 1;
+        } else {
+            report.lists_skipped += 1;
+        }
+        let next_position = match next_positions.get_mut(&list.id) {
+            Some(next_position) => next_position,
+            None => next_positions
+                .entry(list.id)
+                .or_insert(repo.get_highest_position(&list.id).await? + 1),
+        };
+
+        for task in list.tasks {
+            if !known_tasks.insert(task.id) {
+                report.tasks_skipped += 1;
+                continue;
+            }
+            let position = match mode {
+                ImportMode::Restore => task.position,
+                ImportMode::Merge => {
+                    let position = *next_position;
+                    *next_position += 1;
+                    position
+                }
+            };
+            writes.push(Write::InsertTask(task.into_task(list.id), position));
+            report.tasks_added += 1;
+        }
+    }
+    repo.apply(writes).await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+
+    #[tokio::test]
+    async fn restores_and_merges_an_export() {
+        let dir = TempDir::new();
+        let [(_, source), (_, target)] = testing::stacks(&dir).await;
+        source.push("First".to_string(), None).await.unwrap();
+        source.push("Second".to_string(), None).await.unwrap();
+        let document = source.export().await.unwrap();
+
+        let report = target
+            .import(document.clone(), ImportMode::Restore)
+            .await
+            .unwrap();
+        assert_eq!((report.lists_added, report.tasks_added), (1, 2));
+        assert_eq!(target.get_lists().await.unwrap().len(), 1);
+        let titles: Vec<String> = target
+            .get_tasks()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|task| task.title)
+            .collect();
+        assert_eq!(titles, ["First", "Second"]);
+
+        // Restoring again is refused, merging skips what's there
+        assert!(target
+            .import(document.clone(), ImportMode::Restore)
+            .await
+            .is_err());
+        let report = target.import(document, ImportMode::Merge).await.unwrap();
+        assert_eq!((report.lists_skipped, report.tasks_skipped), (1, 2));
+        assert_eq!(report.tasks_added, 0);
+    }
+
+    fn entry(list: Option<&str>, title: &str) -> Entry {
+        Entry {
+            list: list.map(str::to_string),
+            task: TaskRecord {
+                id: Ulid::new(),
+                title: title.to_string(),
+                description: None,
+                created_at: Utc::now(),
+                state: TaskState::Active,
+                completed_at: None,
+                position: 0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn creates_lists_only_for_new_entries() {
+        let dir = TempDir::new();
+        let [(_, stack), _] = testing::stacks(&dir).await;
+        let repo = stack.get_repository();
+        let same_name = |list: &TaskList, name: &str| list.name == name;
+        let entries = vec![
+            entry(Some("Errands"), "Stamps"),
+            entry(None, "Loose"),
+            entry(Some("Errands"), "Milk"),
+        ];
+
+        let added = append_entries(
+            repo.as_ref(),
+            entries.clone(),
+            Destination::New("Imported".to_string()),
+            same_name,
+        )
+        .await
+        .unwrap();
+        assert_eq!(added, 3);
+        let errands = stack.find_list("Errands").await.unwrap();
+        let titles: Vec<String> = stack
+            .for_list(errands)
+            .get_tasks()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|task| task.title)
+            .collect();
+        assert_eq!(titles, ["Stamps", "Milk"]);
+        assert_eq!(stack.get_lists().await.unwrap().len(), 3);
+
+        // Importing the same entries again adds neither tasks nor lists
+        let added = append_entries(
+            repo.as_ref(),
+            entries,
+            Destination::New("Again".to_string()),
+            same_name,
+        )
+        .await
+        .unwrap();
+        assert_eq!(added, 0);
+        assert_eq!(stack.get_lists().await.unwrap().len(), 3);
+    }
+}
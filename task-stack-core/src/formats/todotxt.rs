@@ -0,0 +1,373 @@
+use super::{
+    append_entries, start_of_day, Destination, Entry, ListRecord, TaskRecord, DATE_FORMAT,
+};
+use crate::repository::TaskRepository;
+use crate::tasks::TaskState;
+use chrono::{DateTime, NaiveDate, Utc};
+use ulid::Ulid;
+
+const ID_KEY: &str = "id:";
+const DESCRIPTION_KEY: &str = "desc:";
+
+/// The `+project` a list is written as: its name with whitespace turned into dashes.
+pub fn project(list_name: &str) -> String {
+    list_name.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+/// Escapes a description into a single `desc:` value, percent-encoding every
+/// kind of whitespace since any of it would split the value.
+fn escape(description: &str) -> String {
+    let mut escaped = String::with_capacity(description.len());
+    for c in description.chars() {
+        if c == '%' || c.is_whitespace() {
+            for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                escaped.push_str(&format!("%{byte:02X}"));
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// An `@context` or a `key:value` pair other than a URL, which Task Stack
+/// keeps in the description rather than the title.
+fn is_tag(word: &str) -> bool {
+    if let Some(context) = word.strip_prefix('@') {
+        return !context.is_empty();
+    }
+    word.split_once(':').is_some_and(|(key, value)| {
+        key.starts_with(|c: char| c.is_ascii_alphabetic())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            && !value.is_empty()
+            && !value.starts_with('/')
+    })
+}
+
+/// Splits the tags `parse_line` put in the last paragraph of a description
+/// off what was written there.
+fn split_tags(description: &str) -> (Option<&str>, Vec<&str>) {
+    let (body, last) = description.rsplit_once("\n\n").unwrap_or(("", description));
+    let tags: Vec<&str> = last.split(' ').collect();
+    if tags.iter().all(|tag| is_tag(tag)) {
+        (Some(body).filter(|body| !body.is_empty()), tags)
+    } else {
+        (Some(description), vec![])
+    }
+}
+
+/// Writes the list one task per line in stack order. Completed tasks get the
+/// `x` marker and completion date, every task its creation date, the list's
+/// `+project` and `id:` and `desc:` extensions for what todo.txt has no place for.
+/// Tags read from todo.txt are written back as they were.
+pub fn export(list: &ListRecord) -> String {
+    let project = format!("+{}", project(&list.name));
+    let mut todo = String::new();
+    for task in &list.tasks {
+        let mut title = task.title.as_str();
+        if task.state == TaskState::Completed {
+            let completed_at = task.completed_at.unwrap_or(task.created_at);
+            todo.push_str(&format!("x {} ", completed_at.format(DATE_FORMAT)));
+        } else if let Some((priority, rest)) = priority(title) {
+            todo.push_str(priority);
+            todo.push(' ');
+            title = rest;
+        }
+        todo.push_str(&format!("{} {title}", task.created_at.format(DATE_FORMAT)));
+        let (description, tags) = match &task.description {
+            Some(description) => split_tags(description),
+            None => (None, vec![]),
+        };
+        for tag in tags {
+            todo.push_str(&format!(" {tag}"));
+        }
+        if !title.split_whitespace().any(|word| word == project) {
+            todo.push_str(&format!(" {project}"));
+        }
+        todo.push_str(&format!(" {ID_KEY}{}", task.id));
+        if let Some(description) = description {
+            todo.push_str(&format!(" {DESCRIPTION_KEY}{}", escape(description)));
+        }
+        todo.push('\n');
+    }
+    todo
+}
+
+fn is_priority(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    bytes.len() == 3 && bytes[0] == b'(' && bytes[1].is_ascii_uppercase() && bytes[2] == b')'
+}
+
+/// Splits a leading `(A)` priority off a title.
+fn priority(title: &str) -> Option<(&str, &str)> {
+    title
+        .split_once(' ')
+        .filter(|(first, _)| is_priority(first))
+}
+
+fn date(word: Option<&&str>) -> Option<NaiveDate> {
+    word.and_then(|word| NaiveDate::parse_from_str(word, DATE_FORMAT).ok())
+}
+
+fn parse_line(line: &str, position: i64) -> Option<Entry> {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let completed = words.first() == Some(&"x");
+    let mut completed_on = None;
+    let mut prefix = Vec::new();
+    if completed {
+        words.remove(0);
+        if let Some(date) = date(words.first()) {
+            completed_on = Some(date);
+            words.remove(0);
+        }
+    } else if words.first().is_some_and(|word| is_priority(word)) {
+        prefix.push(words.remove(0));
+    }
+    let created_on = date(words.first());
+    if created_on.is_some() {
+        words.remove(0);
+    }
+
+    let mut id = None;
+    let mut description = None;
+    let mut project = None;
+    let mut tags = Vec::new();
+    let mut title = prefix;
+    for word in words {
+        if let Some(value) = word.strip_prefix(ID_KEY) {
+            if let Ok(value) = Ulid::from_string(value) {
+                id = Some(value);
+                continue;
+            }
+        }
+        if let Some(value) = word.strip_prefix(DESCRIPTION_KEY) {
+            description = Some(unescape(value));
+            continue;
+        }
+        if let Some(name) = word.strip_prefix('+').filter(|name| !name.is_empty()) {
+            project = Some(name.to_string());
+        }
+        if is_tag(word) {
+            tags.push(word);
+            continue;
+        }
+        title.push(word);
+    }
+    // The list's project is implied by the list, so it doesn't stay in the title.
+    if let Some(project) = &project {
+        let tag = format!("+{project}");
+        if let Some(index) = title.iter().rposition(|word| *word == tag) {
+            title.remove(index);
+        }
+    }
+    // A line of nothing but tags keeps them as its title
+    if title.is_empty() {
+        title = std::mem::take(&mut tags);
+    }
+    if title.is_empty() {
+        return None;
+    }
+    let description = description.filter(|d| !d.is_empty());
+    let description = match (description, tags.is_empty()) {
+        (description, true) => description,
+        (Some(description), false) => Some(format!("{description}\n\n{}", tags.join(" "))),
+        (None, false) => Some(tags.join(" ")),
+    };
+
+    let now = Utc::now();
+    let created_at = created_on
+        .map(start_of_day)
+        .or_else(|| id.map(|id| DateTime::<Utc>::from(id.datetime())))
+        .unwrap_or(now);
+    Some(Entry {
+        list: project,
+        task: TaskRecord {
+            id: id.unwrap_or_else(Ulid::new),
+            title: title.join(" "),
+            description,
+            created_at,
+            state: if completed {
+                TaskState::Completed
+            } else {
+                TaskState::Active
+            },
+            completed_at: completed.then(|| completed_on.map(start_of_day).unwrap_or(now)),
+            position,
+        },
+    })
+}
+
+/// Reads each non-blank line as a task, in file order, with the last
+/// `+project` on the line as its list. `@context`s and other `key:value`
+/// pairs go in a last paragraph of the description, where `export` finds them.
+pub fn parse(todo: &str) -> Vec<Entry> {
+    todo.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .filter_map(|(position, line)| parse_line(line, position as i64))
+        .collect()
+}
+
+/// Adds entries to the list their project names, creating it if no list
+/// matches, and the rest to `destination`. Returns how many tasks were added.
+pub async fn import(
+    repo: &dyn TaskRepository,
+    entries: Vec<Entry>,
+    destination: Destination,
+) -> Result<usize, String> {
+    append_entries(repo, entries, destination, |list, name| {
+        project(&list.name).eq_ignore_ascii_case(name)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPLETION: &str = include_str!("../../tests/fixtures/todotxt/completion.txt");
+    const TAGS: &str = include_str!("../../tests/fixtures/todotxt/tags.txt");
+    const EXPORT: &str = include_str!("../../tests/fixtures/todotxt/export.txt");
+
+    fn day(date: &str) -> Option<DateTime<Utc>> {
+        NaiveDate::parse_from_str(date, DATE_FORMAT)
+            .ok()
+            .map(start_of_day)
+    }
+
+    fn list(name: &str, entries: Vec<Entry>) -> ListRecord {
+        ListRecord {
+            id: Ulid::new(),
+            name: name.to_string(),
+            created_at: Utc::now(),
+            tasks: entries.into_iter().map(|entry| entry.task).collect(),
+        }
+    }
+
+    #[test]
+    fn reads_completion_markers_dates_and_projects() {
+        let entries = parse(COMPLETION);
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.task.title.as_str(),
+                    entry.list.as_deref(),
+                    entry.task.state.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Pay the rent", Some("Home"), TaskState::Completed),
+                ("File taxes", None, TaskState::Completed),
+                ("Water the plants", None, TaskState::Completed),
+                ("(A) Call the bank", Some("Home"), TaskState::Active),
+                ("Book flights", None, TaskState::Active),
+                // Only the last project is the list; the others stay in the title
+                (
+                    "Pick up groceries +Home",
+                    Some("Errands"),
+                    TaskState::Active
+                ),
+            ]
+        );
+
+        let rent = &entries[0].task;
+        assert_eq!(rent.completed_at, day("2024-03-02"));
+        assert_eq!(Some(rent.created_at), day("2024-03-01"));
+        // A single date after the marker is when it was completed
+        assert_eq!(entries[1].task.completed_at, day("2024-03-05"));
+        assert!(entries[2].task.completed_at.is_some());
+        assert_eq!(Some(entries[3].task.created_at), day("2024-02-28"));
+        assert!(entries[4].task.completed_at.is_none());
+        assert_eq!(
+            entries.iter().map(|e| e.task.position).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn keeps_contexts_and_key_values_in_the_description() {
+        let entries = parse(TAGS);
+        let tasks: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.task.title.as_str(), entry.task.description.as_deref()))
+            .collect();
+        assert_eq!(
+            tasks,
+            [
+                ("Call Sam", Some("@phone due:2024-04-01")),
+                ("Buy stamps", Some("@errands")),
+                // URLs and times aren't key:value pairs
+                ("Read https://example.com/post at 10:30", None),
+                (
+                    "Reply Re: the invoice",
+                    Some("Ask about the late fee\n\npri:high @email")
+                ),
+                ("@someday", None),
+            ]
+        );
+        assert_eq!(entries[0].list.as_deref(), Some("Work"));
+    }
+
+    #[test]
+    fn writes_tags_back_where_they_were_read() {
+        let exported = export(&list("Work", parse(TAGS)));
+        let line = exported.lines().next().unwrap();
+        assert!(line.contains(" Call Sam @phone due:2024-04-01 +Work id:"));
+        assert!(!line.contains(DESCRIPTION_KEY));
+    }
+
+    #[test]
+    fn round_trips_its_own_export() {
+        let entries = parse(EXPORT);
+        let call = &entries[1].task;
+        assert_eq!(call.title, "(B) Call Sam");
+        assert_eq!(
+            call.description.as_deref(),
+            Some("Ask about:\n\t- the invoice\r\n\t- the rate\n\n@phone due:2024-04-01")
+        );
+        assert_eq!(
+            entries[2].task.description.as_deref(),
+            Some("50%\u{a0}done")
+        );
+        assert_eq!(export(&list("Work", entries)), EXPORT);
+    }
+
+    #[test]
+    fn escapes_every_kind_of_whitespace() {
+        let description = "tab\there\r\nnbsp\u{a0}100%";
+        let escaped = escape(description);
+        assert_eq!(escaped, "tab%09here%0D%0Anbsp%C2%A0100%25");
+        assert_eq!(escaped.split_whitespace().count(), 1);
+        assert_eq!(unescape(&escaped), description);
+    }
+}
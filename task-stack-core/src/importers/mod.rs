@@ -0,0 +1,46 @@
+pub mod taskwarrior;
+pub mod todoist;
+pub mod trello;
+
+use crate::formats::Entry;
+use ulid::Ulid;
+
+/// Reads another app's export file into entries, each naming the list it
+/// belongs to where the app has projects or boards.
+pub trait Importer: Send + Sync {
+    /// Identifies the importer in forms.
+    fn id(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    /// Which file to export from the app.
+    fn hint(&self) -> &'static str;
+    fn parse(&self, contents: &str) -> Result<Vec<Entry>, String>;
+}
+
+pub const IMPORTERS: [&dyn Importer; 3] = [
+    &taskwarrior::Taskwarrior,
+    &todoist::Todoist,
+    &trello::Trello,
+];
+
+pub fn find(id: &str) -> Result<&'static dyn Importer, String> {
+    IMPORTERS
+        .into_iter()
+        .find(|importer| importer.id() == id)
+        .ok_or_else(|| format!("Unknown importer {id}"))
+}
+
+/// A task id derived from the app's own id when it's a number or hex (UUIDs,
+/// Trello's object ids), so importing the same export twice skips what's
+/// already there. Anything else gets a fresh id.
+fn stable_id(source_id: &str) -> Ulid {
+    let hex: String = source_id.chars().filter(|c| *c != '-').collect();
+    let parsed = if hex.chars().all(|c| c.is_ascii_digit()) {
+        hex.parse::<u128>().ok()
+    } else {
+        u128::from_str_radix(&hex, 16).ok()
+    };
+    parsed
+        .filter(|_| !hex.is_empty())
+        .map(Ulid::from)
+        .unwrap_or_else(Ulid::new)
+}
@@ -0,0 +1,157 @@
+use super::{stable_id, Importer};
+use crate::formats::{Entry, TaskRecord};
+use crate::tasks::TaskState;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+
+/// Reads `task export` output: a JSON array, or one object per line from older versions.
+pub struct Taskwarrior;
+
+#[derive(Deserialize)]
+struct Task {
+    uuid: String,
+    description: String,
+    status: String,
+    entry: Option<String>,
+    end: Option<String>,
+    project: Option<String>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Deserialize)]
+struct Annotation {
+    description: String,
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|at| at.and_utc())
+}
+
+impl Importer for Taskwarrior {
+    fn id(&self) -> &'static str {
+        "taskwarrior"
+    }
+
+    fn name(&self) -> &'static str {
+        "Taskwarrior"
+    }
+
+    fn hint(&self) -> &'static str {
+        "The output of task export, saved to a .json file"
+    }
+
+    /// Projects become lists. Pending, waiting and recurring tasks are active,
+    /// deleted ones are left out, and annotations become the description.
+    fn parse(&self, contents: &str) -> Result<Vec<Entry>, String> {
+        let contents = contents.trim();
+        let tasks: Vec<Task> = if contents.starts_with('[') {
+            serde_json::from_str(contents).map_err(|e| e.to_string())?
+        } else {
+            contents
+                .lines()
+                .map(|line| line.trim().trim_end_matches(','))
+                .filter(|line| !line.is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|e| e.to_string())?
+        };
+
+        let now = Utc::now();
+        Ok(tasks
+            .into_iter()
+            .filter(|task| task.status != "deleted")
+            .enumerate()
+            .map(|(position, task)| {
+                let completed = task.status == "completed";
+                let description = task
+                    .annotations
+                    .iter()
+                    .map(|annotation| annotation.description.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Entry {
+                    list: task.project.filter(|project| !project.is_empty()),
+                    task: TaskRecord {
+                        id: stable_id(&task.uuid),
+                        title: task.description,
+                        description: (!description.is_empty()).then_some(description),
+                        created_at: task.entry.as_deref().and_then(parse_date).unwrap_or(now),
+                        state: if completed {
+                            TaskState::Completed
+                        } else {
+                            TaskState::Active
+                        },
+                        completed_at: completed
+                            .then(|| task.end.as_deref().and_then(parse_date).unwrap_or(now)),
+                        position: position as i64,
+                    },
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = include_str!("../../tests/fixtures/taskwarrior/export.json");
+
+    #[test]
+    fn reads_an_export_without_deleted_tasks() {
+        let entries = Taskwarrior.parse(EXPORT).unwrap();
+        let read: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.list.as_deref(),
+                    entry.task.title.as_str(),
+                    entry.task.state.clone(),
+                    entry.task.position,
+                )
+            })
+            .collect();
+        assert_eq!(
+            read,
+            [
+                (Some("Work"), "Write the report", TaskState::Active, 0),
+                (Some("Home"), "Pay the rent", TaskState::Completed, 1),
+                (None, "Water the plants", TaskState::Active, 2),
+            ]
+        );
+        let report = &entries[0].task;
+        assert_eq!(
+            report.description.as_deref(),
+            Some("Use last month's figures\nSend to Sam")
+        );
+        assert_eq!(report.created_at, parse_date("20240301T090000Z").unwrap());
+        assert_eq!(entries[1].task.completed_at, parse_date("20240302T120000Z"));
+        assert!(entries[2].task.completed_at.is_none());
+        // The same ids when imported again
+        let again = Taskwarrior.parse(EXPORT).unwrap();
+        assert_eq!(again[0].task.id, report.id);
+    }
+
+    #[test]
+    fn reads_one_task_per_line() {
+        let tasks: Vec<serde_json::Value> = serde_json::from_str(EXPORT).unwrap();
+        let lines = tasks
+            .iter()
+            .map(|task| task.to_string())
+            .collect::<Vec<_>>()
+            .join(",\n");
+        let titles: Vec<_> = Taskwarrior
+            .parse(&lines)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.task.title)
+            .collect();
+        assert_eq!(
+            titles,
+            ["Write the report", "Pay the rent", "Water the plants"]
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use super::{stable_id, Importer};
+use crate::formats::{Entry, TaskRecord};
+use crate::tasks::TaskState;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use ulid::Ulid;
+
+/// Reads a project's CSV export, or the JSON of a Sync API dump with
+/// `projects` and `items`.
+pub struct Todoist;
+
+impl Importer for Todoist {
+    fn id(&self) -> &'static str {
+        "todoist"
+    }
+
+    fn name(&self) -> &'static str {
+        "Todoist"
+    }
+
+    fn hint(&self) -> &'static str {
+        "A project exported as CSV, or a JSON backup with projects and items"
+    }
+
+    fn parse(&self, contents: &str) -> Result<Vec<Entry>, String> {
+        if contents.trim_start().starts_with('{') {
+            parse_json(contents)
+        } else {
+            parse_csv(contents)
+        }
+    }
+}
+
+/// A CSV export holds one project with no name in it, so its tasks go where
+/// the import is pointed. Notes are added to the description of the task
+/// above them; sections are skipped.
+fn parse_csv(contents: &str) -> Result<Vec<Entry>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let (Some(kind), Some(content)) = (column("TYPE"), column("CONTENT")) else {
+        return Err("Not a Todoist CSV export: no TYPE and CONTENT columns".to_string());
+    };
+    let description = column("DESCRIPTION");
+
+    let now = Utc::now();
+    let mut entries: Vec<Entry> = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let text = record.get(content).unwrap_or_default().trim();
+        if text.is_empty() {
+            continue;
+        }
+        match record.get(kind).unwrap_or_default() {
+            "task" => entries.push(Entry {
+                list: None,
+                task: TaskRecord {
+                    id: Ulid::new(),
+                    title: text.to_string(),
+                    description: description
+                        .and_then(|i| record.get(i))
+                        .map(str::trim)
+                        .filter(|d| !d.is_empty())
+                        .map(str::to_string),
+                    created_at: now,
+                    state: TaskState::Active,
+                    completed_at: None,
+                    position: entries.len() as i64,
+                },
+            }),
+            "note" => {
+                if let Some(entry) = entries.last_mut() {
+                    let description = entry.task.description.get_or_insert_with(String::new);
+                    if !description.is_empty() {
+                        description.push_str("\n\n");
+                    }
+                    description.push_str(text);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// Ids are strings in the current API and numbers in older dumps.
+fn id(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+fn date(item: &Value, keys: &[&str]) -> Option<DateTime<Utc>> {
+    keys.iter()
+        .filter_map(|key| item[*key].as_str())
+        .find_map(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// Projects become lists, in their own order with items in theirs.
+fn parse_json(contents: &str) -> Result<Vec<Entry>, String> {
+    let dump: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let (Some(projects), Some(items)) = (dump["projects"].as_array(), dump["items"].as_array())
+    else {
+        return Err("Not a Todoist backup: no projects and items".to_string());
+    };
+    let project_names: HashMap<String, (usize, String)> = projects
+        .iter()
+        .enumerate()
+        .filter_map(|(order, project)| {
+            Some((
+                id(&project["id"])?,
+                (order, project["name"].as_str()?.to_string()),
+            ))
+        })
+        .collect();
+
+    let mut items: Vec<&Value> = items
+        .iter()
+        .filter(|item| !item["is_deleted"].as_bool().unwrap_or(false))
+        .collect();
+    let project_order = |item: &Value| {
+        id(&item["project_id"])
+            .and_then(|id| project_names.get(&id))
+            .map_or(usize::MAX, |(order, _)| *order)
+    };
+    let child_order = |item: &Value| {
+        item["child_order"]
+            .as_i64()
+            .or_else(|| item["item_order"].as_i64())
+            .unwrap_or(i64::MAX)
+    };
+    items.sort_by_key(|item| (project_order(item), child_order(item)));
+
+    let now = Utc::now();
+    Ok(items
+        .into_iter()
+        .filter_map(|item| {
+            let title = item["content"].as_str()?.trim();
+            (!title.is_empty()).then_some((item, title))
+        })
+        .enumerate()
+        .map(|(position, (item, title))| {
+            let completed = match &item["checked"] {
+                Value::Bool(checked) => *checked,
+                Value::Number(checked) => checked.as_i64() == Some(1),
+                _ => false,
+            };
+            Entry {
+                list: id(&item["project_id"])
+                    .and_then(|id| project_names.get(&id))
+                    .map(|(_, name)| name.clone()),
+                task: TaskRecord {
+                    id: id(&item["id"]).map_or_else(Ulid::new, |id| stable_id(&id)),
+                    title: title.to_string(),
+                    description: item["description"]
+                        .as_str()
+                        .map(str::trim)
+                        .filter(|d| !d.is_empty())
+                        .map(str::to_string),
+                    created_at: date(item, &["added_at", "date_added"]).unwrap_or(now),
+                    state: if completed {
+                        TaskState::Completed
+                    } else {
+                        TaskState::Active
+                    },
+                    completed_at: completed
+                        .then(|| date(item, &["completed_at", "date_completed"]).unwrap_or(now)),
+                    position: position as i64,
+                },
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = include_str!("../../tests/fixtures/todoist/project.csv");
+    const BACKUP: &str = include_str!("../../tests/fixtures/todoist/backup.json");
+
+    fn summary(entries: &[Entry]) -> Vec<(Option<&str>, &str, TaskState, i64)> {
+        entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.list.as_deref(),
+                    entry.task.title.as_str(),
+                    entry.task.state.clone(),
+                    entry.task.position,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_a_project_export_with_its_notes() {
+        let entries = Todoist.parse(PROJECT).unwrap();
+        assert_eq!(
+            summary(&entries),
+            [
+                (None, "Buy milk", TaskState::Active, 0),
+                (None, "Post the letter", TaskState::Active, 1),
+            ]
+        );
+        assert_eq!(
+            entries[0].task.description.as_deref(),
+            Some("Oat\n\nFrom the corner shop\n\nBefore Friday")
+        );
+        assert!(Todoist.parse("CONTENT\nBuy milk\n").is_err());
+    }
+
+    #[test]
+    fn reads_a_backup_by_project_then_item_order() {
+        let entries = Todoist.parse(BACKUP).unwrap();
+        assert_eq!(
+            summary(&entries),
+            [
+                (Some("Work"), "Plan the week", TaskState::Active, 0),
+                (Some("Work"), "Write the report", TaskState::Active, 1),
+                (Some("Home"), "Pay the rent", TaskState::Completed, 2),
+            ]
+        );
+        let rent = &entries[2].task;
+        assert_eq!(
+            rent.completed_at,
+            Some("2024-03-02T12:00:00Z".parse().unwrap())
+        );
+        assert_eq!(rent.id, Ulid::from(7025));
+        assert_eq!(
+            entries[0].task.created_at,
+            "2024-02-28T09:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(
+            entries[1].task.description.as_deref(),
+            Some("Use last month's figures")
+        );
+        assert!(Todoist.parse(r#"{"items": []}"#).is_err());
+    }
+}
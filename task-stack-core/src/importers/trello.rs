@@ -0,0 +1,173 @@
+use super::{stable_id, Importer};
+use crate::formats::{Entry, TaskRecord};
+use crate::tasks::TaskState;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Reads a board exported as JSON from its menu.
+pub struct Trello;
+
+#[derive(Deserialize)]
+struct Board {
+    name: String,
+    lists: Vec<List>,
+    cards: Vec<Card>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct List {
+    id: String,
+    name: String,
+    #[serde(default)]
+    closed: bool,
+    #[serde(default)]
+    pos: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Card {
+    id: String,
+    name: String,
+    #[serde(default)]
+    desc: String,
+    id_list: String,
+    #[serde(default)]
+    closed: bool,
+    #[serde(default)]
+    due_complete: bool,
+    #[serde(default)]
+    pos: f64,
+    date_last_activity: Option<DateTime<Utc>>,
+}
+
+/// Trello ids start with the second they were created at, in hex.
+fn created_at(id: &str) -> Option<DateTime<Utc>> {
+    let seconds = i64::from_str_radix(id.get(..8)?, 16).ok()?;
+    DateTime::from_timestamp(seconds, 0)
+}
+
+/// Whether a board column holds finished cards.
+fn is_done(list: &List) -> bool {
+    let name = list.name.to_lowercase();
+    ["done", "complete", "finished"]
+        .iter()
+        .any(|word| name.contains(word))
+}
+
+impl Importer for Trello {
+    fn id(&self) -> &'static str {
+        "trello"
+    }
+
+    fn name(&self) -> &'static str {
+        "Trello"
+    }
+
+    fn hint(&self) -> &'static str {
+        "A board exported from Menu, Print and export, Export as JSON"
+    }
+
+    /// The board becomes a list with its cards in column and card order.
+    /// Cards marked complete or in a column named like "Done" are completed;
+    /// archived cards and columns are left out.
+    fn parse(&self, contents: &str) -> Result<Vec<Entry>, String> {
+        let board: Board = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+        let lists: HashMap<&str, &List> = board
+            .lists
+            .iter()
+            .filter(|list| !list.closed)
+            .map(|list| (list.id.as_str(), list))
+            .collect();
+
+        let mut cards: Vec<(&List, &Card)> = board
+            .cards
+            .iter()
+            .filter(|card| !card.closed)
+            .filter_map(|card| Some((*lists.get(card.id_list.as_str())?, card)))
+            .collect();
+        cards.sort_by(|(a_list, a), (b_list, b)| {
+            a_list
+                .pos
+                .total_cmp(&b_list.pos)
+                .then(a.pos.total_cmp(&b.pos))
+        });
+
+        let now = Utc::now();
+        Ok(cards
+            .into_iter()
+            .enumerate()
+            .map(|(position, (list, card))| {
+                let completed = card.due_complete || is_done(list);
+                let description = card.desc.trim();
+                Entry {
+                    list: Some(board.name.clone()),
+                    task: TaskRecord {
+                        id: stable_id(&card.id),
+                        title: card.name.trim().to_string(),
+                        description: (!description.is_empty()).then(|| description.to_string()),
+                        created_at: created_at(&card.id).unwrap_or(now),
+                        state: if completed {
+                            TaskState::Completed
+                        } else {
+                            TaskState::Active
+                        },
+                        completed_at: completed.then(|| card.date_last_activity.unwrap_or(now)),
+                        position: position as i64,
+                    },
+                }
+            })
+            .filter(|entry| !entry.task.title.is_empty())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: &str = include_str!("../../tests/fixtures/trello/board.json");
+
+    #[test]
+    fn reads_a_board_in_column_and_card_order() {
+        let entries = Trello.parse(BOARD).unwrap();
+        let read: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.list.as_deref(),
+                    entry.task.title.as_str(),
+                    entry.task.state.clone(),
+                    entry.task.position,
+                )
+            })
+            .collect();
+        assert_eq!(
+            read,
+            [
+                (Some("Launch"), "Plan", TaskState::Completed, 0),
+                (Some("Launch"), "Write docs", TaskState::Active, 1),
+                // In the Done column
+                (Some("Launch"), "Ship it", TaskState::Completed, 2),
+            ]
+        );
+        let docs = &entries[1].task;
+        assert_eq!(docs.description.as_deref(), Some("Readme first"));
+        assert_eq!(
+            docs.created_at,
+            "2024-03-01T09:41:07Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert!(docs.completed_at.is_none());
+        assert_eq!(
+            entries[2].task.completed_at,
+            Some("2024-03-02T12:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            Trello.parse(BOARD).unwrap()[1].task.id,
+            docs.id,
+            "Importing again should give the same ids"
+        );
+    }
+}
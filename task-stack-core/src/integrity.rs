@@ -0,0 +1,423 @@
+use crate::database;
+use chrono::DateTime;
+use libsql::{params, Connection, Database};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use ulid::Ulid;
+
+/// Name of the list that orphaned tasks are moved into.
+pub const RECOVERED_LIST_NAME: &str = "Recovered Tasks";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// Reported by SQLite itself; only restoring a backup can fix these.
+    Corruption(String),
+    UnreadableList {
+        rowid: i64,
+        reason: String,
+    },
+    UnreadableTask {
+        rowid: i64,
+        reason: String,
+    },
+    OrphanedTask {
+        id: Ulid,
+        list_id: Ulid,
+    },
+    MissingPositions {
+        list_id: Ulid,
+        count: usize,
+    },
+    DuplicatePosition {
+        list_id: Ulid,
+        position: i64,
+        count: usize,
+    },
+}
+
+impl Problem {
+    pub fn is_repairable(&self) -> bool {
+        !matches!(self, Problem::Corruption(_))
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Corruption(message) => write!(f, "Database file is damaged: {message}"),
+            Problem::UnreadableList { rowid, reason } => {
+                write!(f, "List in row {rowid} can't be read: {reason}")
+            }
+            Problem::UnreadableTask { rowid, reason } => {
+                write!(f, "Task in row {rowid} can't be read: {reason}")
+            }
+            Problem::OrphanedTask { id, list_id } => {
+                write!(f, "Task {id} belongs to missing list {list_id}")
+            }
+            Problem::MissingPositions { list_id, count } => {
+                write!(f, "{count} tasks in list {list_id} have no position")
+            }
+            Problem::DuplicatePosition {
+                list_id,
+                position,
+                count,
+            } => write!(
+                f,
+                "{count} tasks in list {list_id} share position {position}"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Repairs {
+    pub quarantined: usize,
+    pub reassigned: usize,
+    pub renumbered_lists: usize,
+}
+
+impl Repairs {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for Repairs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Quarantined {} rows, reassigned {} orphaned tasks and renumbered {} lists",
+            self.quarantined, self.reassigned, self.renumbered_lists
+        )
+    }
+}
+
+fn parse_ulid(value: Option<String>, column: &str) -> Result<Ulid, String> {
+    let value = value.ok_or_else(|| format!("{column} is missing"))?;
+    Ulid::from_string(&value).map_err(|e| format!("{column} {value:?} is not a ULID ({e})"))
+}
+
+fn parse_date(value: Option<String>, column: &str) -> Result<(), String> {
+    let value = value.ok_or_else(|| format!("{column} is missing"))?;
+    DateTime::parse_from_rfc3339(&value)
+        .map(|_| ())
+        .map_err(|e| format!("{column} {value:?} is not a date ({e})"))
+}
+
+fn text(row: &libsql::Row, index: i32) -> Option<String> {
+    row.get::<Option<String>>(index).ok().flatten()
+}
+
+fn parse_task(row: &libsql::Row) -> Result<(Ulid, Ulid), String> {
+    let id = parse_ulid(text(row, 1), "id")?;
+    let list_id = parse_ulid(text(row, 2), "list_id")?;
+    match text(row, 3).as_deref() {
+        Some("Active") | Some("Completed") => {}
+        other => return Err(format!("state {other:?} is not Active or Completed")),
+    }
+    parse_date(text(row, 4), "created_at")?;
+    if let Some(completed_at) = text(row, 5) {
+        parse_date(Some(completed_at), "completed_at")?;
+    }
+    Ok((id, list_id))
+}
+
+/// Every list that deserializes cleanly, and the rows that don't.
+async fn read_lists(conn: &Connection) -> Result<(HashSet<Ulid>, Vec<Problem>), libsql::Error> {
+    let mut rows = conn
+        .query("SELECT rowid, id, created_at FROM tasklists", params![])
+        .await?;
+    let mut lists = HashSet::new();
+    let mut problems = Vec::new();
+
+    while let Some(row) = rows.next().await? {
+        let rowid: i64 = row.get(0)?;
+        let parsed = parse_ulid(text(&row, 1), "id")
+            .and_then(|id| parse_date(text(&row, 2), "created_at").map(|_| id));
+        match parsed {
+            Ok(id) => {
+                lists.insert(id);
+            }
+            Err(reason) => problems.push(Problem::UnreadableList { rowid, reason }),
+        }
+    }
+    Ok((lists, problems))
+}
+
+async fn read_tasks(
+    conn: &Connection,
+    lists: &HashSet<Ulid>,
+) -> Result<Vec<Problem>, libsql::Error> {
+    let mut rows = conn
+        .query(
+            "SELECT rowid, id, list_id, state, created_at, completed_at, position FROM tasks",
+            params![],
+        )
+        .await?;
+    let mut problems = Vec::new();
+    let mut positions: HashMap<Ulid, Vec<Option<i64>>> = HashMap::new();
+
+    while let Some(row) = rows.next().await? {
+        let rowid: i64 = row.get(0)?;
+        let parsed = parse_task(&row);
+
+        match parsed {
+            Err(reason) => problems.push(Problem::UnreadableTask { rowid, reason }),
+            Ok((id, list_id)) if !lists.contains(&list_id) => {
+                problems.push(Problem::OrphanedTask { id, list_id })
+            }
+            Ok((_, list_id)) => positions
+                .entry(list_id)
+                .or_default()
+                .push(row.get::<Option<i64>>(6).ok().flatten()),
+        }
+    }
+
+    for (list_id, list_positions) in positions {
+        let missing = list_positions.iter().filter(|p| p.is_none()).count();
+        if missing > 0 {
+            problems.push(Problem::MissingPositions {
+                list_id,
+                count: missing,
+            });
+        }
+        let mut counts: HashMap<i64, usize> = HashMap::new();
+        for position in list_positions.into_iter().flatten() {
+            *counts.entry(position).or_default() += 1;
+        }
+        let mut duplicates: Vec<_> = counts.into_iter().filter(|(_, count)| *count > 1).collect();
+        duplicates.sort();
+        problems.extend(duplicates.into_iter().map(|(position, count)| {
+            Problem::DuplicatePosition {
+                list_id,
+                position,
+                count,
+            }
+        }));
+    }
+    Ok(problems)
+}
+
+pub async fn check(db: &Database) -> Result<Vec<Problem>, libsql::Error> {
+    let mut problems: Vec<Problem> = database::quick_check(db)
+        .await?
+        .into_iter()
+        .map(Problem::Corruption)
+        .collect();
+
+    let conn = database::connect(db).await?;
+    let (lists, list_problems) = read_lists(&conn).await?;
+    problems.extend(list_problems);
+    problems.extend(read_tasks(&conn, &lists).await?);
+    Ok(problems)
+}
+
+/// The list recovered tasks go into, created the first time it's needed.
+async fn recovered(conn: &Connection, list: &mut Option<Ulid>) -> Result<Ulid, libsql::Error> {
+    if let Some(list_id) = *list {
+        return Ok(list_id);
+    }
+    let list_id = Ulid::new();
+    conn.execute(
+        "INSERT INTO tasklists (id, name, created_at) VALUES (?, ?, strftime('%FT%R:%f+00:00'))",
+        params![list_id.to_string(), RECOVERED_LIST_NAME],
+    )
+    .await?;
+    *list = Some(list_id);
+    Ok(list_id)
+}
+
+/// Moves unreadable rows into `quarantine`, gives orphaned tasks a list and
+/// renumbers lists whose positions are missing or duplicated.
+pub async fn repair(db: &Database) -> Result<Repairs, libsql::Error> {
+    let mut repairs = Repairs::default();
+    let conn = database::connect(db).await?;
+    let tx = conn.transaction().await?;
+
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS quarantine (
+            source TEXT NOT NULL,
+            data TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            quarantined_at TEXT NOT NULL
+        )",
+        params![],
+    )
+    .await?;
+
+    let mut renumber = HashSet::new();
+    let mut recovered_list = None;
+    let (_, list_problems) = read_lists(&tx).await?;
+    for problem in list_problems {
+        if let Problem::UnreadableList { rowid, reason } = problem {
+            tx.execute(
+                "INSERT INTO quarantine (source, data, reason, quarantined_at)
+                 SELECT 'tasklists', json_object('id', id, 'name', name, 'created_at', created_at),
+                        ?, strftime('%FT%R:%f+00:00')
+                 FROM tasklists WHERE rowid = ?",
+                params![reason, rowid],
+            )
+            .await?;
+            // Deleting the list would take its tasks with it
+            let list_id = recovered(&tx, &mut recovered_list).await?;
+            let moved = tx
+                .execute(
+                    "UPDATE tasks SET list_id = ?
+                     WHERE list_id = (SELECT id FROM tasklists WHERE rowid = ?)",
+                    params![list_id.to_string(), rowid],
+                )
+                .await?;
+            if moved > 0 {
+                renumber.insert(list_id);
+                repairs.reassigned += moved as usize;
+            }
+            tx.execute("DELETE FROM tasklists WHERE rowid = ?", params![rowid])
+                .await?;
+            repairs.quarantined += 1;
+        }
+    }
+
+    // Re-read now that bad lists are gone
+    let (lists, _) = read_lists(&tx).await?;
+    for problem in read_tasks(&tx, &lists).await? {
+        match problem {
+            Problem::UnreadableTask { rowid, reason } => {
+                tx.execute(
+                    "INSERT INTO quarantine (source, data, reason, quarantined_at)
+                     SELECT 'tasks', json_object(
+                                'id', id, 'list_id', list_id, 'title', title,
+                                'description', description, 'created_at', created_at,
+                                'state', state, 'completed_at', completed_at, 'position', position
+                            ),
+                            ?, strftime('%FT%R:%f+00:00')
+                     FROM tasks WHERE rowid = ?",
+                    params![reason, rowid],
+                )
+                .await?;
+                tx.execute("DELETE FROM tasks WHERE rowid = ?", params![rowid])
+                    .await?;
+                repairs.quarantined += 1;
+            }
+            Problem::OrphanedTask { id, .. } => {
+                let list_id = recovered(&tx, &mut recovered_list).await?;
+                tx.execute(
+                    "UPDATE tasks SET list_id = ? WHERE id = ?",
+                    params![list_id.to_string(), id.to_string()],
+                )
+                .await?;
+                renumber.insert(list_id);
+                repairs.reassigned += 1;
+            }
+            Problem::MissingPositions { list_id, .. }
+            | Problem::DuplicatePosition { list_id, .. } => {
+                renumber.insert(list_id);
+            }
+            Problem::Corruption(_) | Problem::UnreadableList { .. } => {}
+        }
+    }
+
+    for list_id in &renumber {
+        let mut rows = tx
+            .query(
+                "SELECT rowid FROM tasks WHERE list_id = ?
+                 ORDER BY position IS NULL, position, created_at, rowid",
+                params![list_id.to_string()],
+            )
+            .await?;
+        let mut rowids: Vec<i64> = Vec::new();
+        while let Some(row) = rows.next().await? {
+            rowids.push(row.get(0)?);
+        }
+        for (position, rowid) in rowids.into_iter().enumerate() {
+            tx.execute(
+                "UPDATE tasks SET position = ? WHERE rowid = ?",
+                params![position as i64 + 1, rowid],
+            )
+            .await?;
+        }
+    }
+    repairs.renumbered_lists = renumber.len();
+
+    tx.commit().await?;
+    Ok(repairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+
+    /// A list whose creation time can't be read, holding one task, and a task
+    /// whose list id isn't a ULID.
+    async fn add_unreadable_list(db: &Database) {
+        let conn = database::connect(db).await.unwrap();
+        let list_id = Ulid::new().to_string();
+        conn.execute(
+            "INSERT INTO tasklists (id, name, created_at) VALUES (?, 'Broken', 'yesterday')",
+            params![list_id.clone()],
+        )
+        .await
+        .unwrap();
+        conn.execute(
+            "INSERT INTO tasks (id, list_id, title, created_at, state, position)
+             VALUES (?, ?, 'Stranded', '2024-01-01T00:00:00.000+00:00', 'Active', 1)",
+            params![Ulid::new().to_string(), list_id],
+        )
+        .await
+        .unwrap();
+        // As databases from before foreign keys could hold
+        conn.execute("PRAGMA foreign_keys = OFF", params![])
+            .await
+            .unwrap();
+        conn.execute(
+            "INSERT INTO tasks (id, list_id, title, created_at, state, position)
+             VALUES (?, 'not-a-ulid', 'Garbled', '2024-01-01T00:00:00.000+00:00', 'Active', 1)",
+            params![Ulid::new().to_string()],
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_problems_without_repairing_until_asked() {
+        let dir = TempDir::new();
+        let path = dir.join("tasks.db");
+        let stack = testing::disk_stack(&path).await;
+        let db = database::init_database(&path, None).await.unwrap();
+        add_unreadable_list(&db).await;
+
+        // Reading skips the bad rows instead of panicking
+        assert_eq!(stack.get_lists().await.unwrap().len(), 1);
+        assert_eq!(stack.count_tasks().await.unwrap().len(), 1);
+
+        // Opening again leaves them for the user to see
+        let reopened = testing::disk_stack(&path).await;
+        let problems = reopened.check_integrity().await.unwrap();
+        assert!(matches!(
+            problems.as_slice(),
+            [
+                Problem::UnreadableList { .. },
+                Problem::OrphanedTask { .. },
+                Problem::UnreadableTask { .. }
+            ]
+        ));
+
+        let repairs = reopened.repair().await.unwrap();
+        assert_eq!(repairs.quarantined, 2);
+        assert_eq!(repairs.reassigned, 1);
+        assert!(reopened.check_integrity().await.unwrap().is_empty());
+        let recovered = reopened.find_list(RECOVERED_LIST_NAME).await.unwrap();
+        let tasks = reopened.for_list(recovered).get_tasks().await.unwrap();
+        assert_eq!(tasks[0].title, "Stranded");
+        // Renumbered from 1, like every other write
+        let positions: Vec<i64> = reopened
+            .get_repository()
+            .get_all_tasks(&recovered)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, position)| position)
+            .collect();
+        assert_eq!(positions, [1]);
+    }
+}
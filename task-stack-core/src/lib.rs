@@ -0,0 +1,18 @@
+//! Tasks, lists and their storage, shared by the app, the CLI and anything
+//! else that works on a Task Stack database.
+
+pub mod backup;
+pub mod database;
+pub mod formats;
+pub mod importers;
+pub mod integrity;
+pub mod profiles;
+pub mod repository;
+pub mod settings;
+pub mod status;
+pub mod tasks;
+
+#[cfg(test)]
+mod testing;
+
+pub use tasks::{Change, Task, TaskList, TaskStack, TaskState};
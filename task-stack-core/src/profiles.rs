@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the profile list is kept, relative to the app data directory.
+pub const PROFILES_FILE: &str = "profiles.json";
+/// Where databases for new profiles are created, relative to the app data directory.
+pub const PROFILES_DIR: &str = "profiles";
+/// The profile for `tasks.db`, which is where tasks lived before profiles.
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// A named database file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profiles {
+    /// Name of the profile opened on launch.
+    pub current: String,
+    pub profiles: Vec<Profile>,
+}
+
+impl Profiles {
+    fn default_for(app_data_dir: &Path) -> Self {
+        Self {
+            current: DEFAULT_PROFILE.to_string(),
+            profiles: vec![Profile {
+                name: DEFAULT_PROFILE.to_string(),
+                path: app_data_dir.join("tasks.db"),
+            }],
+        }
+    }
+
+    /// Reads the profile list, starting with just the default profile if
+    /// there is none or it can't be read.
+    pub fn load(app_data_dir: &Path) -> Self {
+        let mut profiles = match fs::read_to_string(app_data_dir.join(PROFILES_FILE)) {
+            Ok(json) => serde_json::from_str(&json)
+                .inspect_err(|e| eprintln!("Failed to read profiles: {}", e))
+                .unwrap_or_else(|_| Self::default_for(app_data_dir)),
+            Err(_) => Self::default_for(app_data_dir),
+        };
+        if profiles.profiles.is_empty() {
+            profiles = Self::default_for(app_data_dir);
+        }
+        if profiles.get(&profiles.current).is_none() {
+            profiles.current = profiles.profiles[0].name.clone();
+        }
+        profiles
+    }
+
+    pub fn save(&self, app_data_dir: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(app_data_dir.join(PROFILES_FILE), json).map_err(|e| e.to_string())
+    }
+
+    pub fn current(&self) -> &Profile {
+        self.get(&self.current)
+            .or_else(|| self.profiles.first())
+            .expect("At least one profile")
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    pub fn find_path(&self, path: &Path) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.path == path)
+    }
+
+    pub fn switch(&mut self, name: &str) -> Result<&Profile, String> {
+        if self.get(name).is_none() {
+            return Err(format!("Profile {name} not found"));
+        }
+        self.current = name.to_string();
+        Ok(self.current())
+    }
+
+    /// Adds a profile with a new database file under `PROFILES_DIR`.
+    pub fn create(&mut self, name: &str, app_data_dir: &Path) -> Result<&Profile, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name can't be empty".to_string());
+        }
+        if self.get(name).is_some() {
+            return Err(format!("Profile {name} already exists"));
+        }
+        let slug: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let dir = app_data_dir.join(PROFILES_DIR);
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let mut path = dir.join(format!("{slug}.db"));
+        let mut suffix = 1;
+        while path.exists() || self.find_path(&path).is_some() {
+            suffix += 1;
+            path = dir.join(format!("{slug}-{suffix}.db"));
+        }
+        self.profiles.push(Profile {
+            name: name.to_string(),
+            path,
+        });
+        Ok(self.profiles.last().expect("Profile to be added"))
+    }
+
+    /// Adds a profile for an existing database file, named after the file,
+    /// or returns the profile that already uses it.
+    pub fn add_file(&mut self, path: &Path) -> Result<&Profile, String> {
+        if !path.is_file() {
+            return Err(format!("{} is not a file", path.display()));
+        }
+        let path = path.canonicalize().map_err(|e| e.to_string())?;
+        if let Some(index) = self.profiles.iter().position(|p| p.path == path) {
+            return Ok(&self.profiles[index]);
+        }
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "tasks".to_string());
+        let mut name = stem.clone();
+        let mut suffix = 1;
+        while self.get(&name).is_some() {
+            suffix += 1;
+            name = format!("{stem} ({suffix})");
+        }
+        self.profiles.push(Profile { name, path });
+        Ok(self.profiles.last().expect("Profile to be added"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn names(profiles: &Profiles) -> Vec<&str> {
+        profiles.profiles.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn creates_and_switches_to_profiles_by_name() {
+        let dir = TempDir::new();
+        let app_data_dir = dir.join("");
+        let mut profiles = Profiles::load(&app_data_dir);
+        assert_eq!(profiles.current().name, DEFAULT_PROFILE);
+
+        let path = profiles
+            .create(" Work ", &app_data_dir)
+            .unwrap()
+            .path
+            .clone();
+        assert_eq!(path, app_data_dir.join(PROFILES_DIR).join("work.db"));
+        assert!(profiles.create("Work", &app_data_dir).is_err());
+        assert!(profiles.create("  ", &app_data_dir).is_err());
+        // Names that slug alike get files of their own
+        let other = profiles.create("WORK", &app_data_dir).unwrap().path.clone();
+        assert_eq!(other, app_data_dir.join(PROFILES_DIR).join("work-2.db"));
+
+        assert!(profiles.switch("Nowhere").is_err());
+        assert_eq!(profiles.current().name, DEFAULT_PROFILE);
+        assert_eq!(profiles.switch("Work").unwrap().path, path);
+
+        profiles.save(&app_data_dir).unwrap();
+        let loaded = Profiles::load(&app_data_dir);
+        assert_eq!(loaded.current, "Work");
+        assert_eq!(names(&loaded), [DEFAULT_PROFILE, "Work", "WORK"]);
+    }
+
+    #[test]
+    fn adds_existing_files_once() {
+        let dir = TempDir::new();
+        let app_data_dir = dir.join("");
+        let mut profiles = Profiles::load(&app_data_dir);
+
+        assert!(profiles.add_file(&dir.join("missing.db")).is_err());
+        assert_eq!(names(&profiles), [DEFAULT_PROFILE]);
+
+        let path = dir.join("home.db");
+        fs::write(&path, "").unwrap();
+        assert_eq!(profiles.add_file(&path).unwrap().name, "home");
+        // Already a profile
+        assert_eq!(profiles.add_file(&path).unwrap().name, "home");
+        // Another file with the same name
+        fs::create_dir(dir.join("other")).unwrap();
+        let other = dir.join("other").join("home.db");
+        fs::write(&other, "").unwrap();
+        assert_eq!(profiles.add_file(&other).unwrap().name, "home (2)");
+        assert_eq!(names(&profiles), [DEFAULT_PROFILE, "home", "home (2)"]);
+    }
+
+    #[test]
+    fn falls_back_to_the_default_profile() {
+        let dir = TempDir::new();
+        let app_data_dir = dir.join("");
+        let default = Profiles::default_for(&app_data_dir);
+
+        fs::write(app_data_dir.join(PROFILES_FILE), "{ not json").unwrap();
+        let loaded = Profiles::load(&app_data_dir);
+        assert_eq!(loaded.current, DEFAULT_PROFILE);
+        assert_eq!(loaded.profiles, default.profiles);
+
+        // A current profile that was removed by hand
+        fs::write(
+            app_data_dir.join(PROFILES_FILE),
+            r#"{"current":"Gone","profiles":[{"name":"Work","path":"/work.db"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(Profiles::load(&app_data_dir).current().name, "Work");
+
+        fs::write(
+            app_data_dir.join(PROFILES_FILE),
+            r#"{"current":"Work","profiles":[]}"#,
+        )
+        .unwrap();
+        assert_eq!(Profiles::load(&app_data_dir).profiles, default.profiles);
+    }
+}
@@ -0,0 +1,128 @@
+use super::{TaskCounts, TaskRepository, Write};
+use crate::integrity::{Problem, Repairs};
+use crate::tasks::{Task, TaskList, TaskState};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use ulid::Ulid;
+
+/// Stands in for a database that couldn't be opened, e.g. a locked profile,
+/// refusing everything so nothing reaches the one open before it.
+pub struct ClosedRepository;
+
+fn closed<T>() -> Result<T, String> {
+    Err("No database is open".to_string())
+}
+
+#[async_trait]
+impl TaskRepository for ClosedRepository {
+    async fn insert_task(&self, _task: &Task, _position: i64) -> Result<(), String> {
+        closed()
+    }
+
+    async fn push_task(&self, _task: &Task) -> Result<(), String> {
+        closed()
+    }
+
+    async fn get_all_tasks(&self, _list_id: &Ulid) -> Result<Vec<(Task, i64)>, String> {
+        closed()
+    }
+
+    async fn get_current_tasks(
+        &self,
+        _list_id: &Ulid,
+        _completed_window_hours: u32,
+    ) -> Result<Vec<(Task, i64)>, String> {
+        closed()
+    }
+
+    async fn get_active_tasks(&self, _list_id: &Ulid) -> Result<Vec<(Task, i64)>, String> {
+        closed()
+    }
+
+    async fn get_first_active_task(&self, _list_id: &Ulid) -> Result<Option<Task>, String> {
+        closed()
+    }
+
+    async fn update_task_state(
+        &self,
+        _id: &Ulid,
+        _state: TaskState,
+        _completed_at: Option<DateTime<Utc>>,
+    ) -> Result<(), String> {
+        closed()
+    }
+
+    async fn update_task_position(&self, _id: &Ulid, _position: i64) -> Result<(), String> {
+        closed()
+    }
+
+    async fn move_task_to_end(&self, _id: &Ulid) -> Result<(), String> {
+        closed()
+    }
+
+    async fn delete_task(&self, _id: &Ulid) -> Result<(), String> {
+        closed()
+    }
+
+    async fn get_lists(&self) -> Result<Vec<TaskList>, String> {
+        closed()
+    }
+
+    async fn create_list(&self, _name: &str) -> Result<Ulid, String> {
+        closed()
+    }
+
+    async fn insert_list(&self, _list: &TaskList) -> Result<(), String> {
+        closed()
+    }
+
+    async fn delete_list(&self, _id: &Ulid) -> Result<(), String> {
+        closed()
+    }
+
+    async fn apply(&self, _writes: Vec<Write>) -> Result<(), String> {
+        closed()
+    }
+
+    async fn get_highest_position(&self, _list_id: &Ulid) -> Result<i64, String> {
+        closed()
+    }
+
+    async fn count_tasks(&self) -> Result<HashMap<Ulid, TaskCounts>, String> {
+        closed()
+    }
+
+    async fn get_setting(&self, _key: &str) -> Result<Option<String>, String> {
+        closed()
+    }
+
+    async fn set_setting(&self, _key: &str, _value: &str) -> Result<(), String> {
+        closed()
+    }
+
+    async fn data_version(&self) -> Result<i64, String> {
+        closed()
+    }
+
+    async fn check_integrity(&self) -> Result<Vec<Problem>, String> {
+        closed()
+    }
+
+    async fn repair(&self) -> Result<Repairs, String> {
+        closed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn refuses_checks_and_repairs() {
+        let repo = ClosedRepository;
+        assert!(repo.data_version().await.is_err());
+        assert!(repo.check_integrity().await.is_err());
+        assert!(repo.repair().await.is_err());
+    }
+}
@@ -0,0 +1,404 @@
+use super::{TaskCounts, TaskRepository, Write};
+use crate::database::{self, Session};
+use crate::integrity::{self, Problem, Repairs};
+use crate::tasks::{Task, TaskList, TaskState};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use libsql::{params, Database};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use ulid::Ulid;
+
+/// Tasks stored in a libsql database file, optionally encrypted.
+pub struct LibsqlRepository {
+    /// `None` while closed, e.g. while a restore swaps the file.
+    session: RwLock<Option<Arc<Session>>>,
+    path: PathBuf,
+    passphrase: Mutex<Option<String>>,
+}
+
+impl LibsqlRepository {
+    /// Opens or creates the database at `path`, migrating it to the current schema.
+    pub async fn open(path: PathBuf, passphrase: Option<String>) -> Result<Self, libsql::Error> {
+        let db = database::init_database(&path, passphrase.as_deref()).await?;
+        Ok(Self {
+            session: RwLock::new(Some(Arc::new(Session::open(db).await?))),
+            path,
+            passphrase: Mutex::new(passphrase),
+        })
+    }
+
+    pub fn session(&self) -> Result<Arc<Session>, String> {
+        self.session
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "The database is closed".to_string())
+    }
+
+    /// Swaps in a freshly opened database, dropping the old connection once
+    /// the queries still using it finish.
+    async fn replace(&self, db: Database) -> Result<(), String> {
+        let session = Session::open(db).await.map_err(|e| e.to_string())?;
+        *self.session.write().unwrap() = Some(Arc::new(session));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TaskRepository for LibsqlRepository {
+    async fn insert_task(&self, task: &Task, position: i64) -> Result<(), String> {
+        let session = self.session()?;
+        database::retry(|| database::insert_task(&session, task, position))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn push_task(&self, task: &Task) -> Result<(), String> {
+        let session = self.session()?;
+        database::retry(|| database::push_task(&session, task))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get_all_tasks(&self, list_id: &Ulid) -> Result<Vec<(Task, i64)>, String> {
+        let session = self.session()?;
+        database::retry(|| database::get_all_tasks(&session, list_id))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get_current_tasks(
+        &self,
+        list_id: &Ulid,
+        completed_window_hours: u32,
+    ) -> Result<Vec<(Task, i64)>, String> {
+        let session = self.session()?;
+        database::retry(|| database::get_current_tasks(&session, list_id, completed_window_hours))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get_active_tasks(&self, list_id: &Ulid) -> Result<Vec<(Task, i64)>, String> {
+        let session = self.session()?;
+        database::retry(|| database::get_active_tasks(&session, list_id))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get_first_active_task(&self, list_id: &Ulid) -> Result<Option<Task>, String> {
+        let session = self.session()?;
+        database::retry(|| database::get_first_active_task(&session, list_id))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn update_task_state(
+        &self,
+        id: &Ulid,
+        state: TaskState,
+        completed_at: Option<DateTime<Utc>>,
+    ) -> Result<(), String> {
+        let session = self.session()?;
+        database::retry(|| database::update_task_state(&session, id, state.clone(), completed_at))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn update_task_position(&self, id: &Ulid, position: i64) -> Result<(), String> {
+        let session = self.session()?;
+        database::retry(|| database::update_task_position(&session, id, position))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn move_task_to_end(&self, id: &Ulid) -> Result<(), String> {
+        let session = self.session()?;
+        database::retry(|| database::move_task_to_end(&session, id))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn delete_task(&self, id: &Ulid) -> Result<(), String> {
+        let session = self.session()?;
+        database::retry(|| database::delete_task(&session, id))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get_lists(&self) -> Result<Vec<TaskList>, String> {
+        let session = self.session()?;
+        database::retry(|| database::get_lists(&session))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn create_list(&self, name: &str) -> Result<Ulid, String> {
+        let session = self.session()?;
+        database::retry(|| database::create_list(&session, name))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn insert_list(&self, list: &TaskList) -> Result<(), String> {
+        let session = self.session()?;
+        database::retry(|| database::insert_list(&session, list))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn delete_list(&self, id: &Ulid) -> Result<(), String> {
+        let session = self.session()?;
+        database::retry(|| database::delete_list(&session, id))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn apply(&self, writes: Vec<Write>) -> Result<(), String> {
+        let session = self.session()?;
+        database::retry(|| database::apply(session.database(), &writes))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get_highest_position(&self, list_id: &Ulid) -> Result<i64, String> {
+        let session = self.session()?;
+        database::retry(|| database::get_highest_position(&session, list_id))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn count_tasks(&self) -> Result<HashMap<Ulid, TaskCounts>, String> {
+        let session = self.session()?;
+        database::retry(|| database::count_tasks(&session))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get_setting(&self, key: &str) -> Result<Option<String>, String> {
+        let session = self.session()?;
+        database::retry(|| database::get_setting(&session, key))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn set_setting(&self, key: &str, value: &str) -> Result<(), String> {
+        let session = self.session()?;
+        database::retry(|| database::set_setting(&session, key, value))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn passphrase(&self) -> Option<String> {
+        self.passphrase.lock().unwrap().clone()
+    }
+
+    /// Encrypts the database with `passphrase`, or removes encryption when `None`.
+    async fn set_passphrase(&self, passphrase: Option<&str>) -> Result<(), String> {
+        let session = self.session()?;
+        let db = database::rekey(&session, &self.path, passphrase)
+            .await
+            .map_err(|e| e.to_string())?;
+        self.replace(db).await?;
+        *self.passphrase.lock().unwrap() = passphrase.map(str::to_string);
+        Ok(())
+    }
+
+    /// Checkpoints the WAL into the file, then holds a read transaction so no
+    /// later writes are checkpointed into it mid-copy. Retries while other
+    /// connections keep the checkpoint from finishing.
+    async fn snapshot(&self, target: &Path) -> Result<(), String> {
+        let session = self.session()?;
+        let conn = database::connect(session.database())
+            .await
+            .map_err(|e| e.to_string())?;
+        database::retry(|| database::begin_snapshot(&conn, &self.path))
+            .await
+            .map_err(|e| e.to_string())?;
+        let copied = std::fs::copy(&self.path, target)
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+        conn.execute("COMMIT", params![])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        copied
+    }
+
+    /// Waits for the queries still using the connection to finish, then drops
+    /// it so nothing reads or writes the file until `reopen`.
+    async fn close(&self) -> Result<(), String> {
+        let Some(session) = self.session.write().unwrap().take() else {
+            return Ok(());
+        };
+        let deadline = Instant::now() + database::BUSY_TIMEOUT;
+        while Arc::strong_count(&session) > 1 {
+            if Instant::now() >= deadline {
+                *self.session.write().unwrap() = Some(session);
+                return Err("The database is still in use".to_string());
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        drop(session);
+        Ok(())
+    }
+
+    /// Re-opens the file after it was replaced on disk, or after `close`.
+    async fn reopen(&self) -> Result<(), String> {
+        let passphrase = self
+            .passphrase()
+            .filter(|_| database::is_encrypted(&self.path));
+        let db = database::init_database(&self.path, passphrase.as_deref())
+            .await
+            .map_err(|e| e.to_string())?;
+        self.replace(db).await
+    }
+
+    async fn data_version(&self) -> Result<i64, String> {
+        let session = self.session()?;
+        database::retry(|| database::data_version(&session))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn check_integrity(&self) -> Result<Vec<Problem>, String> {
+        let session = self.session()?;
+        database::retry(|| integrity::check(session.database()))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn repair(&self) -> Result<Repairs, String> {
+        integrity::repair(self.session()?.database())
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[tokio::test]
+    async fn refuses_queries_while_closed() {
+        let dir = TempDir::new();
+        let repo = LibsqlRepository::open(dir.join("tasks.db"), None)
+            .await
+            .unwrap();
+        repo.create_list("Work").await.unwrap();
+
+        repo.close().await.unwrap();
+        assert!(repo.get_lists().await.is_err());
+
+        repo.reopen().await.unwrap();
+        assert_eq!(repo.get_lists().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn snapshots_writes_a_reader_kept_in_the_wal() {
+        let dir = TempDir::new();
+        let repo = LibsqlRepository::open(dir.join("tasks.db"), None)
+            .await
+            .unwrap();
+        repo.create_list("Before").await.unwrap();
+
+        // A reader from before the next write keeps it from being checkpointed
+        let reader = database::connect(repo.session().unwrap().database())
+            .await
+            .unwrap();
+        reader.execute("BEGIN", params![]).await.unwrap();
+        let mut rows = reader
+            .query("SELECT COUNT(*) FROM tasklists", params![])
+            .await
+            .unwrap();
+        rows.next().await.unwrap();
+        drop(rows);
+        repo.create_list("After").await.unwrap();
+        // Finishes once the snapshot has found the checkpoint blocked and waits to retry
+        tokio::spawn(async move { reader.execute("COMMIT", params![]).await.unwrap() });
+
+        let target = dir.join("snapshot.db");
+        repo.snapshot(&target).await.unwrap();
+        let copy = database::open_read_only(&target, None).await.unwrap();
+        assert_eq!(database::count_rows(&copy, "tasklists").await.unwrap(), 2);
+    }
+
+    async fn journal_mode(repo: &LibsqlRepository) -> String {
+        let conn = database::connect(repo.session().unwrap().database())
+            .await
+            .unwrap();
+        let mut rows = conn.query("PRAGMA journal_mode", params![]).await.unwrap();
+        rows.next().await.unwrap().unwrap().get(0).unwrap()
+    }
+
+    /// Opens the database with `passphrase` and checks the task written
+    /// before encrypting it is still there.
+    async fn reopened(path: &Path, passphrase: Option<&str>, task: &Task) -> LibsqlRepository {
+        let repo = LibsqlRepository::open(path.to_path_buf(), passphrase.map(str::to_string))
+            .await
+            .expect("Database to open with the current passphrase");
+        let tasks = repo.get_all_tasks(&task.list_id).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].0.title, task.title);
+        repo
+    }
+
+    async fn refuses(path: &Path, passphrase: Option<&str>) {
+        let error = LibsqlRepository::open(path.to_path_buf(), passphrase.map(str::to_string))
+            .await
+            .err()
+            .expect("Database not to open with another passphrase");
+        if passphrase.is_some() {
+            assert!(database::is_wrong_passphrase(&error), "{error}");
+        }
+    }
+
+    #[tokio::test]
+    async fn encrypts_rekeys_and_decrypts_keeping_the_data() {
+        let dir = TempDir::new();
+        let path = dir.join("tasks.db");
+        let repo = LibsqlRepository::open(path.clone(), None).await.unwrap();
+        let list_id = repo.create_list("Errands").await.unwrap();
+        let task = Task::new("Buy milk".to_string(), list_id);
+        repo.insert_task(&task, 1).await.unwrap();
+        assert!(!database::is_encrypted(&path));
+
+        // Encrypting a plain database
+        repo.set_passphrase(Some("first")).await.unwrap();
+        assert!(database::is_encrypted(&path));
+        assert_eq!(repo.passphrase().as_deref(), Some("first"));
+        // Still usable, and back in WAL mode after rekeying outside it
+        assert_eq!(repo.get_all_tasks(&list_id).await.unwrap().len(), 1);
+        assert_eq!(journal_mode(&repo).await, "wal");
+        drop(repo);
+
+        refuses(&path, None).await;
+        refuses(&path, Some("wrong")).await;
+        let repo = reopened(&path, Some("first"), &task).await;
+
+        // Changing the passphrase
+        repo.set_passphrase(Some("second")).await.unwrap();
+        assert!(database::is_encrypted(&path));
+        assert_eq!(journal_mode(&repo).await, "wal");
+        drop(repo);
+
+        refuses(&path, Some("first")).await;
+        let repo = reopened(&path, Some("second"), &task).await;
+
+        // Removing encryption
+        repo.set_passphrase(None).await.unwrap();
+        assert!(!database::is_encrypted(&path));
+        assert_eq!(repo.passphrase(), None);
+        assert_eq!(journal_mode(&repo).await, "wal");
+        drop(repo);
+
+        reopened(&path, None, &task).await;
+    }
+}
@@ -0,0 +1,258 @@
+use super::{TaskCounts, TaskRepository, Write};
+use crate::tasks::{Task, TaskList, TaskState};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use ulid::Ulid;
+
+/// Keeps everything in memory, for exercising `TaskStack` and pages without a file.
+#[derive(Default)]
+pub struct InMemoryRepository {
+    state: Mutex<State>,
+}
+
+#[derive(Default, Clone)]
+struct State {
+    lists: Vec<TaskList>,
+    tasks: Vec<(Task, i64)>,
+    settings: HashMap<String, String>,
+}
+
+impl State {
+    fn insert_task(&mut self, task: &Task, position: i64) -> Result<(), String> {
+        if !self.lists.iter().any(|list| list.id == task.list_id) {
+            return Err(format!("List {} not found", task.list_id));
+        }
+        if self.tasks.iter().any(|(t, _)| t.id == task.id) {
+            return Err(format!("Task {} already exists", task.id));
+        }
+        self.tasks.push((task.clone(), position));
+        Ok(())
+    }
+
+    /// The highest position in the list, or 0 when it has no tasks.
+    fn end_of(&self, list_id: &Ulid) -> i64 {
+        self.tasks
+            .iter()
+            .filter(|(task, _)| task.list_id == *list_id)
+            .map(|(_, position)| *position)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn insert_list(&mut self, list: &TaskList) -> Result<(), String> {
+        if self.lists.iter().any(|l| l.id == list.id) {
+            return Err(format!("List {} already exists", list.id));
+        }
+        self.lists.push(list.clone());
+        Ok(())
+    }
+
+    fn delete_list(&mut self, id: &Ulid) {
+        self.lists.retain(|list| list.id != *id);
+        self.tasks.retain(|(task, _)| task.list_id != *id);
+    }
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn tasks_in(&self, list_id: &Ulid, keep: impl Fn(&Task) -> bool) -> Vec<(Task, i64)> {
+        let mut tasks: Vec<(Task, i64)> = self
+            .state
+            .lock()
+            .unwrap()
+            .tasks
+            .iter()
+            .filter(|(task, _)| task.list_id == *list_id && keep(task))
+            .cloned()
+            .collect();
+        tasks.sort_by_key(|(_, position)| *position);
+        tasks
+    }
+
+    fn update_task(&self, id: &Ulid, update: impl FnOnce(&mut (Task, i64))) {
+        if let Some(entry) = self
+            .state
+            .lock()
+            .unwrap()
+            .tasks
+            .iter_mut()
+            .find(|(task, _)| task.id == *id)
+        {
+            update(entry);
+        }
+    }
+}
+
+#[async_trait]
+impl TaskRepository for InMemoryRepository {
+    async fn insert_task(&self, task: &Task, position: i64) -> Result<(), String> {
+        self.state.lock().unwrap().insert_task(task, position)
+    }
+
+    async fn push_task(&self, task: &Task) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        let position = state.end_of(&task.list_id) + 1;
+        state.insert_task(task, position)
+    }
+
+    async fn get_all_tasks(&self, list_id: &Ulid) -> Result<Vec<(Task, i64)>, String> {
+        let mut tasks = self.tasks_in(list_id, |_| true);
+        tasks.sort_by(|(a, a_position), (b, b_position)| {
+            b.completed()
+                .cmp(&a.completed())
+                .then(b.completed_at.cmp(&a.completed_at))
+                .then(a_position.cmp(b_position))
+        });
+        Ok(tasks)
+    }
+
+    async fn get_current_tasks(
+        &self,
+        list_id: &Ulid,
+        completed_window_hours: u32,
+    ) -> Result<Vec<(Task, i64)>, String> {
+        let cutoff = Utc::now() - Duration::hours(completed_window_hours.into());
+        Ok(self.tasks_in(list_id, |task| match task.state {
+            TaskState::Active => true,
+            TaskState::Completed => task.completed_at.is_some_and(|at| at >= cutoff),
+        }))
+    }
+
+    async fn get_active_tasks(&self, list_id: &Ulid) -> Result<Vec<(Task, i64)>, String> {
+        Ok(self.tasks_in(list_id, |task| !task.completed()))
+    }
+
+    async fn get_first_active_task(&self, list_id: &Ulid) -> Result<Option<Task>, String> {
+        Ok(self
+            .tasks_in(list_id, |task| !task.completed())
+            .into_iter()
+            .next()
+            .map(|(task, _)| task))
+    }
+
+    async fn update_task_state(
+        &self,
+        id: &Ulid,
+        state: TaskState,
+        completed_at: Option<DateTime<Utc>>,
+    ) -> Result<(), String> {
+        self.update_task(id, |(task, _)| {
+            task.state = state;
+            task.completed_at = completed_at;
+        });
+        Ok(())
+    }
+
+    async fn update_task_position(&self, id: &Ulid, position: i64) -> Result<(), String> {
+        self.update_task(id, |(_, p)| *p = position);
+        Ok(())
+    }
+
+    async fn move_task_to_end(&self, id: &Ulid) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        let Some(list_id) = state
+            .tasks
+            .iter()
+            .find(|(task, _)| task.id == *id)
+            .map(|(task, _)| task.list_id)
+        else {
+            return Ok(());
+        };
+        let end = state.end_of(&list_id) + 1;
+        if let Some((_, position)) = state.tasks.iter_mut().find(|(task, _)| task.id == *id) {
+            *position = end;
+        }
+        Ok(())
+    }
+
+    async fn delete_task(&self, id: &Ulid) -> Result<(), String> {
+        self.state
+            .lock()
+            .unwrap()
+            .tasks
+            .retain(|(task, _)| task.id != *id);
+        Ok(())
+    }
+
+    async fn get_lists(&self) -> Result<Vec<TaskList>, String> {
+        let mut lists = self.state.lock().unwrap().lists.clone();
+        lists.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        Ok(lists)
+    }
+
+    async fn create_list(&self, name: &str) -> Result<Ulid, String> {
+        let id = Ulid::new();
+        self.state.lock().unwrap().lists.push(TaskList {
+            id,
+            name: name.to_string(),
+            created_at: Utc::now(),
+        });
+        Ok(id)
+    }
+
+    async fn insert_list(&self, list: &TaskList) -> Result<(), String> {
+        self.state.lock().unwrap().insert_list(list)
+    }
+
+    async fn delete_list(&self, id: &Ulid) -> Result<(), String> {
+        self.state.lock().unwrap().delete_list(id);
+        Ok(())
+    }
+
+    /// Works on a copy, kept only once every write has succeeded.
+    async fn apply(&self, writes: Vec<Write>) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        let mut next = state.clone();
+        for write in &writes {
+            match write {
+                Write::InsertList(list) => next.insert_list(list)?,
+                Write::InsertTask(task, position) => next.insert_task(task, *position)?,
+                Write::SetPosition(id, position) => {
+                    if let Some((_, p)) = next.tasks.iter_mut().find(|(task, _)| task.id == *id) {
+                        *p = *position;
+                    }
+                }
+                Write::DeleteList(id) => next.delete_list(id),
+                Write::SetSetting(key, value) => {
+                    next.settings.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        *state = next;
+        Ok(())
+    }
+
+    async fn get_highest_position(&self, list_id: &Ulid) -> Result<i64, String> {
+        Ok(self.state.lock().unwrap().end_of(list_id))
+    }
+
+    async fn count_tasks(&self) -> Result<HashMap<Ulid, TaskCounts>, String> {
+        let mut counts: HashMap<Ulid, TaskCounts> = HashMap::new();
+        for (task, _) in &self.state.lock().unwrap().tasks {
+            let count = counts.entry(task.list_id).or_default();
+            count.total += 1;
+            if !task.completed() {
+                count.active += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn get_setting(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.state.lock().unwrap().settings.get(key).cloned())
+    }
+
+    async fn set_setting(&self, key: &str, value: &str) -> Result<(), String> {
+        self.state
+            .lock()
+            .unwrap()
+            .settings
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+pub mod closed;
+pub mod disk;
+pub mod memory;
+
+pub use closed::ClosedRepository;
+pub use disk::LibsqlRepository;
+pub use memory::InMemoryRepository;
+
+use crate::integrity::{Problem, Repairs};
+use crate::tasks::{Task, TaskList, TaskState};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+use ulid::Ulid;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TaskCounts {
+    pub active: usize,
+    pub total: usize,
+}
+
+/// One write in a batch given to `TaskRepository::apply`.
+#[derive(Debug, Clone)]
+pub enum Write {
+    /// Keeps the list's id and creation time.
+    InsertList(TaskList),
+    InsertTask(Task, i64),
+    /// Moves a task to a new position in its list.
+    SetPosition(Ulid, i64),
+    /// Along with the list's tasks.
+    DeleteList(Ulid),
+    /// A key and value for the `settings` table, replacing any value already there.
+    SetSetting(String, String),
+}
+
+/// Everything `TaskStack` reads and writes. Tasks come back with their position.
+#[async_trait]
+pub trait TaskRepository: Send + Sync {
+    async fn insert_task(&self, task: &Task, position: i64) -> Result<(), String>;
+
+    /// Adds the task after the last in its list, working out the position in
+    /// the same write so tasks pushed at once can't share one.
+    async fn push_task(&self, task: &Task) -> Result<(), String>;
+
+    /// Completed tasks newest first, then active tasks in stack order.
+    async fn get_all_tasks(&self, list_id: &Ulid) -> Result<Vec<(Task, i64)>, String>;
+
+    /// Active tasks plus those completed within the last `completed_window_hours`.
+    async fn get_current_tasks(
+        &self,
+        list_id: &Ulid,
+        completed_window_hours: u32,
+    ) -> Result<Vec<(Task, i64)>, String>;
+
+    async fn get_active_tasks(&self, list_id: &Ulid) -> Result<Vec<(Task, i64)>, String>;
+
+    async fn get_first_active_task(&self, list_id: &Ulid) -> Result<Option<Task>, String>;
+
+    async fn update_task_state(
+        &self,
+        id: &Ulid,
+        state: TaskState,
+        completed_at: Option<DateTime<Utc>>,
+    ) -> Result<(), String>;
+
+    async fn update_task_position(&self, id: &Ulid, position: i64) -> Result<(), String>;
+
+    /// Moves the task after the last in its list, in a single write like `push_task`.
+    async fn move_task_to_end(&self, id: &Ulid) -> Result<(), String>;
+
+    async fn delete_task(&self, id: &Ulid) -> Result<(), String>;
+
+    /// Oldest first.
+    async fn get_lists(&self) -> Result<Vec<TaskList>, String>;
+
+    async fn create_list(&self, name: &str) -> Result<Ulid, String>;
+
+    /// Adds a list keeping its id and creation time.
+    async fn insert_list(&self, list: &TaskList) -> Result<(), String>;
+
+    /// Deletes the list along with its tasks.
+    async fn delete_list(&self, id: &Ulid) -> Result<(), String>;
+
+    /// Makes every write in order, or none of them if one fails.
+    async fn apply(&self, writes: Vec<Write>) -> Result<(), String>;
+
+    /// 0 when the list has no tasks.
+    async fn get_highest_position(&self, list_id: &Ulid) -> Result<i64, String>;
+
+    /// Lists without tasks are left out.
+    async fn count_tasks(&self) -> Result<HashMap<Ulid, TaskCounts>, String>;
+
+    async fn get_setting(&self, key: &str) -> Result<Option<String>, String>;
+
+    async fn set_setting(&self, key: &str, value: &str) -> Result<(), String>;
+
+    /// The file backing this repository, if there is one to back up or encrypt.
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+
+    fn passphrase(&self) -> Option<String> {
+        None
+    }
+
+    async fn set_passphrase(&self, _passphrase: Option<&str>) -> Result<(), String> {
+        Err("This storage can't be encrypted".to_string())
+    }
+
+    /// Copies the stored data to `target` without letting writes land mid-copy.
+    async fn snapshot(&self, _target: &Path) -> Result<(), String> {
+        Err("This storage can't be backed up".to_string())
+    }
+
+    /// Lets go of the underlying file until `reopen`, so it can be replaced.
+    async fn close(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Picks up changes made to the underlying file, e.g. after a restore.
+    async fn reopen(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Changes when something other than this repository writes to the
+    /// storage, e.g. the CLI working on the same file.
+    async fn data_version(&self) -> Result<i64, String> {
+        Ok(0)
+    }
+
+    async fn check_integrity(&self) -> Result<Vec<Problem>, String> {
+        Ok(vec![])
+    }
+
+    async fn repair(&self) -> Result<Repairs, String> {
+        Ok(Repairs::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+
+    #[tokio::test]
+    async fn applies_all_writes_or_none() {
+        let dir = TempDir::new();
+        for (name, stack) in testing::stacks(&dir).await {
+            let repo = stack.get_repository();
+            let existing = stack.get_list_id();
+            let list = TaskList {
+                id: Ulid::new(),
+                name: "Imported".to_string(),
+                created_at: Utc::now(),
+            };
+
+            // The last write fails, as its list doesn't exist
+            let writes = vec![
+                Write::DeleteList(existing),
+                Write::InsertList(list.clone()),
+                Write::InsertTask(Task::new("Kept".to_string(), list.id), 1),
+                Write::SetSetting("imported".to_string(), "yes".to_string()),
+                Write::InsertTask(Task::new("Orphan".to_string(), Ulid::new()), 1),
+            ];
+            assert!(repo.apply(writes).await.is_err(), "{name}");
+            let lists = repo.get_lists().await.unwrap();
+            let ids: Vec<Ulid> = lists.iter().map(|list| list.id).collect();
+            assert_eq!(ids, [existing], "{name}");
+            assert_eq!(repo.get_setting("imported").await.unwrap(), None, "{name}");
+
+            let writes = vec![
+                Write::DeleteList(existing),
+                Write::InsertList(list.clone()),
+                Write::InsertTask(Task::new("Kept".to_string(), list.id), 1),
+                Write::SetSetting("imported".to_string(), "yes".to_string()),
+            ];
+            repo.apply(writes).await.unwrap();
+            let lists = repo.get_lists().await.unwrap();
+            let ids: Vec<Ulid> = lists.iter().map(|list| list.id).collect();
+            assert_eq!(ids, [list.id], "{name}");
+            assert_eq!(
+                repo.get_all_tasks(&list.id).await.unwrap().len(),
+                1,
+                "{name}"
+            );
+            assert_eq!(
+                repo.get_setting("imported").await.unwrap().as_deref(),
+                Some("yes"),
+                "{name}"
+            );
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::repository::{TaskRepository, Write};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use ulid::Ulid;
+
+/// A key in the `settings` table and the type its value is stored as.
+pub struct Key<T> {
+    name: &'static str,
+    value: PhantomData<T>,
+}
+
+impl<T> Key<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            value: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+pub const LAST_LIST: Key<Ulid> = Key::new("last_list");
+pub const LAST_VIEW: Key<View> = Key::new("last_view");
+pub const COMPLETED_WINDOW_HOURS: Key<u32> = Key::new("completed_window_hours");
+pub const STACK_DEPTH: Key<usize> = Key::new("stack_depth");
+pub const NOTIFICATION_DURATION_MS: Key<u32> = Key::new("notification_duration_ms");
+
+/// User preferences, each stored under its own key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// How long completed tasks still count as current.
+    pub completed_window_hours: u32,
+    /// How many cards are drawn behind the current task.
+    pub stack_depth: usize,
+    pub notification_duration_ms: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            completed_window_hours: 12,
+            stack_depth: 3,
+            notification_duration_ms: 3000,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.completed_window_hours > 24 * 30 {
+            return Err("Completed tasks can stay current for at most 30 days".to_string());
+        }
+        if self.stack_depth > 10 {
+            return Err("The stack can show at most 10 cards".to_string());
+        }
+        if !(500..=60_000).contains(&self.notification_duration_ms) {
+            return Err("Notifications must last between 0.5 and 60 seconds".to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn load(repo: &dyn TaskRepository) -> Result<Self, String> {
+        let defaults = Self::default();
+        Ok(Self {
+            completed_window_hours: get(repo, COMPLETED_WINDOW_HOURS)
+                .await?
+                .unwrap_or(defaults.completed_window_hours),
+            stack_depth: get(repo, STACK_DEPTH)
+                .await?
+                .unwrap_or(defaults.stack_depth),
+            notification_duration_ms: get(repo, NOTIFICATION_DURATION_MS)
+                .await?
+                .unwrap_or(defaults.notification_duration_ms),
+        })
+    }
+
+    /// Saves every setting or, if one write fails, none of them.
+    pub async fn save(&self, repo: &dyn TaskRepository) -> Result<(), String> {
+        repo.apply(vec![
+            write(COMPLETED_WINDOW_HOURS, &self.completed_window_hours),
+            write(STACK_DEPTH, &self.stack_depth),
+            write(NOTIFICATION_DURATION_MS, &self.notification_duration_ms),
+        ])
+        .await
+    }
+}
+
+/// The pages that are restored on launch.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum View {
+    #[default]
+    Index,
+    List,
+    Lists,
+}
+
+impl fmt::Display for View {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            View::Index => "index",
+            View::List => "list",
+            View::Lists => "lists",
+        })
+    }
+}
+
+impl FromStr for View {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "index" => Ok(View::Index),
+            "list" => Ok(View::List),
+            "lists" => Ok(View::Lists),
+            _ => Err(format!("Unknown view: {s}")),
+        }
+    }
+}
+
+/// Reads a setting, treating values that no longer parse as unset.
+pub async fn get<T: FromStr>(repo: &dyn TaskRepository, key: Key<T>) -> Result<Option<T>, String> {
+    Ok(repo
+        .get_setting(key.name())
+        .await?
+        .and_then(|value| value.parse().ok()))
+}
+
+pub async fn set<T: ToString>(
+    repo: &dyn TaskRepository,
+    key: Key<T>,
+    value: &T,
+) -> Result<(), String> {
+    repo.set_setting(key.name(), &value.to_string()).await
+}
+
+/// A setting to save in a batch given to `TaskRepository::apply`.
+fn write<T: ToString>(key: Key<T>, value: &T) -> Write {
+    Write::SetSetting(key.name().to_string(), value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::InMemoryRepository;
+
+    #[test]
+    fn accepts_values_up_to_each_limit() {
+        let valid = |change: fn(&mut Settings)| {
+            let mut settings = Settings::default();
+            change(&mut settings);
+            settings.validate()
+        };
+        assert_eq!(valid(|_| {}), Ok(()));
+        assert_eq!(valid(|s| s.completed_window_hours = 0), Ok(()));
+        assert_eq!(valid(|s| s.completed_window_hours = 24 * 30), Ok(()));
+        assert!(valid(|s| s.completed_window_hours = 24 * 30 + 1).is_err());
+        assert_eq!(valid(|s| s.stack_depth = 0), Ok(()));
+        assert_eq!(valid(|s| s.stack_depth = 10), Ok(()));
+        assert!(valid(|s| s.stack_depth = 11).is_err());
+        assert_eq!(valid(|s| s.notification_duration_ms = 500), Ok(()));
+        assert_eq!(valid(|s| s.notification_duration_ms = 60_000), Ok(()));
+        assert!(valid(|s| s.notification_duration_ms = 499).is_err());
+        assert!(valid(|s| s.notification_duration_ms = 60_001).is_err());
+    }
+
+    #[tokio::test]
+    async fn loads_what_was_saved_and_defaults_the_rest() {
+        let repo = InMemoryRepository::new();
+        assert_eq!(Settings::load(&repo).await.unwrap(), Settings::default());
+
+        let settings = Settings {
+            completed_window_hours: 48,
+            stack_depth: 5,
+            notification_duration_ms: 1500,
+        };
+        settings.save(&repo).await.unwrap();
+        assert_eq!(Settings::load(&repo).await.unwrap(), settings);
+
+        // Values that no longer parse are treated as unset
+        repo.set_setting(STACK_DEPTH.name(), "deep").await.unwrap();
+        let loaded = Settings::load(&repo).await.unwrap();
+        assert_eq!(loaded.stack_depth, Settings::default().stack_depth);
+        assert_eq!(loaded.completed_window_hours, 48);
+    }
+}
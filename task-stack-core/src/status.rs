@@ -0,0 +1,110 @@
+//! A quick, read-only look at the top of the stack for status bars and shell
+//! prompts. It skips the migrations opening a `LibsqlRepository` runs, so
+//! it's safe alongside the app and fast enough to poll.
+
+use crate::database::{self, Session};
+use crate::settings;
+use crate::tasks::Task;
+use serde::Serialize;
+use std::path::Path;
+use ulid::Ulid;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    /// Name of the list looked at.
+    pub list: String,
+    /// The first active task, if there is one.
+    pub task: Option<Task>,
+    pub active: usize,
+    pub total: usize,
+}
+
+/// Reads the status of `list`, by id or name ignoring case, or of the list
+/// the app last had open.
+pub async fn read(
+    db_path: &Path,
+    passphrase: Option<&str>,
+    list: Option<&str>,
+) -> Result<Status, String> {
+    let db = database::open_read_only(db_path, passphrase)
+        .await
+        .map_err(|e| e.to_string())?;
+    let session = Session::open(db).await.map_err(|e| e.to_string())?;
+
+    let lists = database::get_lists(&session)
+        .await
+        .map_err(|e| e.to_string())?;
+    let list = match list {
+        Some(list) => lists
+            .into_iter()
+            .find(|l| l.id.to_string() == list || l.name.eq_ignore_ascii_case(list))
+            .ok_or_else(|| format!("No list named {list}"))?,
+        None => {
+            let last = database::get_setting(&session, settings::LAST_LIST.name())
+                .await
+                .map_err(|e| e.to_string())?
+                .and_then(|id| Ulid::from_string(&id).ok());
+            let first = lists.first().cloned();
+            lists
+                .into_iter()
+                .find(|l| Some(l.id) == last)
+                .or(first)
+                .ok_or_else(|| "There are no lists".to_string())?
+        }
+    };
+
+    let task = database::get_first_active_task(&session, &list.id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let counts = database::count_tasks(&session)
+        .await
+        .map_err(|e| e.to_string())?
+        .remove(&list.id)
+        .unwrap_or_default();
+    Ok(Status {
+        list: list.name,
+        task,
+        active: counts.active,
+        total: counts.total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{disk_stack, TempDir};
+    use libsql::params;
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn reads_past_a_writer_holding_the_lock() {
+        let dir = TempDir::new();
+        let path = dir.join("tasks.db");
+        let stack = disk_stack(&path).await;
+        stack.push("Write".to_string(), None).await.unwrap();
+
+        // Another process partway through a write holds the WAL write lock
+        let db = database::init_database(&path, None).await.unwrap();
+        let writer = database::connect(&db).await.unwrap();
+        writer.execute("BEGIN IMMEDIATE", params![]).await.unwrap();
+        writer
+            .execute(
+                "UPDATE tasks SET title = 'Rewrite', state = 'Completed'",
+                params![],
+            )
+            .await
+            .unwrap();
+
+        // Sees what was committed without waiting out the busy timeout
+        let start = Instant::now();
+        let status = read(&path, None, None).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(status.task.unwrap().title, "Write");
+        assert_eq!((status.active, status.total), (1, 1));
+
+        writer.execute("COMMIT", params![]).await.unwrap();
+        let status = read(&path, None, None).await.unwrap();
+        assert!(status.task.is_none());
+        assert_eq!((status.active, status.total), (0, 1));
+    }
+}
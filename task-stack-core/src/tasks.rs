@@ -0,0 +1,768 @@
+use crate::database;
+use crate::formats;
+use crate::integrity;
+use crate::repository::{ClosedRepository, TaskCounts, TaskRepository, Write};
+use crate::settings::{self, Settings, View};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast;
+use ulid::Ulid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum TaskState {
+    Active,
+    Completed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Task {
+    pub id: Ulid,
+    pub list_id: Ulid,
+    pub title: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub state: TaskState,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl Task {
+    pub fn new(title: String, list_id: Ulid) -> Self {
+        Self {
+            id: Ulid::new(),
+            list_id,
+            title,
+            description: None,
+            created_at: Utc::now(),
+            state: TaskState::Active,
+            completed_at: None,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn created_at(&self) -> &DateTime<Utc> {
+        &self.created_at
+    }
+
+    pub fn completed(&self) -> bool {
+        self.state == TaskState::Completed
+    }
+
+    pub fn completed_at(&self) -> Option<&DateTime<Utc>> {
+        self.completed_at.as_ref()
+    }
+
+    pub fn mark_completed(&mut self) {
+        self.state = TaskState::Completed;
+        self.completed_at = Some(Utc::now());
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskList {
+    pub id: Ulid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a `TaskStack` changed, sent to its subscribers so views of the same
+/// data can refresh.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    /// Tasks were added, completed or moved within a list.
+    Tasks {
+        list_id: Ulid,
+    },
+    /// Lists were created or deleted, or another list became the current one.
+    Lists,
+    Settings,
+    /// Anything may have changed, e.g. after an import, a restore or a write
+    /// from another process.
+    Database,
+}
+
+pub struct TaskStack {
+    repo: RwLock<Arc<dyn TaskRepository>>,
+    list_id: Mutex<Ulid>,
+    settings: RwLock<Settings>,
+    changes: broadcast::Sender<Change>,
+}
+
+impl TaskStack {
+    pub fn new(repo: Arc<dyn TaskRepository>, list_id: Ulid, settings: Settings) -> Self {
+        Self {
+            repo: RwLock::new(repo),
+            list_id: Mutex::new(list_id),
+            settings: RwLock::new(settings),
+            changes: broadcast::channel(64).0,
+        }
+    }
+
+    /// Changes made through this stack, or through stacks made from it with
+    /// `for_list`, from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Change> {
+        self.changes.subscribe()
+    }
+
+    /// Tells subscribers about `change`; there may be none.
+    pub fn notify(&self, change: Change) {
+        let _ = self.changes.send(change);
+    }
+
+    fn tasks_changed(&self) {
+        self.notify(Change::Tasks {
+            list_id: self.get_list_id(),
+        });
+    }
+
+    /// Opens a stack on the last selected list, falling back to the first one
+    /// and creating a list if there are none. Nothing is checked or repaired;
+    /// see `check_integrity`.
+    pub async fn load(repo: Arc<dyn TaskRepository>) -> Result<Self, String> {
+        let list_id = Self::initial_list(repo.as_ref()).await?;
+        let settings = Settings::load(repo.as_ref()).await?;
+        Ok(Self::new(repo, list_id, settings))
+    }
+
+    async fn initial_list(repo: &dyn TaskRepository) -> Result<Ulid, String> {
+        let lists = repo.get_lists().await?;
+        let last_list = settings::get(repo, settings::LAST_LIST).await?;
+        match last_list
+            .filter(|id| lists.iter().any(|list| list.id == *id))
+            .or_else(|| lists.first().map(|list| list.id))
+        {
+            Some(id) => Ok(id),
+            None => repo.create_list("Initial List").await,
+        }
+    }
+
+    /// Whether the current list survived something that can remove lists.
+    async fn has_current_list(&self) -> Result<bool, String> {
+        let lists = self.repo().get_lists().await?;
+        let list_id = self.get_list_id();
+        Ok(lists.iter().any(|list| list.id == list_id))
+    }
+
+    pub fn get_list_id(&self) -> Ulid {
+        *self.list_id.lock().unwrap()
+    }
+
+    pub async fn set_list_id(&self, list_id: Ulid) -> Result<(), String> {
+        *self.list_id.lock().unwrap() = list_id;
+        settings::set(self.repo().as_ref(), settings::LAST_LIST, &list_id).await?;
+        self.notify(Change::Lists);
+        Ok(())
+    }
+
+    /// The view shown on launch, defaulting to the single task view.
+    pub async fn get_last_view(&self) -> View {
+        settings::get(self.repo().as_ref(), settings::LAST_VIEW)
+            .await
+            .inspect_err(|e| eprintln!("Failed to read last view: {}", e))
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    pub fn get_settings(&self) -> Settings {
+        self.settings.read().unwrap().clone()
+    }
+
+    pub async fn update_settings(&self, settings: Settings) -> Result<(), String> {
+        settings.validate()?;
+        settings.save(self.repo().as_ref()).await?;
+        *self.settings.write().unwrap() = settings;
+        self.notify(Change::Settings);
+        Ok(())
+    }
+
+    pub async fn set_last_view(&self, view: View) -> Result<(), String> {
+        settings::set(self.repo().as_ref(), settings::LAST_VIEW, &view).await
+    }
+
+    fn repo(&self) -> Arc<dyn TaskRepository> {
+        self.repo.read().unwrap().clone()
+    }
+
+    pub fn get_repository(&self) -> Arc<dyn TaskRepository> {
+        self.repo()
+    }
+
+    /// Takes over another stack's storage, list and settings, e.g. when
+    /// switching profiles.
+    pub fn replace(&self, other: TaskStack) {
+        let TaskStack {
+            repo,
+            list_id,
+            settings,
+            ..
+        } = other;
+        *self.repo.write().unwrap() = repo.into_inner().unwrap();
+        *self.list_id.lock().unwrap() = list_id.into_inner().unwrap();
+        *self.settings.write().unwrap() = settings.into_inner().unwrap();
+        self.notify(Change::Database);
+    }
+
+    /// Lets go of the storage when another database was chosen but couldn't be
+    /// opened, so nothing keeps using the last one until `replace`.
+    pub fn close(&self) {
+        *self.repo.write().unwrap() = Arc::new(ClosedRepository);
+        self.notify(Change::Database);
+    }
+
+    /// The database file, or `None` when the stack isn't stored on disk.
+    pub fn get_db_path(&self) -> Option<PathBuf> {
+        self.repo().path().map(Path::to_path_buf)
+    }
+
+    pub fn get_passphrase(&self) -> Option<String> {
+        self.repo().passphrase()
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.get_db_path()
+            .is_some_and(|path| database::is_encrypted(&path))
+    }
+
+    /// Encrypts the database with `passphrase`, or removes encryption when `None`.
+    pub async fn set_passphrase(&self, passphrase: Option<&str>) -> Result<(), String> {
+        self.repo().set_passphrase(passphrase).await
+    }
+
+    /// Re-opens the database file after it was replaced on disk, moving to the
+    /// first list if the current one no longer exists.
+    pub async fn reopen(&self) -> Result<(), String> {
+        self.repo().reopen().await?;
+        let list_id = if self.has_current_list().await? {
+            self.get_list_id()
+        } else {
+            Self::initial_list(self.repo().as_ref()).await?
+        };
+        let settings = Settings::load(self.repo().as_ref()).await?;
+        *self.settings.write().unwrap() = settings;
+        self.set_list_id(list_id).await?;
+        self.notify(Change::Database);
+        Ok(())
+    }
+
+    pub async fn check_integrity(&self) -> Result<Vec<integrity::Problem>, String> {
+        self.repo().check_integrity().await
+    }
+
+    pub async fn repair(&self) -> Result<integrity::Repairs, String> {
+        let repairs = self.repo().repair().await?;
+        // The current list may have been quarantined
+        if !self.has_current_list().await? {
+            self.set_list_id(Self::initial_list(self.repo().as_ref()).await?)
+                .await?;
+        }
+        self.notify(Change::Database);
+        Ok(repairs)
+    }
+
+    pub async fn push(&self, title: String, description: Option<String>) -> Result<Task, String> {
+        let task = Task {
+            id: Ulid::new(),
+            list_id: self.get_list_id(),
+            title,
+            description,
+            created_at: Utc::now(),
+            state: TaskState::Active,
+            completed_at: None,
+        };
+
+        self.repo().push_task(&task).await?;
+        self.tasks_changed();
+        Ok(task)
+    }
+
+    pub async fn pop(&self) -> Result<Option<Task>, String> {
+        let tasks = self.repo().get_all_tasks(&self.get_list_id()).await?;
+
+        if let Some((task, _)) = tasks.last() {
+            self.repo().delete_task(&task.id).await?;
+            self.tasks_changed();
+            Ok(Some(task.clone()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn first(&self) -> Result<Option<Task>, String> {
+        let tasks = self.repo().get_all_tasks(&self.get_list_id()).await?;
+        Ok(tasks.first().map(|(task, _)| task.clone()))
+    }
+
+    pub async fn first_active(&self) -> Result<Option<Task>, String> {
+        self.repo().get_first_active_task(&self.get_list_id()).await
+    }
+
+    pub async fn size(&self) -> Result<usize, String> {
+        let tasks = self.repo().get_all_tasks(&self.get_list_id()).await?;
+        Ok(tasks.len())
+    }
+
+    pub async fn find_task_position(&self, task: &Task) -> Result<usize, String> {
+        let tasks = self.repo().get_all_tasks(&self.get_list_id()).await?;
+        Ok(tasks
+            .iter()
+            .position(|(t, _)| t.id == task.id)
+            .unwrap_or(tasks.len()))
+    }
+
+    pub async fn complete_task(&self, id: Ulid) -> Result<Task, String> {
+        let tasks = self.repo().get_all_tasks(&self.get_list_id()).await?;
+
+        let task = tasks
+            .iter()
+            .find(|(t, _)| t.id == id)
+            .ok_or_else(|| "Task not found".to_string())?
+            .0
+            .clone();
+
+        let mut updated_task = task.clone();
+        updated_task.mark_completed();
+
+        self.repo()
+            .update_task_state(&id, TaskState::Completed, updated_task.completed_at)
+            .await?;
+        self.tasks_changed();
+
+        Ok(updated_task)
+    }
+
+    pub async fn move_to_end(&self, id: Ulid) -> Result<(), String> {
+        // Positions are per list, so a task from another list can't be moved here
+        self.find_task(&id).await?;
+        self.repo().move_task_to_end(&id).await?;
+        self.tasks_changed();
+        Ok(())
+    }
+
+    /// Puts the list's tasks in the order of `ids`, followed by any it leaves
+    /// out in their current order, renumbering them all in one transaction.
+    pub async fn reorder(&self, ids: &[Ulid]) -> Result<(), String> {
+        let mut tasks = self.repo().get_all_tasks(&self.get_list_id()).await?;
+        tasks.sort_by_key(|(_, position)| *position);
+        if let Some(id) = ids
+            .iter()
+            .find(|id| !tasks.iter().any(|(task, _)| task.id == **id))
+        {
+            return Err(format!("Task {id} is not in this list"));
+        }
+
+        let rest = tasks
+            .iter()
+            .map(|(task, _)| task.id)
+            .filter(|id| !ids.contains(id));
+        let writes = ids
+            .iter()
+            .copied()
+            .chain(rest)
+            .enumerate()
+            .map(|(position, id)| Write::SetPosition(id, position as i64 + 1))
+            .collect();
+        self.repo().apply(writes).await?;
+        self.tasks_changed();
+        Ok(())
+    }
+
+    /// Moves a task to `index` in the list's order, counting from the top.
+    pub async fn move_task(&self, id: Ulid, index: usize) -> Result<(), String> {
+        let mut tasks = self.repo().get_all_tasks(&self.get_list_id()).await?;
+        tasks.sort_by_key(|(_, position)| *position);
+        let mut ids: Vec<Ulid> = tasks.into_iter().map(|(task, _)| task.id).collect();
+        let from = ids
+            .iter()
+            .position(|task| *task == id)
+            .ok_or_else(|| "Task not found".to_string())?;
+        ids.remove(from);
+        ids.insert(index.min(ids.len()), id);
+        self.reorder(&ids).await
+    }
+
+    pub async fn get_tasks(&self) -> Result<Vec<Task>, String> {
+        let tasks = self.repo().get_all_tasks(&self.get_list_id()).await?;
+        Ok(tasks.into_iter().map(|(task, _)| task).collect())
+    }
+
+    pub async fn get_current_tasks(&self) -> Result<Vec<Task>, String> {
+        let tasks = self
+            .repo()
+            .get_current_tasks(
+                &self.get_list_id(),
+                self.get_settings().completed_window_hours,
+            )
+            .await?;
+        Ok(tasks.into_iter().map(|(task, _)| task).collect())
+    }
+
+    /// The task in this list, or `None` so callers can tell a missing task
+    /// from storage failing.
+    pub async fn lookup_task(&self, id: &Ulid) -> Result<Option<Task>, String> {
+        let tasks = self.repo().get_all_tasks(&self.get_list_id()).await?;
+        Ok(tasks
+            .into_iter()
+            .find(|(task, _)| task.id == *id)
+            .map(|(task, _)| task))
+    }
+
+    pub async fn find_task(&self, id: &Ulid) -> Result<Task, String> {
+        self.lookup_task(id)
+            .await?
+            .ok_or_else(|| "Task not found".to_string())
+    }
+
+    /// A task in any list, not just the current one.
+    pub async fn locate_task(&self, id: &Ulid) -> Result<Task, String> {
+        for list in self.repo().get_lists().await? {
+            let tasks = self.repo().get_all_tasks(&list.id).await?;
+            if let Some((task, _)) = tasks.into_iter().find(|(task, _)| task.id == *id) {
+                return Ok(task);
+            }
+        }
+        Err("Task not found".to_string())
+    }
+
+    /// A list by id, or by name ignoring case, or `None` when there's no such list.
+    pub async fn lookup_list(&self, list: &str) -> Result<Option<Ulid>, String> {
+        Ok(self
+            .repo()
+            .get_lists()
+            .await?
+            .into_iter()
+            .find(|l| l.id.to_string() == list || l.name.eq_ignore_ascii_case(list))
+            .map(|l| l.id))
+    }
+
+    pub async fn find_list(&self, list: &str) -> Result<Ulid, String> {
+        self.lookup_list(list)
+            .await?
+            .ok_or_else(|| format!("No list named {list}"))
+    }
+
+    /// A stack on another list, sharing this one's storage and settings, for
+    /// working on that list without switching to it.
+    pub fn for_list(&self, list_id: Ulid) -> TaskStack {
+        TaskStack {
+            changes: self.changes.clone(),
+            ..TaskStack::new(self.get_repository(), list_id, self.get_settings())
+        }
+    }
+
+    /// Newest first.
+    pub async fn get_lists(&self) -> Result<Vec<TaskList>, String> {
+        let mut lists = self.repo().get_lists().await?;
+        lists.reverse();
+        Ok(lists)
+    }
+
+    /// Whether something other than this stack wrote to its storage since
+    /// the version last seen, which is updated in `seen`.
+    pub async fn changed_elsewhere(&self, seen: &mut Option<i64>) -> Result<bool, String> {
+        let version = self.repo().data_version().await?;
+        Ok(seen.replace(version).is_some_and(|last| last != version))
+    }
+
+    /// Active and total task counts per list.
+    pub async fn count_tasks(&self) -> Result<HashMap<Ulid, TaskCounts>, String> {
+        self.repo().count_tasks().await
+    }
+
+    /// Deletes a list along with its tasks. The last list can't be deleted.
+    pub async fn delete_list(&self, id: Ulid) -> Result<(), String> {
+        let lists = self.repo().get_lists().await?;
+        if !lists.iter().any(|list| list.id == id) {
+            return Err("List not found".to_string());
+        }
+        if lists.len() <= 1 {
+            return Err("Can't delete the only list".to_string());
+        }
+        self.repo().delete_list(&id).await?;
+        if self.get_list_id() == id {
+            self.set_list_id(Self::initial_list(self.repo().as_ref()).await?)
+                .await?;
+        }
+        self.notify(Change::Lists);
+        Ok(())
+    }
+
+    pub async fn create_new_list(&self, name: &str) -> Result<Ulid, String> {
+        let id = self.repo().create_list(name).await?;
+        self.notify(Change::Lists);
+        Ok(id)
+    }
+
+    /// The list's tasks in stack order, ready to be written out.
+    pub async fn export_list(&self, list_id: Ulid) -> Result<formats::ListRecord, String> {
+        let list = self
+            .repo()
+            .get_lists()
+            .await?
+            .into_iter()
+            .find(|list| list.id == list_id)
+            .ok_or_else(|| "List not found".to_string())?;
+        formats::list_record(self.repo().as_ref(), list).await
+    }
+
+    pub async fn import_todotxt(
+        &self,
+        entries: Vec<formats::Entry>,
+        destination: formats::Destination,
+    ) -> Result<usize, String> {
+        let added = formats::todotxt::import(self.repo().as_ref(), entries, destination).await?;
+        self.notify(Change::Database);
+        Ok(added)
+    }
+
+    /// Adds entries to the list with the name they give, creating it if
+    /// there's none, and the rest to `destination`.
+    pub async fn import_entries(
+        &self,
+        entries: Vec<formats::Entry>,
+        destination: formats::Destination,
+    ) -> Result<usize, String> {
+        let added =
+            formats::append_entries(self.repo().as_ref(), entries, destination, |list, name| {
+                list.name == name
+            })
+            .await?;
+        self.notify(Change::Database);
+        Ok(added)
+    }
+
+    /// What `import_entries` would do, without writing anything.
+    pub async fn preview_entries(
+        &self,
+        entries: &[formats::Entry],
+        destination: &formats::Destination,
+    ) -> Result<formats::Preview, String> {
+        formats::preview(self.repo().as_ref(), entries, destination).await
+    }
+
+    pub async fn export(&self) -> Result<formats::Document, String> {
+        formats::collect(self.repo().as_ref()).await
+    }
+
+    /// Imports `document`, moving to the first list if the current one was
+    /// replaced by a restore.
+    pub async fn import(
+        &self,
+        document: formats::Document,
+        mode: formats::ImportMode,
+    ) -> Result<formats::ImportReport, String> {
+        let report = formats::import(self.repo().as_ref(), document, mode).await?;
+        if !self.has_current_list().await? {
+            self.set_list_id(Self::initial_list(self.repo().as_ref()).await?)
+                .await?;
+        }
+        self.notify(Change::Database);
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+
+    async fn push_all(stack: &TaskStack, titles: &[&str]) -> Vec<Ulid> {
+        let mut ids = Vec::new();
+        for title in titles {
+            ids.push(stack.push(title.to_string(), None).await.unwrap().id);
+        }
+        ids
+    }
+
+    async fn current(stack: &TaskStack) -> Vec<String> {
+        let tasks = stack.get_current_tasks().await.unwrap();
+        tasks.into_iter().map(|task| task.title).collect()
+    }
+
+    #[tokio::test]
+    async fn pushes_and_completes_in_order() {
+        let dir = TempDir::new();
+        for (name, stack) in testing::stacks(&dir).await {
+            let ids = push_all(&stack, &["First", "Second"]).await;
+            assert_eq!(current(&stack).await, ["First", "Second"], "{name}");
+            assert_eq!(stack.size().await.unwrap(), 2, "{name}");
+
+            let completed = stack.complete_task(ids[0]).await.unwrap();
+            assert!(completed.completed(), "{name}");
+            assert!(
+                stack.find_task(&ids[0]).await.unwrap().completed(),
+                "{name}"
+            );
+            let first = stack.first_active().await.unwrap().unwrap();
+            assert_eq!(first.title, "Second", "{name}");
+            assert!(stack.complete_task(Ulid::new()).await.is_err(), "{name}");
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn pushes_at_once_without_sharing_positions() {
+        let dir = TempDir::new();
+        for (name, stack) in testing::stacks(&dir).await {
+            let first = push_all(&stack, &["First"]).await[0];
+            let list_id = stack.get_list_id();
+            let mut pushes = Vec::new();
+            for i in 0..20 {
+                let stack = stack.for_list(list_id);
+                pushes.push(tokio::spawn(async move {
+                    stack.push(format!("Task {i}"), None).await.unwrap();
+                    if i % 5 == 0 {
+                        stack.move_to_end(first).await.unwrap();
+                    }
+                }));
+            }
+            for push in pushes {
+                push.await.unwrap();
+            }
+
+            let tasks = stack
+                .get_repository()
+                .get_all_tasks(&list_id)
+                .await
+                .unwrap();
+            let mut positions: Vec<i64> = tasks.iter().map(|(_, position)| *position).collect();
+            positions.sort();
+            positions.dedup();
+            assert_eq!(positions.len(), 21, "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn restores_the_last_list_and_view() {
+        let dir = TempDir::new();
+        let path = dir.join("tasks.db");
+        let stack = testing::disk_stack(&path).await;
+        let first = stack.get_list_id();
+        assert_eq!(stack.get_last_view().await, View::Index);
+        let errands = stack.create_new_list("Errands").await.unwrap();
+        stack.set_list_id(errands).await.unwrap();
+        stack.set_last_view(View::Lists).await.unwrap();
+        drop(stack);
+
+        let stack = testing::disk_stack(&path).await;
+        assert_eq!(stack.get_list_id(), errands);
+        assert_eq!(stack.get_last_view().await, View::Lists);
+
+        // Deleted elsewhere, e.g. by the CLI, so the oldest list is opened instead
+        let repo = stack.get_repository();
+        repo.delete_list(&errands).await.unwrap();
+        // A view that doesn't parse opens the single task view
+        repo.set_setting(settings::LAST_VIEW.name(), "gone")
+            .await
+            .unwrap();
+        drop((stack, repo));
+        let stack = testing::disk_stack(&path).await;
+        assert_eq!(stack.get_list_id(), first);
+        assert_eq!(stack.get_last_view().await, View::Index);
+    }
+
+    #[tokio::test]
+    async fn keeps_invalid_settings_out() {
+        let dir = TempDir::new();
+        for (name, stack) in testing::stacks(&dir).await {
+            let invalid = Settings {
+                stack_depth: 11,
+                ..Settings::default()
+            };
+            assert!(stack.update_settings(invalid).await.is_err(), "{name}");
+            assert_eq!(stack.get_settings(), Settings::default(), "{name}");
+            let loaded = Settings::load(stack.get_repository().as_ref()).await;
+            assert_eq!(loaded.unwrap(), Settings::default(), "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_everything_once_closed_until_replaced() {
+        let dir = TempDir::new();
+        let path = dir.join("tasks.db");
+        let stack = testing::disk_stack(&path).await;
+        push_all(&stack, &["Kept"]).await;
+        let mut changes = stack.subscribe();
+
+        stack.close();
+        assert_eq!(changes.try_recv(), Ok(Change::Database));
+        assert!(stack.push("Lost".to_string(), None).await.is_err());
+        assert!(stack.get_current_tasks().await.is_err());
+        assert_eq!(stack.get_db_path(), None);
+
+        stack.replace(testing::disk_stack(&path).await);
+        assert_eq!(current(&stack).await, ["Kept"]);
+    }
+
+    #[tokio::test]
+    async fn moves_tasks_within_the_list() {
+        let dir = TempDir::new();
+        for (name, stack) in testing::stacks(&dir).await {
+            let ids = push_all(&stack, &["A", "B", "C"]).await;
+
+            stack.move_to_end(ids[0]).await.unwrap();
+            assert_eq!(current(&stack).await, ["B", "C", "A"], "{name}");
+
+            // Ids left out keep their order after the ones given
+            stack.reorder(&[ids[2], ids[0]]).await.unwrap();
+            assert_eq!(current(&stack).await, ["C", "A", "B"], "{name}");
+            assert!(stack.reorder(&[Ulid::new()]).await.is_err(), "{name}");
+
+            stack.move_task(ids[1], 0).await.unwrap();
+            assert_eq!(current(&stack).await, ["B", "C", "A"], "{name}");
+
+            // Tasks in other lists are left alone
+            let other = stack.for_list(stack.create_new_list("Other").await.unwrap());
+            let elsewhere = push_all(&other, &["Elsewhere"]).await[0];
+            assert!(stack.move_to_end(elsewhere).await.is_err(), "{name}");
+            assert_eq!(current(&stack).await, ["B", "C", "A"], "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn creates_finds_and_deletes_lists() {
+        let dir = TempDir::new();
+        for (name, stack) in testing::stacks(&dir).await {
+            let initial = stack.get_list_id();
+            let other = stack.create_new_list("Other").await.unwrap();
+            // Lists made in the same millisecond may come back in either order
+            let lists = stack.get_lists().await.unwrap();
+            let mut names: Vec<&str> = lists.iter().map(|list| list.name.as_str()).collect();
+            names.sort();
+            assert_eq!(names, ["Initial List", "Other"], "{name}");
+            assert_eq!(stack.find_list("other").await.unwrap(), other, "{name}");
+            assert_eq!(
+                stack.find_list(&other.to_string()).await.unwrap(),
+                other,
+                "{name}"
+            );
+            assert!(stack.find_list("Missing").await.is_err(), "{name}");
+
+            stack.set_list_id(other).await.unwrap();
+            push_all(&stack, &["Doomed"]).await;
+            assert_eq!(
+                stack.count_tasks().await.unwrap()[&other].total,
+                1,
+                "{name}"
+            );
+
+            // Deleting the current list takes its tasks and moves to another
+            stack.delete_list(other).await.unwrap();
+            assert_eq!(stack.get_list_id(), initial, "{name}");
+            assert!(
+                !stack.count_tasks().await.unwrap().contains_key(&other),
+                "{name}"
+            );
+            assert!(stack.delete_list(initial).await.is_err(), "{name}");
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Helpers shared by the tests.
+
+use crate::repository::{InMemoryRepository, LibsqlRepository};
+use crate::tasks::TaskStack;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use ulid::Ulid;
+
+/// A fresh directory under the system temp directory, removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("task-stack-test-{}", Ulid::new()));
+        std::fs::create_dir_all(&path).expect("Temp dir to be created");
+        Self(path)
+    }
+
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A stack on the database at `path`, creating it if needed.
+pub async fn disk_stack(path: &Path) -> TaskStack {
+    let repo = LibsqlRepository::open(path.to_path_buf(), None)
+        .await
+        .expect("Database to open");
+    TaskStack::load(Arc::new(repo))
+        .await
+        .expect("Stack to load")
+}
+
+/// The same empty stack in memory and on disk under `dir`, for tests that
+/// should behave alike on both.
+pub async fn stacks(dir: &TempDir) -> [(&'static str, TaskStack); 2] {
+    let memory = TaskStack::load(Arc::new(InMemoryRepository::new()))
+        .await
+        .expect("Stack to load");
+    [
+        ("in-memory", memory),
+        ("libsql", disk_stack(&dir.join("tasks.db")).await),
+    ]
+}